@@ -6,8 +6,45 @@ pub enum EphemeralSplError {
     InvalidInstruction,
     // account already initialized / in use
     AlreadyInUse,
-    // Ephemeral ATA, Vault, Mint or Owner mismatch
+    // Ephemeral ATA, Vault, Mint or Owner mismatch; superseded by the specific
+    // mismatch variants below and kept so the error codes stay stable
     EphemeralAtaMismatch,
+    // token program is neither SPL Token nor Token-2022
+    InvalidTokenProgram,
+    // mint has the Token-2022 non-transferable extension
+    NonTransferableMint,
+    // mint has the Token-2022 permanent delegate extension
+    PermanentDelegateMint,
+    // mint has another Token-2022 extension the vault cannot support
+    UnsupportedMintExtension,
+    // account layout version is not the current one; migrate it first
+    InvalidAccountVersion,
+    // Ephemeral ATA balance or delegated allowance is too low
+    InsufficientFunds,
+    // account belongs to another mint
+    MintMismatch,
+    // signer is not the owner of the account
+    OwnerMismatch,
+    // vault token account is not the ATA of the Global Vault
+    VaultMismatch,
+    // Ephemeral ATA is delegated to the ephemeral rollup; undelegate it first
+    AccountDelegated,
+    // permission program is not the ACL program
+    InvalidPermissionProgram,
+    // arithmetic overflow or underflow in balance accounting
+    Overflow,
+    // Ephemeral ATA still holds a balance
+    NonZeroBalance,
+    // signer is not the approved delegate of the Ephemeral ATA
+    InvalidDelegate,
+    // payout exceeds what the vault has recorded as deposited
+    ExceedsVaultDeposits,
+    // Ephemeral ATA did not leave the ephemeral rollup with `ExitRollup`, or its
+    // exit was already settled
+    ExitNotRequested,
+    // withdrawal intent has nothing left to pay out; it was never created or was
+    // already settled
+    NoWithdrawalIntent,
 }
 
 impl From<EphemeralSplError> for ProgramError {
@@ -24,6 +61,23 @@ impl ToStr for EphemeralSplError {
             EphemeralSplError::EphemeralAtaMismatch => {
                 "Error: Ephemeral ATA/Vault/Mint/Owner mismatch"
             }
+            EphemeralSplError::InvalidTokenProgram => "Error: Invalid token program",
+            EphemeralSplError::NonTransferableMint => "Error: Mint is non-transferable",
+            EphemeralSplError::PermanentDelegateMint => "Error: Mint has a permanent delegate",
+            EphemeralSplError::UnsupportedMintExtension => "Error: Unsupported mint extension",
+            EphemeralSplError::InvalidAccountVersion => "Error: Account version mismatch",
+            EphemeralSplError::InsufficientFunds => "Error: Insufficient funds",
+            EphemeralSplError::MintMismatch => "Error: Mint mismatch",
+            EphemeralSplError::OwnerMismatch => "Error: Owner mismatch",
+            EphemeralSplError::VaultMismatch => "Error: Vault token account mismatch",
+            EphemeralSplError::AccountDelegated => "Error: Account is delegated",
+            EphemeralSplError::InvalidPermissionProgram => "Error: Invalid permission program",
+            EphemeralSplError::Overflow => "Error: Arithmetic overflow",
+            EphemeralSplError::NonZeroBalance => "Error: Account has a non-zero balance",
+            EphemeralSplError::InvalidDelegate => "Error: Invalid delegate",
+            EphemeralSplError::ExceedsVaultDeposits => "Error: Payout exceeds vault deposits",
+            EphemeralSplError::ExitNotRequested => "Error: No pending exit",
+            EphemeralSplError::NoWithdrawalIntent => "Error: No pending withdrawal intent",
         }
     }
 }
@@ -35,6 +89,23 @@ impl core::convert::TryFrom<u32> for EphemeralSplError {
             0 => Ok(EphemeralSplError::InvalidInstruction),
             1 => Ok(EphemeralSplError::AlreadyInUse),
             2 => Ok(EphemeralSplError::EphemeralAtaMismatch),
+            3 => Ok(EphemeralSplError::InvalidTokenProgram),
+            4 => Ok(EphemeralSplError::NonTransferableMint),
+            5 => Ok(EphemeralSplError::PermanentDelegateMint),
+            6 => Ok(EphemeralSplError::UnsupportedMintExtension),
+            7 => Ok(EphemeralSplError::InvalidAccountVersion),
+            8 => Ok(EphemeralSplError::InsufficientFunds),
+            9 => Ok(EphemeralSplError::MintMismatch),
+            10 => Ok(EphemeralSplError::OwnerMismatch),
+            11 => Ok(EphemeralSplError::VaultMismatch),
+            12 => Ok(EphemeralSplError::AccountDelegated),
+            13 => Ok(EphemeralSplError::InvalidPermissionProgram),
+            14 => Ok(EphemeralSplError::Overflow),
+            15 => Ok(EphemeralSplError::NonZeroBalance),
+            16 => Ok(EphemeralSplError::InvalidDelegate),
+            17 => Ok(EphemeralSplError::ExceedsVaultDeposits),
+            18 => Ok(EphemeralSplError::ExitNotRequested),
+            19 => Ok(EphemeralSplError::NoWithdrawalIntent),
             _ => Err(ProgramError::InvalidArgument),
         }
     }
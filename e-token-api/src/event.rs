@@ -0,0 +1,132 @@
+use pinocchio::{error::ProgramError, Address};
+
+/// Kind of an [`EphemeralSplEvent`], the first byte of its encoding.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+#[cfg_attr(feature = "borsh", borsh(use_discriminant = true))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EventKind {
+    /// Tokens were deposited into the vault and credited to the Ephemeral ATA.
+    Deposit = 0,
+    /// Tokens were withdrawn from the vault and debited from the Ephemeral ATA.
+    Withdraw = 1,
+    /// The Ephemeral ATA was delegated to the ephemeral rollup.
+    Delegate = 2,
+    /// The Ephemeral ATA was committed and scheduled for undelegation.
+    Undelegate = 3,
+    /// The permission of the Ephemeral ATA was created.
+    PermissionCreate = 4,
+    /// The members of the Ephemeral ATA permission were reset.
+    PermissionReset = 5,
+    /// The permission of the Ephemeral ATA was delegated to the ephemeral rollup.
+    PermissionDelegate = 6,
+    /// The permission of the Ephemeral ATA was scheduled for undelegation.
+    PermissionUndelegate = 7,
+    /// Tokens were deposited into the vault while the Ephemeral ATA was delegated;
+    /// they are credited by a `Deposit` event once it is undelegated.
+    DepositQueued = 8,
+    /// A commit of the Ephemeral ATA to the base layer was scheduled; it stays delegated.
+    Commit = 9,
+    /// Tokens were debited from the Ephemeral ATA in the ephemeral rollup for a
+    /// withdrawal intent, paid out on the base layer by `SettleWithdrawalIntent`.
+    WithdrawalIntent = 10,
+    /// Tokens were moved out of the Ephemeral ATA to another Ephemeral ATA.
+    TransferOut = 11,
+    /// Tokens were moved into the Ephemeral ATA from another Ephemeral ATA.
+    TransferIn = 12,
+}
+
+impl TryFrom<u8> for EventKind {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(EventKind::Deposit),
+            1 => Ok(EventKind::Withdraw),
+            2 => Ok(EventKind::Delegate),
+            3 => Ok(EventKind::Undelegate),
+            4 => Ok(EventKind::PermissionCreate),
+            5 => Ok(EventKind::PermissionReset),
+            6 => Ok(EventKind::PermissionDelegate),
+            7 => Ok(EventKind::PermissionUndelegate),
+            8 => Ok(EventKind::DepositQueued),
+            9 => Ok(EventKind::Commit),
+            10 => Ok(EventKind::WithdrawalIntent),
+            11 => Ok(EventKind::TransferOut),
+            12 => Ok(EventKind::TransferIn),
+            _ => Err(ProgramError::InvalidArgument),
+        }
+    }
+}
+
+/// Event logged by the program through `sol_log_data`.
+///
+/// It is encoded as a single `Program data:` entry: the [`EventKind`] byte, the
+/// Ephemeral ATA, mint and owner addresses, then `amount` and `post_balance` as
+/// little-endian `u64`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EphemeralSplEvent {
+    /// What happened to the Ephemeral ATA.
+    pub kind: EventKind,
+    /// The Ephemeral ATA.
+    pub ephemeral_ata: Address,
+    /// The mint of the Ephemeral ATA.
+    pub mint: Address,
+    /// The owner of the Ephemeral ATA.
+    pub owner: Address,
+    /// The amount moved by a deposit or withdraw; zero for the other kinds.
+    pub amount: u64,
+    /// The balance of the Ephemeral ATA after the instruction. For a queued deposit,
+    /// the balance recorded on the base layer when the account was delegated.
+    pub post_balance: u64,
+}
+
+impl EphemeralSplEvent {
+    /// Length of the encoded event.
+    pub const LEN: usize = 1 + 3 * 32 + 2 * 8;
+
+    /// Encode the event.
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut data = [0u8; Self::LEN];
+        data[0] = self.kind as u8;
+        data[1..33].copy_from_slice(self.ephemeral_ata.as_ref());
+        data[33..65].copy_from_slice(self.mint.as_ref());
+        data[65..97].copy_from_slice(self.owner.as_ref());
+        data[97..105].copy_from_slice(&self.amount.to_le_bytes());
+        data[105..113].copy_from_slice(&self.post_balance.to_le_bytes());
+        data
+    }
+
+    /// Decode an event from the bytes of a `Program data:` log entry.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let data: &[u8; Self::LEN] = data.try_into().map_err(|_| ProgramError::InvalidArgument)?;
+        let address = |offset: usize| {
+            let mut bytes = [0u8; 32];
+            bytes.copy_from_slice(&data[offset..offset + 32]);
+            Address::new_from_array(bytes)
+        };
+        let u64_at = |offset: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&data[offset..offset + 8]);
+            u64::from_le_bytes(bytes)
+        };
+
+        Ok(Self {
+            kind: EventKind::try_from(data[0])?,
+            ephemeral_ata: address(1),
+            mint: address(33),
+            owner: address(65),
+            amount: u64_at(97),
+            post_balance: u64_at(105),
+        })
+    }
+}
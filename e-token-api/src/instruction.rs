@@ -0,0 +1,469 @@
+//! Instructions of the Ephemeral SPL program.
+//!
+//! The `u8` constants are the instruction discriminators; [`EphemeralSplInstruction`]
+//! is the typed form shared by the on-chain dispatcher and clients.
+
+use ephemeral_rollups_pinocchio::consts::EXTERNAL_UNDELEGATE_DISCRIMINATOR;
+use pinocchio::error::ProgramError;
+
+use crate::error::EphemeralSplError;
+
+/// 0 - InitializeEphemeralAta: initialize the ephemeral ATA account derived from [user, mint]
+pub const INITIALIZE_EPHEMERAL_ATA: u8 = 0;
+/// 1 - InitializeGlobalVault: initialize the global vault account derived from [mint]
+pub const INITIALIZE_GLOBAL_VAULT: u8 = 1;
+/// 2 - DepositSplTokens: transfer tokens to global vault and increase EphemeralAta amount
+///     Instruction data:
+///     [0..8] amount (LE u64)
+pub const DEPOSIT_SPL_TOKENS: u8 = 2;
+/// 3 - WithdrawSplTokens: transfer tokens from global vault back to user and decrease EphemeralAta amount
+///     Instruction data:
+///     [0..8] amount (LE u64)
+pub const WITHDRAW_SPL_TOKENS: u8 = 3;
+/// 4 - DelegateEphemeralAta: delegate the ephemeral ATA to a DLP program using PDA seeds
+///     Instruction data (each part optional; defaults of `DelegateConfig` otherwise):
+///     [0..4]  commit frequency in milliseconds (LE u32), present in the 4 and 36-byte forms
+///     [..+32] validator
+///     The legacy [bump] and [bump, validator] forms are still accepted.
+pub const DELEGATE_EPHEMERAL_ATA: u8 = 4;
+/// 5 - UndelegateEphemeralAta: commit state and undelegate an ephemeral ATA via the delegation program
+///     Accounts: [payer, ephemeral_ata, magic_context, magic_program]. The legacy layout with the
+///     user token account at index 1 is still accepted; that account is ignored.
+pub const UNDELEGATE_EPHEMERAL_ATA: u8 = 5;
+/// 6 - CreateEphemeralAtaPermission: create a permission account for the ephemeral ATA
+///     Instruction data:
+///     [0] MemberFlags bitfield encoded via MemberFlags::to_acl_flag_byte.
+pub const CREATE_EPHEMERAL_ATA_PERMISSION: u8 = 6;
+/// 7 - DelegateEphemeralAtaPermission: delegate the permission PDA for an ephemeral ATA
+pub const DELEGATE_EPHEMERAL_ATA_PERMISSION: u8 = 7;
+/// 8 - UndelegateEphemeralAtaPermission: commit and undelegate the permission PDA
+pub const UNDELEGATE_EPHEMERAL_ATA_PERMISSION: u8 = 8;
+/// 9 - ResetEphemeralAtaPermission: reset permission members to creation-time defaults
+///     Instruction data:
+///     [0] MemberFlags bitfield encoded via MemberFlags::to_acl_flag_byte.
+pub const RESET_EPHEMERAL_ATA_PERMISSION: u8 = 9;
+/// 10 - TransferEphemeral: move balance between two ephemeral ATAs of the same mint
+///     Instruction data:
+///     [0..8] amount (LE u64)
+pub const TRANSFER_EPHEMERAL: u8 = 10;
+/// 11 - ApproveEphemeral: allow a delegate to transfer up to `amount` from an ephemeral ATA
+///     Instruction data:
+///     [0..8] amount (LE u64)
+pub const APPROVE_EPHEMERAL: u8 = 11;
+/// 12 - RevokeEphemeral: remove the delegate of an ephemeral ATA
+pub const REVOKE_EPHEMERAL: u8 = 12;
+/// 13 - TransferFromEphemeral: delegated transfer between two ephemeral ATAs of the same mint
+///     Instruction data:
+///     [0..8] amount (LE u64)
+pub const TRANSFER_FROM_EPHEMERAL: u8 = 13;
+/// 14 - CloseEphemeralAta: close an empty, undelegated ephemeral ATA without pending deposits and
+///      reclaim its rent. Takes the pending deposit PDA after the destination
+pub const CLOSE_EPHEMERAL_ATA: u8 = 14;
+/// 15 - ReconcileGlobalVault: compare the vault token balance with the vault's total deposits
+///     Return data:
+///     [0..8]   vault token balance (LE u64)
+///     [8..16]  total deposited (LE u64)
+///     [16..24] surplus (LE u64)
+///     [24..32] shortfall (LE u64)
+pub const RECONCILE_GLOBAL_VAULT: u8 = 15;
+/// 16 - MigrateAccount: upgrade an ephemeral ATA or global vault to the current versioned layout
+pub const MIGRATE_ACCOUNT: u8 = 16;
+/// 17 - CreditPendingDeposit: credit the deposits queued while an ephemeral ATA was delegated
+pub const CREDIT_PENDING_DEPOSIT: u8 = 17;
+/// 18 - CommitEphemeralAta: commit the state of a delegated ephemeral ATA without undelegating it
+pub const COMMIT_EPHEMERAL_ATA: u8 = 18;
+/// 19 - DelegateEphemeralAtaBatch: delegate several ephemeral ATAs of one owner in one call
+///     Instruction data (each part optional), applied to every ephemeral ATA:
+///     [0..4]  commit frequency in milliseconds (LE u32), present in the 4 and 36-byte forms
+///     [..+32] validator
+pub const DELEGATE_EPHEMERAL_ATA_BATCH: u8 = 19;
+/// 20 - EnterRollup: create the missing accounts, deposit and delegate an ephemeral ATA in one call
+///     Instruction data:
+///     [0..8]  amount (LE u64)
+///     [8]     MemberFlags of the permission, created when the permission accounts are passed
+///     [9..]   delegation options, as for DelegateEphemeralAtaBatch
+pub const ENTER_ROLLUP: u8 = 20;
+/// 21 - ExitRollup: undelegate an ephemeral ATA and pay its balance to the owner
+///     The payout runs as a SettleExit action once the account is undelegated
+pub const EXIT_ROLLUP: u8 = 21;
+/// 22 - SettleExit: pay the balance of an ephemeral ATA that left with ExitRollup to its owner
+pub const SETTLE_EXIT: u8 = 22;
+/// 23 - DelegateWithdrawalIntent: create if missing and delegate the withdrawal intent of an ephemeral ATA
+///     Instruction data (each part optional), as for DelegateEphemeralAtaBatch:
+///     [0..4]  commit frequency in milliseconds (LE u32), present in the 4 and 36-byte forms
+///     [..+32] validator
+pub const DELEGATE_WITHDRAWAL_INTENT: u8 = 23;
+/// 24 - CreateWithdrawalIntent: in the ephemeral rollup, move tokens from an ephemeral ATA to its
+///      withdrawal intent, commit the ephemeral ATA and undelegate the intent
+///     Instruction data:
+///     [0..8] amount (LE u64)
+pub const CREATE_WITHDRAWAL_INTENT: u8 = 24;
+/// 25 - SettleWithdrawalIntent: pay an undelegated withdrawal intent from the vault to the owner
+pub const SETTLE_WITHDRAWAL_INTENT: u8 = 25;
+/// 196 - UndelegationCallback: restore an undelegated account, invoked by the delegation program
+///     Instruction data:
+///     [0..8] external undelegate discriminator, starting with this byte
+///     [8..]  seeds of the undelegated account
+pub const UNDELEGATION_CALLBACK: u8 = 196;
+
+/// Instructions supported by the Ephemeral SPL program.
+///
+/// Payloads borrow from the instruction data, so unpacking does not copy. Legacy
+/// payloads carrying a bump byte are still accepted; the bump is ignored since the
+/// program uses the canonical bump stored in each account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EphemeralSplInstruction<'a> {
+    InitializeEphemeralAta,
+    InitializeGlobalVault,
+    DepositSplTokens {
+        amount: u64,
+    },
+    WithdrawSplTokens {
+        amount: u64,
+    },
+    DelegateEphemeralAta {
+        validator: Option<&'a [u8; 32]>,
+        commit_frequency_ms: Option<u32>,
+    },
+    UndelegateEphemeralAta,
+    CreateEphemeralAtaPermission {
+        flags: u8,
+    },
+    DelegateEphemeralAtaPermission,
+    UndelegateEphemeralAtaPermission,
+    ResetEphemeralAtaPermission {
+        flags: u8,
+    },
+    TransferEphemeral {
+        amount: u64,
+    },
+    ApproveEphemeral {
+        amount: u64,
+    },
+    RevokeEphemeral,
+    TransferFromEphemeral {
+        amount: u64,
+    },
+    CloseEphemeralAta,
+    ReconcileGlobalVault,
+    MigrateAccount,
+    CreditPendingDeposit,
+    CommitEphemeralAta,
+    DelegateEphemeralAtaBatch {
+        validator: Option<&'a [u8; 32]>,
+        commit_frequency_ms: Option<u32>,
+    },
+    EnterRollup {
+        amount: u64,
+        flags: u8,
+        validator: Option<&'a [u8; 32]>,
+        commit_frequency_ms: Option<u32>,
+    },
+    ExitRollup,
+    SettleExit,
+    DelegateWithdrawalIntent {
+        validator: Option<&'a [u8; 32]>,
+        commit_frequency_ms: Option<u32>,
+    },
+    CreateWithdrawalIntent {
+        amount: u64,
+    },
+    SettleWithdrawalIntent,
+    /// `args` are the bytes following the 8-byte external undelegate discriminator.
+    UndelegationCallback {
+        args: &'a [u8],
+    },
+}
+
+impl<'a> EphemeralSplInstruction<'a> {
+    /// Length of the discriminator sent by the delegation program to the undelegation callback.
+    const CALLBACK_DISCRIMINATOR_LEN: usize = 8;
+
+    /// Parse an instruction from its data, discriminator included.
+    #[inline(always)]
+    pub fn unpack(data: &'a [u8]) -> Result<Self, ProgramError> {
+        let [discriminator, rest @ ..] = data else {
+            return Err(EphemeralSplError::InvalidInstruction.into());
+        };
+
+        Ok(match *discriminator {
+            INITIALIZE_EPHEMERAL_ATA => Self::InitializeEphemeralAta,
+            INITIALIZE_GLOBAL_VAULT => Self::InitializeGlobalVault,
+            DEPOSIT_SPL_TOKENS => Self::DepositSplTokens {
+                amount: unpack_amount(rest)?,
+            },
+            WITHDRAW_SPL_TOKENS => Self::WithdrawSplTokens {
+                amount: unpack_amount(rest)?,
+            },
+            DELEGATE_EPHEMERAL_ATA => {
+                let (commit_frequency_ms, validator) = match rest.len() {
+                    // legacy [bump] and [bump, validator]
+                    1 => (None, None),
+                    33 => (None, rest[1..].try_into().ok()),
+                    _ => unpack_delegate_options(rest)?,
+                };
+                Self::DelegateEphemeralAta {
+                    validator,
+                    commit_frequency_ms,
+                }
+            }
+            UNDELEGATE_EPHEMERAL_ATA => Self::UndelegateEphemeralAta,
+            CREATE_EPHEMERAL_ATA_PERMISSION => Self::CreateEphemeralAtaPermission {
+                flags: unpack_flags(rest)?,
+            },
+            DELEGATE_EPHEMERAL_ATA_PERMISSION => Self::DelegateEphemeralAtaPermission,
+            UNDELEGATE_EPHEMERAL_ATA_PERMISSION => Self::UndelegateEphemeralAtaPermission,
+            RESET_EPHEMERAL_ATA_PERMISSION => Self::ResetEphemeralAtaPermission {
+                flags: unpack_flags(rest)?,
+            },
+            TRANSFER_EPHEMERAL => Self::TransferEphemeral {
+                amount: unpack_amount(rest)?,
+            },
+            APPROVE_EPHEMERAL => Self::ApproveEphemeral {
+                amount: unpack_amount(rest)?,
+            },
+            REVOKE_EPHEMERAL => Self::RevokeEphemeral,
+            TRANSFER_FROM_EPHEMERAL => Self::TransferFromEphemeral {
+                amount: unpack_amount(rest)?,
+            },
+            CLOSE_EPHEMERAL_ATA => Self::CloseEphemeralAta,
+            RECONCILE_GLOBAL_VAULT => Self::ReconcileGlobalVault,
+            MIGRATE_ACCOUNT => Self::MigrateAccount,
+            CREDIT_PENDING_DEPOSIT => Self::CreditPendingDeposit,
+            COMMIT_EPHEMERAL_ATA => Self::CommitEphemeralAta,
+            DELEGATE_EPHEMERAL_ATA_BATCH => {
+                let (commit_frequency_ms, validator) = unpack_delegate_options(rest)?;
+                Self::DelegateEphemeralAtaBatch {
+                    validator,
+                    commit_frequency_ms,
+                }
+            }
+            ENTER_ROLLUP => {
+                let (amount, rest) = rest
+                    .split_at_checked(size_of::<u64>())
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                let [flags, rest @ ..] = rest else {
+                    return Err(ProgramError::InvalidInstructionData);
+                };
+                let (commit_frequency_ms, validator) = unpack_delegate_options(rest)?;
+                Self::EnterRollup {
+                    amount: unpack_amount(amount)?,
+                    flags: *flags,
+                    validator,
+                    commit_frequency_ms,
+                }
+            }
+            EXIT_ROLLUP => Self::ExitRollup,
+            SETTLE_EXIT => Self::SettleExit,
+            DELEGATE_WITHDRAWAL_INTENT => {
+                let (commit_frequency_ms, validator) = unpack_delegate_options(rest)?;
+                Self::DelegateWithdrawalIntent {
+                    validator,
+                    commit_frequency_ms,
+                }
+            }
+            CREATE_WITHDRAWAL_INTENT => Self::CreateWithdrawalIntent {
+                amount: unpack_amount(rest)?,
+            },
+            SETTLE_WITHDRAWAL_INTENT => Self::SettleWithdrawalIntent,
+            UNDELEGATION_CALLBACK => {
+                let (prefix, args) = rest
+                    .split_at_checked(Self::CALLBACK_DISCRIMINATOR_LEN - 1)
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                if prefix != &EXTERNAL_UNDELEGATE_DISCRIMINATOR[1..] {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Self::UndelegationCallback { args }
+            }
+            _ => return Err(EphemeralSplError::InvalidInstruction.into()),
+        })
+    }
+
+    /// Return the discriminator of the instruction.
+    #[inline(always)]
+    pub fn discriminator(&self) -> u8 {
+        match self {
+            Self::InitializeEphemeralAta => INITIALIZE_EPHEMERAL_ATA,
+            Self::InitializeGlobalVault => INITIALIZE_GLOBAL_VAULT,
+            Self::DepositSplTokens { .. } => DEPOSIT_SPL_TOKENS,
+            Self::WithdrawSplTokens { .. } => WITHDRAW_SPL_TOKENS,
+            Self::DelegateEphemeralAta { .. } => DELEGATE_EPHEMERAL_ATA,
+            Self::UndelegateEphemeralAta => UNDELEGATE_EPHEMERAL_ATA,
+            Self::CreateEphemeralAtaPermission { .. } => CREATE_EPHEMERAL_ATA_PERMISSION,
+            Self::DelegateEphemeralAtaPermission => DELEGATE_EPHEMERAL_ATA_PERMISSION,
+            Self::UndelegateEphemeralAtaPermission => UNDELEGATE_EPHEMERAL_ATA_PERMISSION,
+            Self::ResetEphemeralAtaPermission { .. } => RESET_EPHEMERAL_ATA_PERMISSION,
+            Self::TransferEphemeral { .. } => TRANSFER_EPHEMERAL,
+            Self::ApproveEphemeral { .. } => APPROVE_EPHEMERAL,
+            Self::RevokeEphemeral => REVOKE_EPHEMERAL,
+            Self::TransferFromEphemeral { .. } => TRANSFER_FROM_EPHEMERAL,
+            Self::CloseEphemeralAta => CLOSE_EPHEMERAL_ATA,
+            Self::ReconcileGlobalVault => RECONCILE_GLOBAL_VAULT,
+            Self::MigrateAccount => MIGRATE_ACCOUNT,
+            Self::CreditPendingDeposit => CREDIT_PENDING_DEPOSIT,
+            Self::CommitEphemeralAta => COMMIT_EPHEMERAL_ATA,
+            Self::DelegateEphemeralAtaBatch { .. } => DELEGATE_EPHEMERAL_ATA_BATCH,
+            Self::EnterRollup { .. } => ENTER_ROLLUP,
+            Self::ExitRollup => EXIT_ROLLUP,
+            Self::SettleExit => SETTLE_EXIT,
+            Self::DelegateWithdrawalIntent { .. } => DELEGATE_WITHDRAWAL_INTENT,
+            Self::CreateWithdrawalIntent { .. } => CREATE_WITHDRAWAL_INTENT,
+            Self::SettleWithdrawalIntent => SETTLE_WITHDRAWAL_INTENT,
+            Self::UndelegationCallback { .. } => UNDELEGATION_CALLBACK,
+        }
+    }
+
+    /// Return the length of the packed instruction data, discriminator included.
+    #[inline(always)]
+    pub fn packed_len(&self) -> usize {
+        1 + match self {
+            Self::DepositSplTokens { .. }
+            | Self::WithdrawSplTokens { .. }
+            | Self::TransferEphemeral { .. }
+            | Self::ApproveEphemeral { .. }
+            | Self::TransferFromEphemeral { .. }
+            | Self::CreateWithdrawalIntent { .. } => 8,
+            Self::DelegateEphemeralAta {
+                validator,
+                commit_frequency_ms,
+            }
+            | Self::DelegateEphemeralAtaBatch {
+                validator,
+                commit_frequency_ms,
+            }
+            | Self::DelegateWithdrawalIntent {
+                validator,
+                commit_frequency_ms,
+            } => delegate_options_len(*validator, *commit_frequency_ms),
+            Self::EnterRollup {
+                validator,
+                commit_frequency_ms,
+                ..
+            } => 9 + delegate_options_len(*validator, *commit_frequency_ms),
+            Self::CreateEphemeralAtaPermission { .. }
+            | Self::ResetEphemeralAtaPermission { .. } => 1,
+            Self::UndelegationCallback { args } => {
+                Self::CALLBACK_DISCRIMINATOR_LEN - 1 + args.len()
+            }
+            _ => 0,
+        }
+    }
+
+    /// Write the instruction data into `dst` and return the number of bytes written.
+    ///
+    /// `dst` must be at least [`Self::packed_len`] bytes long.
+    pub fn pack(&self, dst: &mut [u8]) -> Result<usize, ProgramError> {
+        let len = self.packed_len();
+        let [discriminator, rest @ ..] = dst
+            .get_mut(..len)
+            .ok_or(ProgramError::AccountDataTooSmall)?
+        else {
+            return Err(ProgramError::AccountDataTooSmall);
+        };
+        *discriminator = self.discriminator();
+
+        match self {
+            Self::DepositSplTokens { amount }
+            | Self::WithdrawSplTokens { amount }
+            | Self::TransferEphemeral { amount }
+            | Self::ApproveEphemeral { amount }
+            | Self::TransferFromEphemeral { amount }
+            | Self::CreateWithdrawalIntent { amount } => {
+                rest.copy_from_slice(&amount.to_le_bytes())
+            }
+            Self::DelegateEphemeralAta {
+                validator,
+                commit_frequency_ms,
+            }
+            | Self::DelegateEphemeralAtaBatch {
+                validator,
+                commit_frequency_ms,
+            }
+            | Self::DelegateWithdrawalIntent {
+                validator,
+                commit_frequency_ms,
+            } => pack_delegate_options(rest, *validator, *commit_frequency_ms),
+            Self::EnterRollup {
+                amount,
+                flags,
+                validator,
+                commit_frequency_ms,
+            } => {
+                let (head, tail) = rest.split_at_mut(9);
+                head[..8].copy_from_slice(&amount.to_le_bytes());
+                head[8] = *flags;
+                pack_delegate_options(tail, *validator, *commit_frequency_ms);
+            }
+            Self::CreateEphemeralAtaPermission { flags }
+            | Self::ResetEphemeralAtaPermission { flags } => rest[0] = *flags,
+            Self::UndelegationCallback { args } => {
+                let (prefix, suffix) = rest.split_at_mut(Self::CALLBACK_DISCRIMINATOR_LEN - 1);
+                prefix.copy_from_slice(&EXTERNAL_UNDELEGATE_DISCRIMINATOR[1..]);
+                suffix.copy_from_slice(args);
+            }
+            _ => {}
+        }
+
+        Ok(len)
+    }
+}
+
+/// Read the LE u64 amount at the start of `data`.
+#[inline(always)]
+fn unpack_amount(data: &[u8]) -> Result<u64, ProgramError> {
+    data.get(..8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+/// Read the delegation options from `[commit_frequency_ms][validator]`, either part
+/// optional.
+#[inline(always)]
+fn unpack_delegate_options(data: &[u8]) -> Result<(Option<u32>, Option<&[u8; 32]>), ProgramError> {
+    match data.len() {
+        0 => Ok((None, None)),
+        4 | 36 => {
+            let (frequency, validator) = data.split_at(size_of::<u32>());
+            let frequency = frequency.try_into().map(u32::from_le_bytes).ok();
+            Ok((frequency, validator.try_into().ok()))
+        }
+        32 => Ok((None, data.try_into().ok())),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// Length of the delegation options `[commit_frequency_ms][validator]`.
+#[inline(always)]
+fn delegate_options_len(validator: Option<&[u8; 32]>, commit_frequency_ms: Option<u32>) -> usize {
+    commit_frequency_ms.map_or(0, |_| size_of::<u32>()) + validator.map_or(0, |v| v.len())
+}
+
+/// Write the delegation options into `dst`, exactly [`delegate_options_len`] bytes long.
+#[inline(always)]
+fn pack_delegate_options(
+    dst: &mut [u8],
+    validator: Option<&[u8; 32]>,
+    commit_frequency_ms: Option<u32>,
+) {
+    let dst = match commit_frequency_ms {
+        Some(frequency) => {
+            let (head, tail) = dst.split_at_mut(size_of::<u32>());
+            head.copy_from_slice(&frequency.to_le_bytes());
+            tail
+        }
+        None => dst,
+    };
+    if let Some(validator) = validator {
+        dst.copy_from_slice(validator);
+    }
+}
+
+/// Read the permission flags from `[flags]` or the legacy `[bump, flags]`.
+#[inline(always)]
+fn unpack_flags(data: &[u8]) -> Result<u8, ProgramError> {
+    match data {
+        [flags] | [_, flags, ..] => Ok(*flags),
+        [] => Err(ProgramError::InvalidInstructionData),
+    }
+}
@@ -0,0 +1,69 @@
+//! Program derived addresses of the Ephemeral SPL program.
+//!
+//! Every function returns the canonical `(address, bump)` pair. They rely on
+//! `find_program_address`, which is a syscall on-chain and a curve check
+//! off-chain, so programs, clients and tests share the same derivations. The PDAs
+//! of the delegation and permission programs come from `ephemeral_rollups_pinocchio::pda`
+//! and `ephemeral_rollups_pinocchio::acl::pda`.
+
+use {
+    crate::program::{id_address, ASSOCIATED_TOKEN_PROGRAM_ID},
+    solana_address::Address,
+};
+
+/// Ephemeral ATA of `owner` for `mint`: `[owner, mint]` under the program.
+#[inline(always)]
+pub fn find_ephemeral_ata(owner: &Address, mint: &Address) -> (Address, u8) {
+    Address::find_program_address(&[owner.as_ref(), mint.as_ref()], &id_address())
+}
+
+/// Global Vault of `mint`: `[mint]` under the program.
+#[inline(always)]
+pub fn find_global_vault(mint: &Address) -> (Address, u8) {
+    Address::find_program_address(&[mint.as_ref()], &id_address())
+}
+
+/// Seed prefix of the [`find_pending_deposit`] PDA.
+pub const PENDING_DEPOSIT_SEED: &[u8] = b"pending-deposit";
+
+/// Pending deposits of a delegated Ephemeral ATA: `["pending-deposit", ephemeral_ata]`
+/// under the program.
+#[inline(always)]
+pub fn find_pending_deposit(ephemeral_ata: &Address) -> (Address, u8) {
+    Address::find_program_address(
+        &[PENDING_DEPOSIT_SEED, ephemeral_ata.as_ref()],
+        &id_address(),
+    )
+}
+
+/// Seed prefix of the [`find_withdrawal_intent`] PDA.
+pub const WITHDRAWAL_INTENT_SEED: &[u8] = b"withdrawal-intent";
+
+/// Withdrawal intents of an Ephemeral ATA: `["withdrawal-intent", ephemeral_ata]`
+/// under the program.
+#[inline(always)]
+pub fn find_withdrawal_intent(ephemeral_ata: &Address) -> (Address, u8) {
+    Address::find_program_address(
+        &[WITHDRAWAL_INTENT_SEED, ephemeral_ata.as_ref()],
+        &id_address(),
+    )
+}
+
+/// Associated token account of the Global Vault of `mint` under `token_program`.
+#[inline(always)]
+pub fn find_vault_token_account(mint: &Address, token_program: &Address) -> (Address, u8) {
+    find_associated_token_account(&find_global_vault(mint).0, mint, token_program)
+}
+
+/// Associated token account of `wallet` for `mint` under `token_program`.
+#[inline(always)]
+pub fn find_associated_token_account(
+    wallet: &Address,
+    mint: &Address,
+    token_program: &Address,
+) -> (Address, u8) {
+    Address::find_program_address(
+        &[wallet.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+}
@@ -1,16 +1,99 @@
 use pinocchio::Address;
 
-use super::{Initializable, RawType};
+use super::{AccountDiscriminator, AccountHeader, COption, Discriminated, Initializable, RawType};
 
 /// Internal representation of a token account data.
 #[repr(C)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EphemeralAta {
+    /// Account type and layout version
+    pub header: AccountHeader,
     /// The owner of the eata
     pub owner: Address,
     /// The mint associated with this account
     pub mint: Address,
     /// The amount of tokens this account holds.
     pub amount: u64,
+    /// The amount the delegate is still allowed to transfer.
+    pub delegated_amount: u64,
+    /// If `delegate` is `Some` then `delegated_amount` represents
+    /// the amount authorized by the delegate.
+    delegate: COption<Address>,
+    /// Bit flags, see [`EphemeralAta::WITHDRAW_ON_EXIT`].
+    ///
+    /// Carved out of the padding without a version bump: the padding of existing
+    /// accounts is zero, so they read as having no flag set.
+    flags: u8,
+    /// Explicit padding so the layout has no implicit padding bytes.
+    _padding: [u8; 3],
+}
+
+impl EphemeralAta {
+    /// Length of the untagged layout used before account versioning (owner, mint, amount).
+    pub const LEGACY_LEN: usize = 72;
+
+    /// Set when the owner left the ephemeral rollup with `ExitRollup`: once the account
+    /// is undelegated, its `SettleExit` action pays its balance to the owner.
+    pub const WITHDRAW_ON_EXIT: u8 = 1 << 0;
+
+    #[inline(always)]
+    pub fn has_delegate(&self) -> bool {
+        self.delegate.0[0] == 1
+    }
+
+    /// Return the delegate, if any.
+    pub fn delegate(&self) -> Option<&Address> {
+        if self.has_delegate() {
+            Some(&self.delegate.1)
+        } else {
+            None
+        }
+    }
+
+    /// Set the delegate and the amount it is allowed to transfer.
+    #[inline(always)]
+    pub fn set_delegate(&mut self, delegate: &Address, amount: u64) {
+        self.delegate.0[0] = 1;
+        #[allow(clippy::clone_on_copy)]
+        {
+            self.delegate.1 = delegate.clone();
+        }
+        self.delegated_amount = amount;
+    }
+
+    /// Remove the delegate and reset the delegated amount.
+    #[inline(always)]
+    pub fn clear_delegate(&mut self) {
+        self.delegate.0[0] = 0;
+        self.delegate.1 = Address::default();
+        self.delegated_amount = 0;
+    }
+
+    /// Return `true` if the balance is to be paid out to the owner after undelegation.
+    #[inline(always)]
+    pub fn withdraw_on_exit(&self) -> bool {
+        self.flags & Self::WITHDRAW_ON_EXIT != 0
+    }
+
+    /// Request or cancel the payout of the balance after undelegation.
+    #[inline(always)]
+    pub fn set_withdraw_on_exit(&mut self, withdraw_on_exit: bool) {
+        if withdraw_on_exit {
+            self.flags |= Self::WITHDRAW_ON_EXIT;
+        } else {
+            self.flags &= !Self::WITHDRAW_ON_EXIT;
+        }
+    }
+}
+
+impl Discriminated for EphemeralAta {
+    const DISCRIMINATOR: AccountDiscriminator = AccountDiscriminator::EphemeralAta;
+    const VERSION: u8 = 1;
 }
 
 impl RawType for EphemeralAta {
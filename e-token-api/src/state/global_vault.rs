@@ -1,12 +1,32 @@
 use pinocchio::Address;
 
-use super::{Initializable, RawType};
+use super::{AccountDiscriminator, AccountHeader, Discriminated, Initializable, RawType};
 
 /// Internal representation of a global vault for a specific mint.
 #[repr(C)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GlobalVault {
+    /// Account type and layout version
+    pub header: AccountHeader,
     /// The mint associated with this vault
     pub mint: Address,
+    /// The amount of tokens the vault owes to Ephemeral ATA holders.
+    pub total_deposited: u64,
+}
+
+impl GlobalVault {
+    /// Length of the untagged layout used before account versioning (mint only).
+    pub const LEGACY_LEN: usize = 32;
+}
+
+impl Discriminated for GlobalVault {
+    const DISCRIMINATOR: AccountDiscriminator = AccountDiscriminator::GlobalVault;
+    const VERSION: u8 = 1;
 }
 
 impl RawType for GlobalVault {
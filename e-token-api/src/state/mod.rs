@@ -1,7 +1,61 @@
 use pinocchio::error::ProgramError;
 
+use crate::error::EphemeralSplError;
+
 pub mod ephemeral_ata;
 pub mod global_vault;
+pub mod pending_deposit;
+pub mod withdrawal_intent;
+
+/// Type tags for the accounts owned by the program.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountDiscriminator {
+    EphemeralAta = 1,
+    GlobalVault = 2,
+    PendingDeposit = 3,
+    WithdrawalIntent = 4,
+}
+
+/// Header at the start of every account owned by the program.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccountHeader {
+    /// The account type, see [`AccountDiscriminator`].
+    pub discriminator: u8,
+    /// The layout version of the account data.
+    pub version: u8,
+    /// The canonical bump of the account PDA, recorded at creation.
+    pub bump: u8,
+    /// Reserved for future use; keeps the following fields 8-byte aligned.
+    _reserved: [u8; 5],
+}
+
+impl AccountHeader {
+    /// Return the header for the current layout of `T` with the canonical PDA `bump`.
+    #[inline(always)]
+    pub const fn new<T: Discriminated>(bump: u8) -> Self {
+        Self {
+            discriminator: T::DISCRIMINATOR as u8,
+            version: T::VERSION,
+            bump,
+            _reserved: [0; 5],
+        }
+    }
+}
+
+/// Trait to represent an account type tagged with a discriminator and a layout version.
+pub trait Discriminated {
+    /// The account type.
+    const DISCRIMINATOR: AccountDiscriminator;
+    /// The current layout version.
+    const VERSION: u8;
+}
 
 /// Trait to represent a type that can be initialized.
 pub trait Initializable {
@@ -9,13 +63,33 @@ pub trait Initializable {
     fn is_initialized(&self) -> bool;
 }
 
+/// Check that `bytes` start with the header of the current layout of `T`.
+#[inline(always)]
+fn check_header<T: Discriminated>(bytes: &[u8]) -> Result<(), ProgramError> {
+    match bytes {
+        [discriminator, ..] if *discriminator != T::DISCRIMINATOR as u8 => {
+            Err(ProgramError::InvalidAccountData)
+        }
+        [_, version, ..] if *version != T::VERSION => {
+            Err(EphemeralSplError::InvalidAccountVersion.into())
+        }
+        [_, _, ..] => Ok(()),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
 /// Return a reference for an initialized `T` from the given bytes.
 ///
+/// The discriminator and layout version are checked against `T`.
+///
 /// # Safety
 ///
 /// The caller must ensure that `bytes` contains a valid representation of `T`.
 #[inline(always)]
-pub unsafe fn load<T: Initializable + RawType>(bytes: &[u8]) -> Result<&T, ProgramError> {
+pub unsafe fn load<T: Initializable + RawType + Discriminated>(
+    bytes: &[u8],
+) -> Result<&T, ProgramError> {
+    check_header::<T>(bytes)?;
     load_unchecked(bytes).and_then(|t: &T| {
         // checks if the data is initialized
         if t.is_initialized() {
@@ -41,15 +115,53 @@ pub unsafe fn load_unchecked<T: RawType>(bytes: &[u8]) -> Result<&T, ProgramErro
     Ok(&*(bytes.as_ptr() as *const T))
 }
 
+/// Decode an initialized `T` from the given bytes into an owned value.
+///
+/// The length, discriminator and layout version are checked against `T`. The bytes
+/// may have any alignment, so this is suited to account data fetched over RPC.
+#[inline(always)]
+pub fn decode<T: Initializable + RawType + Discriminated>(bytes: &[u8]) -> Result<T, ProgramError> {
+    check_header::<T>(bytes)?;
+    if bytes.len() != T::LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    // SAFETY: the length matches `T` and `RawType` guarantees every byte pattern is valid.
+    let t = unsafe { core::ptr::read_unaligned(bytes.as_ptr() as *const T) };
+    if t.is_initialized() {
+        Ok(t)
+    } else {
+        Err(ProgramError::UninitializedAccount)
+    }
+}
+
+/// Return a reference for an initialized `T` from the given bytes.
+///
+/// Like [`load`], without `unsafe`: the bytes must also be aligned for `T`,
+/// otherwise [`ProgramError::InvalidAccountData`] is returned.
+#[inline(always)]
+pub fn decode_ref<T: Initializable + RawType + Discriminated>(
+    bytes: &[u8],
+) -> Result<&T, ProgramError> {
+    if (bytes.as_ptr() as usize) % core::mem::align_of::<T>() != 0 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    // SAFETY: the length and alignment are checked and `RawType` guarantees every
+    // byte pattern is valid.
+    unsafe { load(bytes) }
+}
+
 /// Return a mutable reference for an initialized `T` from the given bytes.
 ///
+/// The discriminator and layout version are checked against `T`.
+///
 /// # Safety
 ///
 /// The caller must ensure that `bytes` contains a valid representation of `T`.
 #[inline(always)]
-pub unsafe fn load_mut<T: Initializable + RawType>(
+pub unsafe fn load_mut<T: Initializable + RawType + Discriminated>(
     bytes: &mut [u8],
 ) -> Result<&mut T, ProgramError> {
+    check_header::<T>(bytes)?;
     load_mut_unchecked(bytes).and_then(|t: &mut T| {
         // checks if the data is initialized
         if t.is_initialized() {
@@ -66,7 +178,8 @@ pub type COption<T> = ([u8; 4], T);
 /// Marker trait for types that can cast from a raw pointer.
 ///
 /// It is up to the type implementing this trait to guarantee that the cast is safe,
-/// i.e., that the fields of the type are well aligned and there are no padding bytes.
+/// i.e., that the fields of the type are well aligned, there are no padding bytes
+/// and any byte pattern is a valid value, since [`decode`] relies on it.
 pub trait RawType {
     /// The length of the type.
     ///
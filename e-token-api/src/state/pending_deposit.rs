@@ -0,0 +1,41 @@
+use pinocchio::Address;
+
+use super::{AccountDiscriminator, AccountHeader, Discriminated, Initializable, RawType};
+
+/// Deposits made on the base layer while an Ephemeral ATA is delegated.
+///
+/// The tokens are already held by the vault and counted in its `total_deposited`;
+/// `amount` is credited to the Ephemeral ATA once it is undelegated.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PendingDeposit {
+    /// Account type and layout version
+    pub header: AccountHeader,
+    /// The Ephemeral ATA the deposits will be credited to
+    pub ephemeral_ata: Address,
+    /// The account that funded the rent, refunded when the deposits are credited
+    pub payer: Address,
+    /// The amount of tokens waiting to be credited.
+    pub amount: u64,
+}
+
+impl Discriminated for PendingDeposit {
+    const DISCRIMINATOR: AccountDiscriminator = AccountDiscriminator::PendingDeposit;
+    const VERSION: u8 = 1;
+}
+
+impl RawType for PendingDeposit {
+    const LEN: usize = core::mem::size_of::<PendingDeposit>();
+}
+
+impl Initializable for PendingDeposit {
+    #[inline(always)]
+    fn is_initialized(&self) -> bool {
+        self.ephemeral_ata != Address::default()
+    }
+}
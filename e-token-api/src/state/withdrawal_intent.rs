@@ -0,0 +1,45 @@
+use pinocchio::Address;
+
+use super::{AccountDiscriminator, AccountHeader, Discriminated, Initializable, RawType};
+
+/// Withdrawals requested in the ephemeral rollup, awaiting payout on the base layer.
+///
+/// The account is delegated alongside the Ephemeral ATA. `CreateWithdrawalIntent`
+/// moves tokens from the Ephemeral ATA to `amount` in the rollup and undelegates the
+/// account; once it is back on the base layer, `SettleWithdrawalIntent` pays `amount`
+/// from the vault to the owner and resets it.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WithdrawalIntent {
+    /// Account type and layout version
+    pub header: AccountHeader,
+    /// The Ephemeral ATA the withdrawals were debited from
+    pub ephemeral_ata: Address,
+    /// The owner of the Ephemeral ATA, paid on settlement
+    pub owner: Address,
+    /// The mint of the Ephemeral ATA
+    pub mint: Address,
+    /// The amount of tokens waiting to be paid out.
+    pub amount: u64,
+}
+
+impl Discriminated for WithdrawalIntent {
+    const DISCRIMINATOR: AccountDiscriminator = AccountDiscriminator::WithdrawalIntent;
+    const VERSION: u8 = 1;
+}
+
+impl RawType for WithdrawalIntent {
+    const LEN: usize = core::mem::size_of::<WithdrawalIntent>();
+}
+
+impl Initializable for WithdrawalIntent {
+    #[inline(always)]
+    fn is_initialized(&self) -> bool {
+        self.ephemeral_ata != Address::default()
+    }
+}
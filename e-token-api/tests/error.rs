@@ -0,0 +1,14 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use pinocchio::error::{ProgramError, ToStr};
+
+#[test]
+fn error_codes_round_trip() {
+    let mut code = 0;
+    while let Ok(error) = EphemeralSplError::try_from(code) {
+        assert_eq!(error as u32, code);
+        assert_eq!(ProgramError::from(error), ProgramError::Custom(code));
+        assert!(error.to_str().starts_with("Error: "));
+        code += 1;
+    }
+    assert_eq!(code, EphemeralSplError::NoWithdrawalIntent as u32 + 1);
+}
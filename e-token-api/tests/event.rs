@@ -0,0 +1,43 @@
+use ephemeral_spl_api::event::{EphemeralSplEvent, EventKind};
+use pinocchio::error::ProgramError;
+use solana_pubkey::Pubkey;
+
+#[test]
+fn event_pack_unpack_round_trip() {
+    let event = EphemeralSplEvent {
+        kind: EventKind::Withdraw,
+        ephemeral_ata: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount: 42,
+        post_balance: u64::MAX,
+    };
+
+    let data = event.pack();
+    assert_eq!(data.len(), EphemeralSplEvent::LEN);
+    assert_eq!(data[0], EventKind::Withdraw as u8);
+    assert_eq!(EphemeralSplEvent::unpack(&data).unwrap(), event);
+}
+
+#[test]
+fn event_unpack_rejects_malformed_data() {
+    let mut data = EphemeralSplEvent {
+        kind: EventKind::Deposit,
+        ephemeral_ata: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount: 1,
+        post_balance: 1,
+    }
+    .pack();
+
+    assert_eq!(
+        EphemeralSplEvent::unpack(&data[..EphemeralSplEvent::LEN - 1]).unwrap_err(),
+        ProgramError::InvalidArgument
+    );
+    data[0] = 200;
+    assert_eq!(
+        EphemeralSplEvent::unpack(&data).unwrap_err(),
+        ProgramError::InvalidArgument
+    );
+}
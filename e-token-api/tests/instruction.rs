@@ -0,0 +1,204 @@
+use ephemeral_rollups_pinocchio::consts::EXTERNAL_UNDELEGATE_DISCRIMINATOR;
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::instruction::{self, EphemeralSplInstruction};
+use pinocchio::error::ProgramError;
+
+fn round_trip(ix: EphemeralSplInstruction) {
+    let mut data = [0u8; 64];
+    let len = ix.pack(&mut data).unwrap();
+    assert_eq!(len, ix.packed_len());
+    assert_eq!(data[0], ix.discriminator());
+    assert_eq!(EphemeralSplInstruction::unpack(&data[..len]).unwrap(), ix);
+}
+
+#[test]
+fn pack_unpack_round_trip() {
+    let validator = [7u8; 32];
+    let callback_args = [1u8, 0, 0, 0, 1, 0, 0, 0, 9];
+
+    for ix in [
+        EphemeralSplInstruction::InitializeEphemeralAta,
+        EphemeralSplInstruction::InitializeGlobalVault,
+        EphemeralSplInstruction::DepositSplTokens { amount: 42 },
+        EphemeralSplInstruction::WithdrawSplTokens { amount: u64::MAX },
+        EphemeralSplInstruction::DelegateEphemeralAta {
+            validator: None,
+            commit_frequency_ms: None,
+        },
+        EphemeralSplInstruction::DelegateEphemeralAta {
+            validator: Some(&validator),
+            commit_frequency_ms: None,
+        },
+        EphemeralSplInstruction::DelegateEphemeralAta {
+            validator: None,
+            commit_frequency_ms: Some(30_000),
+        },
+        EphemeralSplInstruction::DelegateEphemeralAta {
+            validator: Some(&validator),
+            commit_frequency_ms: Some(500),
+        },
+        EphemeralSplInstruction::UndelegateEphemeralAta,
+        EphemeralSplInstruction::CreateEphemeralAtaPermission { flags: 3 },
+        EphemeralSplInstruction::DelegateEphemeralAtaPermission,
+        EphemeralSplInstruction::UndelegateEphemeralAtaPermission,
+        EphemeralSplInstruction::ResetEphemeralAtaPermission { flags: 0 },
+        EphemeralSplInstruction::TransferEphemeral { amount: 1 },
+        EphemeralSplInstruction::ApproveEphemeral { amount: 2 },
+        EphemeralSplInstruction::RevokeEphemeral,
+        EphemeralSplInstruction::TransferFromEphemeral { amount: 3 },
+        EphemeralSplInstruction::CloseEphemeralAta,
+        EphemeralSplInstruction::ReconcileGlobalVault,
+        EphemeralSplInstruction::MigrateAccount,
+        EphemeralSplInstruction::CreditPendingDeposit,
+        EphemeralSplInstruction::CommitEphemeralAta,
+        EphemeralSplInstruction::DelegateEphemeralAtaBatch {
+            validator: None,
+            commit_frequency_ms: None,
+        },
+        EphemeralSplInstruction::DelegateEphemeralAtaBatch {
+            validator: Some(&validator),
+            commit_frequency_ms: Some(1_000),
+        },
+        EphemeralSplInstruction::EnterRollup {
+            amount: 4,
+            flags: 0,
+            validator: None,
+            commit_frequency_ms: None,
+        },
+        EphemeralSplInstruction::EnterRollup {
+            amount: u64::MAX,
+            flags: 3,
+            validator: Some(&validator),
+            commit_frequency_ms: Some(50),
+        },
+        EphemeralSplInstruction::ExitRollup,
+        EphemeralSplInstruction::SettleExit,
+        EphemeralSplInstruction::DelegateWithdrawalIntent {
+            validator: Some(&validator),
+            commit_frequency_ms: None,
+        },
+        EphemeralSplInstruction::CreateWithdrawalIntent { amount: 5 },
+        EphemeralSplInstruction::SettleWithdrawalIntent,
+        EphemeralSplInstruction::UndelegationCallback {
+            args: &callback_args,
+        },
+    ] {
+        round_trip(ix);
+    }
+}
+
+#[test]
+fn unpack_accepts_legacy_bump_payloads() {
+    let validator = [7u8; 32];
+
+    let mut withdraw = vec![instruction::WITHDRAW_SPL_TOKENS];
+    withdraw.extend_from_slice(&5u64.to_le_bytes());
+    withdraw.push(254);
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&withdraw).unwrap(),
+        EphemeralSplInstruction::WithdrawSplTokens { amount: 5 }
+    );
+
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&[instruction::DELEGATE_EPHEMERAL_ATA, 254]).unwrap(),
+        EphemeralSplInstruction::DelegateEphemeralAta {
+            validator: None,
+            commit_frequency_ms: None,
+        }
+    );
+    let mut delegate = vec![instruction::DELEGATE_EPHEMERAL_ATA, 254];
+    delegate.extend_from_slice(&validator);
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&delegate).unwrap(),
+        EphemeralSplInstruction::DelegateEphemeralAta {
+            validator: Some(&validator),
+            commit_frequency_ms: None,
+        }
+    );
+
+    // 36 bytes are the current [commit_frequency_ms, validator], not a legacy form
+    let mut delegate = vec![instruction::DELEGATE_EPHEMERAL_ATA];
+    delegate.extend_from_slice(&500u32.to_le_bytes());
+    delegate.extend_from_slice(&validator);
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&delegate).unwrap(),
+        EphemeralSplInstruction::DelegateEphemeralAta {
+            validator: Some(&validator),
+            commit_frequency_ms: Some(500),
+        }
+    );
+
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&[instruction::CREATE_EPHEMERAL_ATA_PERMISSION, 254, 3])
+            .unwrap(),
+        EphemeralSplInstruction::CreateEphemeralAtaPermission { flags: 3 }
+    );
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&[instruction::INITIALIZE_GLOBAL_VAULT, 254]).unwrap(),
+        EphemeralSplInstruction::InitializeGlobalVault
+    );
+}
+
+#[test]
+fn unpack_rejects_malformed_data() {
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&[]).unwrap_err(),
+        ProgramError::from(EphemeralSplError::InvalidInstruction)
+    );
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&[200]).unwrap_err(),
+        ProgramError::from(EphemeralSplError::InvalidInstruction)
+    );
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&[instruction::DEPOSIT_SPL_TOKENS, 1, 2, 3]).unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&[instruction::DELEGATE_EPHEMERAL_ATA, 1, 2]).unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+    // Trailing bytes after a legacy [bump, validator] or a current payload
+    for len in [34, 35, 37, 64] {
+        let mut delegate = vec![instruction::DELEGATE_EPHEMERAL_ATA];
+        delegate.resize(1 + len, 7);
+        assert_eq!(
+            EphemeralSplInstruction::unpack(&delegate).unwrap_err(),
+            ProgramError::InvalidInstructionData
+        );
+    }
+    // The batch variant has no legacy bump forms
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&[instruction::DELEGATE_EPHEMERAL_ATA_BATCH, 254])
+            .unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+    let mut enter = vec![instruction::ENTER_ROLLUP];
+    enter.extend_from_slice(&5u64.to_le_bytes());
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&enter).unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&[instruction::RESET_EPHEMERAL_ATA_PERMISSION])
+            .unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&EXTERNAL_UNDELEGATE_DISCRIMINATOR[..4]).unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+    let mut callback = EXTERNAL_UNDELEGATE_DISCRIMINATOR.to_vec();
+    callback[7] ^= 1;
+    assert_eq!(
+        EphemeralSplInstruction::unpack(&callback).unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+}
+
+#[test]
+fn pack_rejects_short_buffer() {
+    let mut data = [0u8; 4];
+    assert!(EphemeralSplInstruction::DepositSplTokens { amount: 1 }
+        .pack(&mut data)
+        .is_err());
+}
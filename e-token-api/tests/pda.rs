@@ -0,0 +1,27 @@
+use ephemeral_spl_api::pda;
+use ephemeral_spl_api::program::{ASSOCIATED_TOKEN_PROGRAM_ID, ID};
+use solana_pubkey::Pubkey;
+
+const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
+
+#[test]
+fn program_pdas_match_seeds() {
+    let owner = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let token_program = spl_token_interface::ID;
+
+    assert_eq!(
+        pda::find_ephemeral_ata(&owner, &mint),
+        Pubkey::find_program_address(&[owner.as_ref(), mint.as_ref()], &PROGRAM)
+    );
+
+    let (vault, _) = Pubkey::find_program_address(&[mint.as_ref()], &PROGRAM);
+    assert_eq!(pda::find_global_vault(&mint).0, vault);
+    assert_eq!(
+        pda::find_vault_token_account(&mint, &token_program),
+        Pubkey::find_program_address(
+            &[vault.as_ref(), token_program.as_ref(), mint.as_ref()],
+            &ASSOCIATED_TOKEN_PROGRAM_ID,
+        )
+    );
+}
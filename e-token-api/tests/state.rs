@@ -0,0 +1,97 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::state::{
+    decode, decode_ref, ephemeral_ata::EphemeralAta, global_vault::GlobalVault, RawType,
+};
+use pinocchio::error::ProgramError;
+use solana_pubkey::Pubkey;
+
+fn ephemeral_ata_bytes(owner: &Pubkey, mint: &Pubkey, amount: u64) -> Vec<u8> {
+    let mut data = vec![1, 1, 254, 0, 0, 0, 0, 0];
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.resize(EphemeralAta::LEN, 0);
+    data
+}
+
+#[test]
+fn decode_ephemeral_ata_at_any_alignment() {
+    let owner = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let data = ephemeral_ata_bytes(&owner, &mint, 42);
+
+    // Shift the data by one byte so it is not aligned for `EphemeralAta`.
+    let mut unaligned = vec![0u8; data.len() + 1];
+    unaligned[1..].copy_from_slice(&data);
+
+    for bytes in [&data[..], &unaligned[1..]] {
+        let ata = decode::<EphemeralAta>(bytes).unwrap();
+        assert_eq!(ata.header.bump, 254);
+        assert_eq!(ata.owner, owner);
+        assert_eq!(ata.mint, mint);
+        assert_eq!(ata.amount, 42);
+        assert_eq!(ata.delegate(), None);
+    }
+}
+
+#[test]
+fn decode_ref_rejects_unaligned_data() {
+    let data = ephemeral_ata_bytes(&Pubkey::new_unique(), &Pubkey::new_unique(), 1);
+    let mut buffer = vec![0u8; data.len() + 8];
+    let aligned = buffer
+        .as_ptr()
+        .align_offset(core::mem::align_of::<EphemeralAta>());
+
+    buffer[aligned..aligned + data.len()].copy_from_slice(&data);
+    assert!(decode_ref::<EphemeralAta>(&buffer[aligned..aligned + data.len()]).is_ok());
+
+    let start = aligned + 1;
+    buffer[start..start + data.len()].copy_from_slice(&data);
+    assert_eq!(
+        decode_ref::<EphemeralAta>(&buffer[start..start + data.len()]).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+}
+
+#[test]
+fn decode_rejects_invalid_data() {
+    let data = ephemeral_ata_bytes(&Pubkey::new_unique(), &Pubkey::new_unique(), 1);
+
+    // Wrong length, e.g. a legacy account.
+    assert_eq!(
+        decode::<EphemeralAta>(&data[..EphemeralAta::LEGACY_LEN]).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+    // Wrong account type.
+    assert_eq!(
+        decode::<GlobalVault>(&data[..GlobalVault::LEN]).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+    // Unknown layout version.
+    let mut versioned = data.clone();
+    versioned[1] = 2;
+    assert_eq!(
+        decode::<EphemeralAta>(&versioned).unwrap_err(),
+        ProgramError::from(EphemeralSplError::InvalidAccountVersion)
+    );
+    // Uninitialized (default mint).
+    let uninitialized = ephemeral_ata_bytes(&Pubkey::new_unique(), &Pubkey::default(), 0);
+    assert_eq!(
+        decode::<EphemeralAta>(&uninitialized).unwrap_err(),
+        ProgramError::UninitializedAccount
+    );
+}
+
+#[test]
+fn withdraw_on_exit_flag_uses_the_former_padding() {
+    let mut data = ephemeral_ata_bytes(&Pubkey::new_unique(), &Pubkey::new_unique(), 1);
+    assert!(!decode::<EphemeralAta>(&data).unwrap().withdraw_on_exit());
+
+    data[EphemeralAta::LEN - 4] = EphemeralAta::WITHDRAW_ON_EXIT;
+    let mut ata = decode::<EphemeralAta>(&data).unwrap();
+    assert!(ata.withdraw_on_exit());
+
+    ata.set_withdraw_on_exit(false);
+    assert!(!ata.withdraw_on_exit());
+    assert_eq!(ata.amount, 1);
+}
@@ -0,0 +1,612 @@
+use {
+    ephemeral_spl_api::instruction::EphemeralSplInstruction,
+    solana_instruction::{AccountMeta, Instruction},
+    solana_pubkey::Pubkey,
+};
+
+use crate::{
+    instruction, pda, ASSOCIATED_TOKEN_PROGRAM_ID, DELEGATION_PROGRAM_ID, ID, MAGIC_CONTEXT_ID,
+    MAGIC_PROGRAM_ID, PERMISSION_PROGRAM_ID, SYSTEM_PROGRAM_ID,
+};
+
+/// Create the Ephemeral ATA of `owner` for `mint`, funded by `payer`.
+pub fn initialize_ephemeral_ata(payer: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::InitializeEphemeralAta,
+        vec![
+            AccountMeta::new(pda::ephemeral_ata(owner, mint), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// Create the Global Vault of `mint` and its associated token account, funded by `payer`.
+pub fn initialize_global_vault(
+    payer: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::InitializeGlobalVault,
+        vec![
+            AccountMeta::new(pda::global_vault(mint), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(pda::vault_token_account(mint, token_program), false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// Move `amount` tokens from `source_token_account` into the vault and credit the
+/// Ephemeral ATA of `owner`. `authority` must own `source_token_account`.
+pub fn deposit_spl_tokens(
+    owner: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    source_token_account: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::DepositSplTokens { amount },
+        vec![
+            AccountMeta::new(pda::ephemeral_ata(owner, mint), false),
+            AccountMeta::new(pda::global_vault(mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*source_token_account, false),
+            AccountMeta::new(pda::vault_token_account(mint, token_program), false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+/// Like [`deposit_spl_tokens`], for an Ephemeral ATA delegated to the ephemeral
+/// rollup: the amount is queued in its pending deposit account, funded by `payer`,
+/// until [`credit_pending_deposit`] runs after undelegation.
+pub fn deposit_spl_tokens_delegated(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    source_token_account: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut ix = deposit_spl_tokens(
+        owner,
+        authority,
+        mint,
+        source_token_account,
+        token_program,
+        amount,
+    );
+    ix.accounts.extend([
+        AccountMeta::new(
+            pda::pending_deposit(&pda::ephemeral_ata(owner, mint)),
+            false,
+        ),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+    ]);
+    ix
+}
+
+/// Credit the deposits queued while the Ephemeral ATA of `owner` for `mint` was
+/// delegated, refunding the pending deposit rent to `payer`, who funded it.
+pub fn credit_pending_deposit(payer: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    let ephemeral_ata = pda::ephemeral_ata(owner, mint);
+    instruction(
+        EphemeralSplInstruction::CreditPendingDeposit,
+        vec![
+            AccountMeta::new(ephemeral_ata, false),
+            AccountMeta::new(pda::pending_deposit(&ephemeral_ata), false),
+            AccountMeta::new(*payer, false),
+        ],
+    )
+}
+
+/// Move `amount` tokens from the vault to `destination_token_account` and debit the
+/// Ephemeral ATA of `owner`.
+pub fn withdraw_spl_tokens(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    destination_token_account: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::WithdrawSplTokens { amount },
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(pda::ephemeral_ata(owner, mint), false),
+            AccountMeta::new(pda::global_vault(mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(pda::vault_token_account(mint, token_program), false),
+            AccountMeta::new(*destination_token_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+/// Delegate the Ephemeral ATA of `owner` for `mint`, optionally to a specific
+/// `validator`. `owner` must sign; `payer` funds the delegation accounts.
+pub fn delegate_ephemeral_ata(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    validator: Option<&Pubkey>,
+) -> Instruction {
+    delegate_ephemeral_ata_with_commit_frequency(payer, owner, mint, validator, None)
+}
+
+/// Like [`delegate_ephemeral_ata`], additionally setting how often the validator
+/// commits the Ephemeral ATA back to the base layer.
+pub fn delegate_ephemeral_ata_with_commit_frequency(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    validator: Option<&Pubkey>,
+    commit_frequency_ms: Option<u32>,
+) -> Instruction {
+    let ephemeral_ata = pda::ephemeral_ata(owner, mint);
+    let (buffer, delegation_record, delegation_metadata) = pda::delegation(&ephemeral_ata, &ID);
+    instruction(
+        EphemeralSplInstruction::DelegateEphemeralAta {
+            validator: validator.map(|validator| validator.as_array()),
+            commit_frequency_ms,
+        },
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(ephemeral_ata, false),
+            AccountMeta::new_readonly(ID, false),
+            AccountMeta::new(buffer, false),
+            AccountMeta::new(delegation_record, false),
+            AccountMeta::new(delegation_metadata, false),
+            AccountMeta::new_readonly(DELEGATION_PROGRAM_ID, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+    )
+}
+
+/// Delegate the Ephemeral ATAs of `owner` for each of `mints` in one instruction, all
+/// with the same `validator` and commit frequency. `owner` must sign; `payer` funds
+/// the delegation accounts.
+pub fn delegate_ephemeral_ata_batch(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mints: &[Pubkey],
+    validator: Option<&Pubkey>,
+    commit_frequency_ms: Option<u32>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(ID, false),
+        AccountMeta::new_readonly(DELEGATION_PROGRAM_ID, false),
+        AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+    ];
+    for mint in mints {
+        let ephemeral_ata = pda::ephemeral_ata(owner, mint);
+        let (buffer, delegation_record, delegation_metadata) = pda::delegation(&ephemeral_ata, &ID);
+        accounts.extend([
+            AccountMeta::new(ephemeral_ata, false),
+            AccountMeta::new(buffer, false),
+            AccountMeta::new(delegation_record, false),
+            AccountMeta::new(delegation_metadata, false),
+        ]);
+    }
+    instruction(
+        EphemeralSplInstruction::DelegateEphemeralAtaBatch {
+            validator: validator.map(|validator| validator.as_array()),
+            commit_frequency_ms,
+        },
+        accounts,
+    )
+}
+
+/// Create the Ephemeral ATA of `owner` and the Global Vault for `mint` if missing,
+/// deposit `amount` from `source_token_account`, and delegate the Ephemeral ATA, in one
+/// instruction. With `permission_flags`, the ACL permission is also created before
+/// delegating. `owner` signs and funds the new accounts.
+#[allow(clippy::too_many_arguments)]
+pub fn enter_rollup(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    source_token_account: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+    permission_flags: Option<u8>,
+    validator: Option<&Pubkey>,
+    commit_frequency_ms: Option<u32>,
+) -> Instruction {
+    let ephemeral_ata = pda::ephemeral_ata(owner, mint);
+    let (buffer, delegation_record, delegation_metadata) = pda::delegation(&ephemeral_ata, &ID);
+    let mut accounts = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(ephemeral_ata, false),
+        AccountMeta::new(pda::global_vault(mint), false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(*source_token_account, false),
+        AccountMeta::new(pda::vault_token_account(mint, token_program), false),
+        AccountMeta::new_readonly(*token_program, false),
+        AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+        AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        AccountMeta::new_readonly(ID, false),
+        AccountMeta::new(buffer, false),
+        AccountMeta::new(delegation_record, false),
+        AccountMeta::new(delegation_metadata, false),
+        AccountMeta::new_readonly(DELEGATION_PROGRAM_ID, false),
+    ];
+    if permission_flags.is_some() {
+        accounts.extend([
+            AccountMeta::new(pda::permission(&ephemeral_ata), false),
+            AccountMeta::new_readonly(PERMISSION_PROGRAM_ID, false),
+        ]);
+    }
+    instruction(
+        EphemeralSplInstruction::EnterRollup {
+            amount,
+            flags: permission_flags.unwrap_or_default(),
+            validator: validator.map(|validator| validator.as_array()),
+            commit_frequency_ms,
+        },
+        accounts,
+    )
+}
+
+/// Commit and undelegate the Ephemeral ATA of `payer` for `mint`.
+pub fn undelegate_ephemeral_ata(payer: &Pubkey, mint: &Pubkey) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::UndelegateEphemeralAta,
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(pda::ephemeral_ata(payer, mint), false),
+            AccountMeta::new(MAGIC_CONTEXT_ID, false),
+            AccountMeta::new_readonly(MAGIC_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// Like [`undelegate_ephemeral_ata`], and pay the balance of the Ephemeral ATA of
+/// `payer` to `destination_token_account`, a token account of `payer`, once it is
+/// undelegated. Sent to the ephemeral rollup; the payout runs on the base layer as a
+/// post-undelegation action signed by the escrow of `payer`. If it fails,
+/// [`settle_exit`] pays the balance instead.
+pub fn exit_rollup(
+    payer: &Pubkey,
+    mint: &Pubkey,
+    destination_token_account: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::ExitRollup,
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(pda::ephemeral_ata(payer, mint), false),
+            AccountMeta::new(MAGIC_CONTEXT_ID, false),
+            AccountMeta::new_readonly(MAGIC_PROGRAM_ID, false),
+            AccountMeta::new_readonly(pda::global_vault(mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(pda::vault_token_account(mint, token_program), false),
+            AccountMeta::new_readonly(*destination_token_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+/// Pay the balance of the Ephemeral ATA of `owner` for `mint`, which left the
+/// ephemeral rollup with [`exit_rollup`], to `destination_token_account`, a token
+/// account of `owner`, when the automatic payout did not run. Permissionless: any fee
+/// payer can send it.
+pub fn settle_exit(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    destination_token_account: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::SettleExit,
+        vec![
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new(pda::ephemeral_ata(owner, mint), false),
+            AccountMeta::new(pda::global_vault(mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(pda::vault_token_account(mint, token_program), false),
+            AccountMeta::new(*destination_token_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+/// Delegate the withdrawal intent of the Ephemeral ATA of `owner` for `mint`,
+/// creating it if missing, optionally to a specific `validator`. Needed before each
+/// [`create_withdrawal_intent`]. `owner` must sign; `payer` funds the accounts.
+pub fn delegate_withdrawal_intent(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    validator: Option<&Pubkey>,
+    commit_frequency_ms: Option<u32>,
+) -> Instruction {
+    let withdrawal_intent = pda::withdrawal_intent(&pda::ephemeral_ata(owner, mint));
+    let (buffer, delegation_record, delegation_metadata) = pda::delegation(&withdrawal_intent, &ID);
+    instruction(
+        EphemeralSplInstruction::DelegateWithdrawalIntent {
+            validator: validator.map(|validator| validator.as_array()),
+            commit_frequency_ms,
+        },
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(withdrawal_intent, false),
+            AccountMeta::new_readonly(ID, false),
+            AccountMeta::new(buffer, false),
+            AccountMeta::new(delegation_record, false),
+            AccountMeta::new(delegation_metadata, false),
+            AccountMeta::new_readonly(DELEGATION_PROGRAM_ID, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// Move `amount` from the Ephemeral ATA of `owner` for `mint` to its withdrawal intent
+/// and undelegate the intent, for [`settle_withdrawal_intent`] to pay on the base
+/// layer. Sent to the ephemeral rollup.
+pub fn create_withdrawal_intent(owner: &Pubkey, mint: &Pubkey, amount: u64) -> Instruction {
+    let ephemeral_ata = pda::ephemeral_ata(owner, mint);
+    instruction(
+        EphemeralSplInstruction::CreateWithdrawalIntent { amount },
+        vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(ephemeral_ata, false),
+            AccountMeta::new(pda::withdrawal_intent(&ephemeral_ata), false),
+            AccountMeta::new(MAGIC_CONTEXT_ID, false),
+            AccountMeta::new_readonly(MAGIC_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// Pay the withdrawal intent of the Ephemeral ATA of `owner` for `mint`, once it is
+/// back on the base layer, to `destination_token_account`, a token account of
+/// `owner`. Permissionless: any fee payer can send it.
+pub fn settle_withdrawal_intent(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    destination_token_account: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::SettleWithdrawalIntent,
+        vec![
+            AccountMeta::new(
+                pda::withdrawal_intent(&pda::ephemeral_ata(owner, mint)),
+                false,
+            ),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new(pda::global_vault(mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(pda::vault_token_account(mint, token_program), false),
+            AccountMeta::new(*destination_token_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+/// Commit the Ephemeral ATA of `owner` for `mint` to the base layer, leaving it delegated.
+pub fn commit_ephemeral_ata(owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::CommitEphemeralAta,
+        vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(pda::ephemeral_ata(owner, mint), false),
+            AccountMeta::new(MAGIC_CONTEXT_ID, false),
+            AccountMeta::new_readonly(MAGIC_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// Create the ACL permission of the Ephemeral ATA of `owner` for `mint`, funded by
+/// `payer`. Only `owner` may pass non-zero `flags`.
+pub fn create_ephemeral_ata_permission(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    flags: u8,
+) -> Instruction {
+    let ephemeral_ata = pda::ephemeral_ata(owner, mint);
+    instruction(
+        EphemeralSplInstruction::CreateEphemeralAtaPermission { flags },
+        vec![
+            AccountMeta::new(ephemeral_ata, false),
+            AccountMeta::new(pda::permission(&ephemeral_ata), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(PERMISSION_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// Delegate the ACL permission of the Ephemeral ATA of `owner` for `mint` to `validator`.
+pub fn delegate_ephemeral_ata_permission(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    validator: &Pubkey,
+) -> Instruction {
+    let ephemeral_ata = pda::ephemeral_ata(owner, mint);
+    let permission = pda::permission(&ephemeral_ata);
+    let (buffer, delegation_record, delegation_metadata) =
+        pda::delegation(&permission, &PERMISSION_PROGRAM_ID);
+    instruction(
+        EphemeralSplInstruction::DelegateEphemeralAtaPermission,
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(ephemeral_ata, false),
+            AccountMeta::new_readonly(PERMISSION_PROGRAM_ID, false),
+            AccountMeta::new(permission, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(buffer, false),
+            AccountMeta::new(delegation_record, false),
+            AccountMeta::new(delegation_metadata, false),
+            AccountMeta::new_readonly(DELEGATION_PROGRAM_ID, false),
+            AccountMeta::new_readonly(*validator, false),
+        ],
+    )
+}
+
+/// Commit and undelegate the ACL permission of the Ephemeral ATA of `owner` for `mint`.
+pub fn undelegate_ephemeral_ata_permission(owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    let ephemeral_ata = pda::ephemeral_ata(owner, mint);
+    instruction(
+        EphemeralSplInstruction::UndelegateEphemeralAtaPermission,
+        vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(ephemeral_ata, false),
+            AccountMeta::new(pda::permission(&ephemeral_ata), false),
+            AccountMeta::new_readonly(PERMISSION_PROGRAM_ID, false),
+            AccountMeta::new_readonly(MAGIC_PROGRAM_ID, false),
+            AccountMeta::new(MAGIC_CONTEXT_ID, false),
+        ],
+    )
+}
+
+/// Reset the ACL permission members of the Ephemeral ATA of `owner` for `mint`.
+pub fn reset_ephemeral_ata_permission(owner: &Pubkey, mint: &Pubkey, flags: u8) -> Instruction {
+    let ephemeral_ata = pda::ephemeral_ata(owner, mint);
+    instruction(
+        EphemeralSplInstruction::ResetEphemeralAtaPermission { flags },
+        vec![
+            AccountMeta::new(ephemeral_ata, false),
+            AccountMeta::new(pda::permission(&ephemeral_ata), false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(PERMISSION_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// Move `amount` from the Ephemeral ATA of `owner` to the one of `recipient`.
+pub fn transfer_ephemeral(
+    owner: &Pubkey,
+    recipient: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::TransferEphemeral { amount },
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(pda::ephemeral_ata(owner, mint), false),
+            AccountMeta::new(pda::ephemeral_ata(recipient, mint), false),
+        ],
+    )
+}
+
+/// Allow `delegate` to transfer up to `amount` from the Ephemeral ATA of `owner`.
+pub fn approve_ephemeral(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    delegate: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::ApproveEphemeral { amount },
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(pda::ephemeral_ata(owner, mint), false),
+            AccountMeta::new_readonly(*delegate, false),
+        ],
+    )
+}
+
+/// Remove the delegate of the Ephemeral ATA of `owner`.
+pub fn revoke_ephemeral(owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::RevokeEphemeral,
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(pda::ephemeral_ata(owner, mint), false),
+        ],
+    )
+}
+
+/// Move `amount` from the Ephemeral ATA of `owner` to the one of `recipient`, signed
+/// by the approved `delegate`.
+pub fn transfer_from_ephemeral(
+    delegate: &Pubkey,
+    owner: &Pubkey,
+    recipient: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::TransferFromEphemeral { amount },
+        vec![
+            AccountMeta::new_readonly(*delegate, true),
+            AccountMeta::new(pda::ephemeral_ata(owner, mint), false),
+            AccountMeta::new(pda::ephemeral_ata(recipient, mint), false),
+        ],
+    )
+}
+
+/// Close the empty Ephemeral ATA of `owner` and send its rent to `destination`.
+pub fn close_ephemeral_ata(owner: &Pubkey, mint: &Pubkey, destination: &Pubkey) -> Instruction {
+    let ephemeral_ata = pda::ephemeral_ata(owner, mint);
+    instruction(
+        EphemeralSplInstruction::CloseEphemeralAta,
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(ephemeral_ata, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(pda::pending_deposit(&ephemeral_ata), false),
+        ],
+    )
+}
+
+/// Compare the vault token balance of `mint` with the vault's total deposits.
+pub fn reconcile_global_vault(mint: &Pubkey, token_program: &Pubkey) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::ReconcileGlobalVault,
+        vec![
+            AccountMeta::new_readonly(pda::global_vault(mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(pda::vault_token_account(mint, token_program), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+/// Upgrade the Ephemeral ATA of `owner` for `mint` to the current layout, funded by `payer`.
+pub fn migrate_ephemeral_ata(payer: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::MigrateAccount,
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(pda::ephemeral_ata(owner, mint), false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// Upgrade the Global Vault of `mint` to the current layout, funded by `payer`.
+pub fn migrate_global_vault(payer: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Instruction {
+    instruction(
+        EphemeralSplInstruction::MigrateAccount,
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(pda::global_vault(mint), false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(pda::vault_token_account(mint, token_program), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
@@ -0,0 +1,47 @@
+//! Instruction builders for the Ephemeral SPL program.
+//!
+//! Each builder derives the program and delegation PDAs it needs and returns an
+//! [`Instruction`] with the account order and writable/signer flags expected by
+//! the on-chain processor. Instruction data is packed with
+//! [`EphemeralSplInstruction`], the definition shared with the program.
+
+use {
+    ephemeral_spl_api::instruction::EphemeralSplInstruction,
+    solana_instruction::{AccountMeta, Instruction},
+    solana_pubkey::Pubkey,
+};
+
+mod instructions;
+mod pda;
+
+pub use instructions::*;
+
+/// Ephemeral SPL program id.
+pub const ID: Pubkey = Pubkey::new_from_array(ephemeral_spl_api::program::ID);
+/// Associated Token Account program id.
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    ephemeral_spl_api::program::ASSOCIATED_TOKEN_PROGRAM_ID;
+/// System program id.
+pub const SYSTEM_PROGRAM_ID: Pubkey = solana_system_interface::program::ID;
+/// MagicBlock delegation program id.
+pub const DELEGATION_PROGRAM_ID: Pubkey =
+    ephemeral_rollups_pinocchio::consts::DELEGATION_PROGRAM_ID;
+/// Magic program id, used to commit and undelegate from the ephemeral rollup.
+pub const MAGIC_PROGRAM_ID: Pubkey = ephemeral_rollups_pinocchio::consts::MAGIC_PROGRAM_ID;
+/// Magic context account, used to commit and undelegate from the ephemeral rollup.
+pub const MAGIC_CONTEXT_ID: Pubkey = ephemeral_rollups_pinocchio::consts::MAGIC_CONTEXT_ID;
+/// ACL permission program id.
+pub const PERMISSION_PROGRAM_ID: Pubkey =
+    ephemeral_rollups_pinocchio::acl::consts::PERMISSION_PROGRAM_ID;
+
+/// Build an instruction of the Ephemeral SPL program.
+fn instruction(ix: EphemeralSplInstruction, accounts: Vec<AccountMeta>) -> Instruction {
+    let mut data = vec![0; ix.packed_len()];
+    ix.pack(&mut data)
+        .expect("buffer is sized to the packed length");
+    Instruction {
+        program_id: ID,
+        accounts,
+        data,
+    }
+}
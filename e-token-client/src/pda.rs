@@ -0,0 +1,51 @@
+use {
+    ephemeral_rollups_pinocchio::{
+        acl::pda::permission_pda_from_permissioned_account,
+        pda::{
+            delegate_buffer_pda_from_delegated_account_and_owner_program,
+            delegation_metadata_pda_from_delegated_account,
+            delegation_record_pda_from_delegated_account,
+        },
+    },
+    ephemeral_spl_api::pda,
+    solana_pubkey::Pubkey,
+};
+
+/// Ephemeral ATA PDA `[owner, mint]`.
+pub(crate) fn ephemeral_ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    pda::find_ephemeral_ata(owner, mint).0
+}
+
+/// Global Vault PDA `[mint]`.
+pub(crate) fn global_vault(mint: &Pubkey) -> Pubkey {
+    pda::find_global_vault(mint).0
+}
+
+/// Pending deposits PDA `["pending-deposit", ephemeral_ata]`.
+pub(crate) fn pending_deposit(ephemeral_ata: &Pubkey) -> Pubkey {
+    pda::find_pending_deposit(ephemeral_ata).0
+}
+
+/// Withdrawal intent PDA `["withdrawal-intent", ephemeral_ata]`.
+pub(crate) fn withdrawal_intent(ephemeral_ata: &Pubkey) -> Pubkey {
+    pda::find_withdrawal_intent(ephemeral_ata).0
+}
+
+/// Associated token account of the Global Vault PDA.
+pub(crate) fn vault_token_account(mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    pda::find_vault_token_account(mint, token_program).0
+}
+
+/// ACL permission PDA of `account`.
+pub(crate) fn permission(account: &Pubkey) -> Pubkey {
+    permission_pda_from_permissioned_account(account)
+}
+
+/// Delegation buffer, record and metadata PDAs of `account`, owned by `owner_program`.
+pub(crate) fn delegation(account: &Pubkey, owner_program: &Pubkey) -> (Pubkey, Pubkey, Pubkey) {
+    (
+        delegate_buffer_pda_from_delegated_account_and_owner_program(account, owner_program),
+        delegation_record_pda_from_delegated_account(account),
+        delegation_metadata_pda_from_delegated_account(account),
+    )
+}
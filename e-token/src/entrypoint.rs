@@ -1,4 +1,4 @@
-use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::instruction::EphemeralSplInstruction;
 use {
     crate::processor::*,
     core::{mem::MaybeUninit, slice::from_raw_parts},
@@ -49,77 +49,182 @@ pub(crate) fn inner_process_instruction(
     accounts: &[AccountView],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    let [discriminator, instruction_data @ ..] = instruction_data else {
-        return Err(EphemeralSplError::InvalidInstruction.into());
-    };
-
-    match *discriminator {
-        0 => {
+    match EphemeralSplInstruction::unpack(instruction_data)? {
+        EphemeralSplInstruction::InitializeEphemeralAta => {
             #[cfg(feature = "logging")]
             pinocchio_log::log!("Instruction: InitializeEphemeralAta");
 
-            process_initialize_ephemeral_ata(accounts, instruction_data)
+            process_initialize_ephemeral_ata(accounts)
         }
-        1 => {
+        EphemeralSplInstruction::InitializeGlobalVault => {
             #[cfg(feature = "logging")]
             pinocchio_log::log!("Instruction: InitializeGlobalVault");
 
-            process_initialize_global_vault(accounts, instruction_data)
+            process_initialize_global_vault(accounts)
         }
-        2 => {
+        EphemeralSplInstruction::DepositSplTokens { amount } => {
             #[cfg(feature = "logging")]
             pinocchio_log::log!("Instruction: DepositSplTokens");
 
-            process_deposit_spl_tokens(accounts, instruction_data)
+            process_deposit_spl_tokens(accounts, amount)
         }
-        3 => {
+        EphemeralSplInstruction::WithdrawSplTokens { amount } => {
             #[cfg(feature = "logging")]
             pinocchio_log::log!("Instruction: WithdrawSplTokens");
 
-            process_withdraw_spl_tokens(accounts, instruction_data)
+            process_withdraw_spl_tokens(accounts, amount)
         }
-        4 => {
+        EphemeralSplInstruction::DelegateEphemeralAta {
+            validator,
+            commit_frequency_ms,
+        } => {
             #[cfg(feature = "logging")]
             pinocchio_log::log!("Instruction: DelegateEphemeralAta");
 
-            process_delegate_ephemeral_ata(accounts, instruction_data)
+            process_delegate_ephemeral_ata(accounts, validator, commit_frequency_ms)
         }
-        5 => {
+        EphemeralSplInstruction::UndelegateEphemeralAta => {
             #[cfg(feature = "logging")]
             pinocchio_log::log!("Instruction: UndelegateEphemeralAta");
 
-            process_undelegate_ephemeral_ata(accounts, instruction_data)
+            process_undelegate_ephemeral_ata(accounts)
         }
-        6 => {
+        EphemeralSplInstruction::CreateEphemeralAtaPermission { flags } => {
             #[cfg(feature = "logging")]
             pinocchio_log::log!("Instruction: CreateEphemeralAtaPermission");
 
-            process_create_ephemeral_ata_permission(accounts, instruction_data)
+            process_create_ephemeral_ata_permission(accounts, flags)
         }
-        7 => {
+        EphemeralSplInstruction::DelegateEphemeralAtaPermission => {
             #[cfg(feature = "logging")]
             pinocchio_log::log!("Instruction: DelegateEphemeralAtaPermission");
 
-            process_delegate_ephemeral_ata_permission(accounts, instruction_data)
+            process_delegate_ephemeral_ata_permission(accounts)
         }
-        8 => {
+        EphemeralSplInstruction::UndelegateEphemeralAtaPermission => {
             #[cfg(feature = "logging")]
             pinocchio_log::log!("Instruction: UndelegateEphemeralAtaPermission");
 
-            process_undelegate_ephemeral_ata_permission(accounts, instruction_data)
+            process_undelegate_ephemeral_ata_permission(accounts)
         }
-        9 => {
+        EphemeralSplInstruction::ResetEphemeralAtaPermission { flags } => {
             #[cfg(feature = "logging")]
             pinocchio_log::log!("Instruction: ResetEphemeralAtaPermission");
 
-            process_reset_ephemeral_ata_permission(accounts, instruction_data)
+            process_reset_ephemeral_ata_permission(accounts, flags)
+        }
+        EphemeralSplInstruction::TransferEphemeral { amount } => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: TransferEphemeral");
+
+            process_transfer_ephemeral(accounts, amount)
+        }
+        EphemeralSplInstruction::ApproveEphemeral { amount } => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: ApproveEphemeral");
+
+            process_approve_ephemeral(accounts, amount)
+        }
+        EphemeralSplInstruction::RevokeEphemeral => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: RevokeEphemeral");
+
+            process_revoke_ephemeral(accounts)
+        }
+        EphemeralSplInstruction::TransferFromEphemeral { amount } => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: TransferFromEphemeral");
+
+            process_transfer_from_ephemeral(accounts, amount)
+        }
+        EphemeralSplInstruction::CloseEphemeralAta => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: CloseEphemeralAta");
+
+            process_close_ephemeral_ata(accounts)
+        }
+        EphemeralSplInstruction::ReconcileGlobalVault => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: ReconcileGlobalVault");
+
+            process_reconcile_global_vault(accounts)
+        }
+        EphemeralSplInstruction::MigrateAccount => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: MigrateAccount");
+
+            process_migrate_account(accounts)
+        }
+        EphemeralSplInstruction::CreditPendingDeposit => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: CreditPendingDeposit");
+
+            process_credit_pending_deposit(accounts)
+        }
+        EphemeralSplInstruction::CommitEphemeralAta => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: CommitEphemeralAta");
+
+            process_commit_ephemeral_ata(accounts)
+        }
+        EphemeralSplInstruction::DelegateEphemeralAtaBatch {
+            validator,
+            commit_frequency_ms,
+        } => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: DelegateEphemeralAtaBatch");
+
+            process_delegate_ephemeral_ata_batch(accounts, validator, commit_frequency_ms)
+        }
+        EphemeralSplInstruction::EnterRollup {
+            amount,
+            flags,
+            validator,
+            commit_frequency_ms,
+        } => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: EnterRollup");
+
+            process_enter_rollup(accounts, amount, flags, validator, commit_frequency_ms)
+        }
+        EphemeralSplInstruction::ExitRollup => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: ExitRollup");
+
+            process_exit_rollup(accounts)
+        }
+        EphemeralSplInstruction::SettleExit => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: SettleExit");
+
+            process_settle_exit(accounts)
+        }
+        EphemeralSplInstruction::DelegateWithdrawalIntent {
+            validator,
+            commit_frequency_ms,
+        } => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: DelegateWithdrawalIntent");
+
+            process_delegate_withdrawal_intent(accounts, validator, commit_frequency_ms)
+        }
+        EphemeralSplInstruction::CreateWithdrawalIntent { amount } => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: CreateWithdrawalIntent");
+
+            process_create_withdrawal_intent(accounts, amount)
+        }
+        EphemeralSplInstruction::SettleWithdrawalIntent => {
+            #[cfg(feature = "logging")]
+            pinocchio_log::log!("Instruction: SettleWithdrawalIntent");
+
+            process_settle_withdrawal_intent(accounts)
         }
-        196 => {
+        EphemeralSplInstruction::UndelegationCallback { args } => {
             #[cfg(feature = "logging")]
             pinocchio_log::log!("Instruction: UndelegationCallback");
 
-            process_undelegation_callback(accounts, instruction_data)
+            process_undelegation_callback(accounts, args)
         }
-        _ => Err(EphemeralSplError::InvalidInstruction.into()),
     }
 }
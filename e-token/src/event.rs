@@ -0,0 +1,30 @@
+use {
+    ephemeral_spl_api::{
+        event::{EphemeralSplEvent, EventKind},
+        state::ephemeral_ata::EphemeralAta,
+    },
+    pinocchio::Address,
+};
+
+/// Log an [`EphemeralSplEvent`] of `kind` for `ephemeral_ata` through `sol_log_data`.
+#[inline(always)]
+pub fn emit(kind: EventKind, address: &Address, ephemeral_ata: &EphemeralAta, amount: u64) {
+    #[allow(clippy::clone_on_copy)]
+    let event = EphemeralSplEvent {
+        kind,
+        ephemeral_ata: address.clone(),
+        mint: ephemeral_ata.mint.clone(),
+        owner: ephemeral_ata.owner.clone(),
+        amount,
+        post_balance: ephemeral_ata.amount,
+    };
+    let data = event.pack();
+
+    #[cfg(any(target_os = "solana", target_arch = "bpf"))]
+    {
+        let fields: [&[u8]; 1] = [&data];
+        unsafe { pinocchio::syscalls::sol_log_data(fields.as_ptr() as *const u8, 1) };
+    }
+    #[cfg(not(any(target_os = "solana", target_arch = "bpf")))]
+    let _ = data;
+}
@@ -4,7 +4,11 @@
 extern crate alloc;
 
 mod entrypoint;
+mod event;
+mod pda;
 mod processor;
+mod token_interface;
+mod validation;
 
 pub use crate::entrypoint::process_instruction;
 pub use ephemeral_spl_api::program::ID;
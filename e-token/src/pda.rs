@@ -0,0 +1,126 @@
+use {
+    ephemeral_spl_api::{
+        pda,
+        state::{
+            ephemeral_ata::EphemeralAta, global_vault::GlobalVault,
+            withdrawal_intent::WithdrawalIntent,
+        },
+    },
+    pinocchio::{error::ProgramError, AccountView, Address, ProgramResult},
+};
+
+/// Find the canonical Ephemeral ATA PDA `[owner, mint]` and ensure it is `ephemeral_ata_info`.
+///
+/// Return the canonical bump.
+#[inline(always)]
+pub fn find_ephemeral_ata(
+    ephemeral_ata_info: &AccountView,
+    owner: &Address,
+    mint: &Address,
+) -> Result<u8, ProgramError> {
+    let (address, bump) = pda::find_ephemeral_ata(owner, mint);
+    if address != *ephemeral_ata_info.address() {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(bump)
+}
+
+/// Find the canonical Global Vault PDA `[mint]` and ensure it is `vault_info`.
+///
+/// Return the canonical bump.
+#[inline(always)]
+pub fn find_global_vault(vault_info: &AccountView, mint: &Address) -> Result<u8, ProgramError> {
+    let (address, bump) = pda::find_global_vault(mint);
+    if address != *vault_info.address() {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(bump)
+}
+
+/// Find the canonical pending deposit PDA `["pending-deposit", ephemeral_ata]` and
+/// ensure it is `pending_deposit_info`.
+///
+/// Return the canonical bump.
+#[inline(always)]
+pub fn find_pending_deposit(
+    pending_deposit_info: &AccountView,
+    ephemeral_ata: &Address,
+) -> Result<u8, ProgramError> {
+    let (address, bump) = pda::find_pending_deposit(ephemeral_ata);
+    if address != *pending_deposit_info.address() {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(bump)
+}
+
+/// Find the canonical withdrawal intent PDA `["withdrawal-intent", ephemeral_ata]` and
+/// ensure it is `withdrawal_intent_info`.
+///
+/// Return the canonical bump.
+#[inline(always)]
+pub fn find_withdrawal_intent(
+    withdrawal_intent_info: &AccountView,
+    ephemeral_ata: &Address,
+) -> Result<u8, ProgramError> {
+    let (address, bump) = pda::find_withdrawal_intent(ephemeral_ata);
+    if address != *withdrawal_intent_info.address() {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(bump)
+}
+
+/// Ensure `ephemeral_ata_info` is derived from the seeds and stored bump of `ephemeral_ata`.
+#[inline(always)]
+pub fn check_ephemeral_ata(
+    ephemeral_ata_info: &AccountView,
+    ephemeral_ata: &EphemeralAta,
+) -> ProgramResult {
+    check_address(
+        ephemeral_ata_info,
+        &[
+            ephemeral_ata.owner.as_ref(),
+            ephemeral_ata.mint.as_ref(),
+            &[ephemeral_ata.header.bump],
+        ],
+    )
+}
+
+/// Ensure `vault_info` is derived from the seeds and stored bump of `vault`.
+#[inline(always)]
+pub fn check_global_vault(vault_info: &AccountView, vault: &GlobalVault) -> ProgramResult {
+    check_address(vault_info, &[vault.mint.as_ref(), &[vault.header.bump]])
+}
+
+/// Ensure `withdrawal_intent_info` is derived from the seeds and stored bump of
+/// `withdrawal_intent`, and that its Ephemeral ATA is the PDA of its stored owner and
+/// mint, which the seeds alone do not cover.
+#[inline(always)]
+pub fn check_withdrawal_intent(
+    withdrawal_intent_info: &AccountView,
+    withdrawal_intent: &WithdrawalIntent,
+) -> ProgramResult {
+    check_address(
+        withdrawal_intent_info,
+        &[
+            pda::WITHDRAWAL_INTENT_SEED,
+            withdrawal_intent.ephemeral_ata.as_ref(),
+            &[withdrawal_intent.header.bump],
+        ],
+    )?;
+    if pda::find_ephemeral_ata(&withdrawal_intent.owner, &withdrawal_intent.mint).0
+        != withdrawal_intent.ephemeral_ata
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+#[inline(always)]
+fn check_address(account_info: &AccountView, seeds: &[&[u8]]) -> ProgramResult {
+    let address = Address::create_program_address(seeds, &ephemeral_spl_api::program::id_address())
+        .map_err(|_| ProgramError::InvalidSeeds)?;
+    if address != *account_info.address() {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use {
+    ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_mut_unchecked},
+    pinocchio::{AccountView, ProgramResult},
+};
+
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `ApproveEphemeral`.
+    pub struct ApproveEphemeralAccounts {
+        /// Owner of the Ephemeral ATA.
+        owner: [signer],
+        /// Ephemeral ATA (PDA [owner, mint]).
+        ephemeral_ata: [writable, ephemeral_ata],
+        /// Delegate allowed to transfer on behalf of the owner.
+        delegate: [],
+    }
+}
+
+#[inline(always)]
+pub fn process_approve_ephemeral(accounts: &[AccountView], amount: u64) -> ProgramResult {
+    let ApproveEphemeralAccounts {
+        owner,
+        ephemeral_ata: ephemeral_ata_info,
+        delegate: delegate_info,
+    } = ApproveEphemeralAccounts::try_from(accounts)?;
+
+    let ephemeral_ata =
+        unsafe { load_mut_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked_mut())? };
+
+    if ephemeral_ata.owner != *owner.address() {
+        return Err(EphemeralSplError::OwnerMismatch.into());
+    }
+
+    ephemeral_ata.set_delegate(delegate_info.address(), amount);
+
+    Ok(())
+}
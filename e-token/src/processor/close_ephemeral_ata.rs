@@ -0,0 +1,67 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use {
+    ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_unchecked},
+    pinocchio::{error::ProgramError, AccountView, ProgramResult},
+};
+
+use crate::{pda::find_pending_deposit, validation::accounts};
+
+accounts! {
+    /// Accounts of `CloseEphemeralAta`.
+    pub struct CloseEphemeralAtaAccounts {
+        /// Owner of the Ephemeral ATA.
+        owner: [signer],
+        /// Ephemeral ATA (PDA derived from [owner, mint]). A delegated eata is owned
+        /// by the delegation program and must be undelegated first.
+        ephemeral_ata: [writable, undelegated, ephemeral_ata],
+        /// Destination account receiving the reclaimed lamports.
+        destination: [writable],
+        /// Pending deposits of the Ephemeral ATA (PDA ["pending-deposit",
+        /// ephemeral_ata]); must not exist.
+        pending_deposit: [],
+    }
+}
+
+/// Close an empty Ephemeral ATA without pending deposits and send its rent lamports to a destination account.
+#[inline(always)]
+pub fn process_close_ephemeral_ata(accounts: &[AccountView]) -> ProgramResult {
+    let CloseEphemeralAtaAccounts {
+        owner,
+        ephemeral_ata: ephemeral_ata_info,
+        destination: destination_info,
+        pending_deposit: pending_deposit_info,
+    } = CloseEphemeralAtaAccounts::try_from(accounts)?;
+
+    if ephemeral_ata_info.address() == destination_info.address() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    {
+        let ephemeral_ata =
+            unsafe { load_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked())? };
+
+        if ephemeral_ata.owner != *owner.address() {
+            return Err(EphemeralSplError::OwnerMismatch.into());
+        }
+
+        // Closing must never discard a balance backed by the vault
+        if ephemeral_ata.amount != 0 {
+            return Err(EphemeralSplError::NonZeroBalance.into());
+        }
+    }
+
+    // Nor deposits queued while it was delegated and not credited yet
+    find_pending_deposit(pending_deposit_info, ephemeral_ata_info.address())?;
+    if !pending_deposit_info.is_data_empty() {
+        return Err(EphemeralSplError::NonZeroBalance.into());
+    }
+
+    let destination_lamports = destination_info
+        .lamports()
+        .checked_add(ephemeral_ata_info.lamports())
+        .ok_or(EphemeralSplError::Overflow)?;
+    destination_info.set_lamports(destination_lamports);
+    ephemeral_ata_info.set_lamports(0);
+
+    ephemeral_ata_info.close()
+}
@@ -0,0 +1,57 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::event::EventKind;
+use ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_unchecked};
+use pinocchio::{AccountView, ProgramResult};
+
+use crate::event::emit;
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `CommitEphemeralAta`.
+    pub struct CommitEphemeralAtaAccounts {
+        /// Owner of the Ephemeral ATA.
+        payer: [signer],
+        /// Ephemeral ATA account (PDA derived from [payer, mint]).
+        ephemeral_ata: [writable, ephemeral_ata],
+        /// Magic context account (as required by the delegation program).
+        magic_context: [writable],
+        /// Magic program.
+        magic_program: [magic_program],
+    }
+}
+
+/// Schedule a commit of a delegated Ephemeral ATA, checkpointing its balance on the
+/// base layer while it stays delegated to the ephemeral rollup.
+pub fn process_commit_ephemeral_ata(accounts: &[AccountView]) -> ProgramResult {
+    let CommitEphemeralAtaAccounts {
+        payer,
+        ephemeral_ata: ephemeral_ata_info,
+        magic_context,
+        magic_program,
+    } = CommitEphemeralAtaAccounts::try_from(accounts)?;
+
+    // Scope the borrow so it's released before the CPI.
+    {
+        let ephemeral_ata =
+            unsafe { load_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked())? };
+        if ephemeral_ata.owner != *payer.address() {
+            return Err(EphemeralSplError::OwnerMismatch.into());
+        }
+
+        emit(
+            EventKind::Commit,
+            ephemeral_ata_info.address(),
+            ephemeral_ata,
+            0,
+        );
+    }
+
+    ephemeral_rollups_pinocchio::instruction::commit_accounts(
+        payer,
+        &[ephemeral_ata_info.clone()],
+        magic_context,
+        magic_program,
+        None,
+        None,
+    )
+}
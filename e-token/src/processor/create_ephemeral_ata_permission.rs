@@ -1,57 +1,56 @@
-use core::marker::PhantomData;
 use ephemeral_rollups_pinocchio::acl::{
     consts::PERMISSION_PROGRAM_ID,
     instruction::CreatePermissionCpiBuilder,
     pda::permission_pda_from_permissioned_account,
     types::{Member, MemberFlags, MembersArgs},
 };
-use ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_unchecked, Initializable};
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::event::EventKind;
+use ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_unchecked};
 use pinocchio::{error::ProgramError, AccountView, ProgramResult};
 
+use crate::event::emit;
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `CreateEphemeralAtaPermission`.
+    pub struct CreateEphemeralAtaPermissionAccounts {
+        /// Ephemeral ATA account (PDA derived from [owner, mint]) - signer via seeds.
+        ephemeral_ata: [any_ephemeral_ata_pda],
+        /// Permission PDA (derived from ["permission:", ephemeral_ata]).
+        permission: [writable],
+        /// Payer (must match the Ephemeral ATA owner unless `flags` is zero).
+        payer: [signer],
+        /// System program.
+        system_program: [system_program],
+        /// Permission program (ACL).
+        permission_program: [permission_program],
+    }
+}
+
 #[inline(always)]
 pub fn process_create_ephemeral_ata_permission(
     accounts: &[AccountView],
-    instruction_data: &[u8],
+    flags: u8,
 ) -> ProgramResult {
-    // Expected accounts:
-    // 0. [writable] Ephemeral ATA account (PDA derived from [owner, mint]) - signer via seeds
-    // 1. [writable] Permission PDA (derived from ["permission:", ephemeral_ata])
-    // 2. [signer]   Payer (must match Ephemeral ATA owner)
-    // 3. []         System program
-    // 4. []         Permission program (ACL)
-
-    // Instruction data layout:
-    // [0] bump
-    // [1] MemberFlags bitfield encoded via MemberFlags::to_acl_flag_byte.
-    let args = CreateEphemeralAtaPermission::try_from_bytes(instruction_data)?;
-
-    let [ephemeral_ata_info, permission_info, payer_info, system_program, permission_program, ..] =
-        accounts
-    else {
-        return Err(ProgramError::NotEnoughAccountKeys);
-    };
+    // `flags` is the MemberFlags bitfield encoded via MemberFlags::to_acl_flag_byte.
 
-    if !payer_info.is_signer() {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
-    if *permission_program.address() != PERMISSION_PROGRAM_ID {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    let CreateEphemeralAtaPermissionAccounts {
+        ephemeral_ata: ephemeral_ata_info,
+        permission: permission_info,
+        payer: payer_info,
+        system_program,
+        ..
+    } = CreateEphemeralAtaPermissionAccounts::try_from(accounts)?;
 
     let ephemeral_ata =
         unsafe { load_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked())? };
 
-    if !ephemeral_ata.is_initialized() {
-        return Err(ProgramError::InvalidAccountData);
-    }
-    let flag_byte = args.flag_byte();
-
     // Valid in 2 cases:
     // - Payer is the owner of the eata
     // - Permisionless, but permission are default (only readable for eata owner)
-    if ephemeral_ata.owner != *payer_info.address() && flag_byte != 0 {
-        return Err(ProgramError::IncorrectAuthority);
+    if ephemeral_ata.owner != *payer_info.address() && flags != 0 {
+        return Err(EphemeralSplError::OwnerMismatch.into());
     }
 
     let expected_permission =
@@ -66,7 +65,7 @@ pub fn process_create_ephemeral_ata_permission(
         return Ok(());
     }
 
-    let mut members_flag = MemberFlags::from_acl_flag_byte(flag_byte);
+    let mut members_flag = MemberFlags::from_acl_flag_byte(flags);
     members_flag.set(MemberFlags::AUTHORITY);
     let members_buf = [Member {
         flags: members_flag,
@@ -86,36 +85,16 @@ pub fn process_create_ephemeral_ata_permission(
     );
     builder
         .seeds(&[ephemeral_ata.owner.as_ref(), ephemeral_ata.mint.as_ref()])
-        .bump(args.bump())
+        .bump(ephemeral_ata.header.bump)
         .members(members_args)
-        .invoke()
-}
-
-pub struct CreateEphemeralAtaPermission<'a> {
-    raw: *const u8,
-    _data: PhantomData<&'a [u8]>,
-}
-
-impl CreateEphemeralAtaPermission<'_> {
-    #[inline]
-    pub fn try_from_bytes(bytes: &[u8]) -> Result<CreateEphemeralAtaPermission, ProgramError> {
-        if bytes.len() < 2 {
-            return Err(ProgramError::InvalidInstructionData);
-        }
+        .invoke()?;
 
-        Ok(CreateEphemeralAtaPermission {
-            raw: bytes.as_ptr(),
-            _data: PhantomData,
-        })
-    }
-
-    #[inline]
-    pub fn bump(&self) -> u8 {
-        unsafe { *self.raw }
-    }
+    emit(
+        EventKind::PermissionCreate,
+        ephemeral_ata_info.address(),
+        ephemeral_ata,
+        0,
+    );
 
-    #[inline]
-    pub fn flag_byte(&self) -> u8 {
-        unsafe { *self.raw.add(1) }
-    }
+    Ok(())
 }
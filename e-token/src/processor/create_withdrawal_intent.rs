@@ -0,0 +1,96 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::event::EventKind;
+use ephemeral_spl_api::state::{
+    ephemeral_ata::EphemeralAta, load_mut_unchecked, withdrawal_intent::WithdrawalIntent,
+};
+use pinocchio::{error::ProgramError, AccountView, ProgramResult};
+
+use crate::event::emit;
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `CreateWithdrawalIntent`.
+    pub struct CreateWithdrawalIntentAccounts {
+        /// Owner of the Ephemeral ATA.
+        owner: [signer],
+        /// Ephemeral ATA debited by the intent (PDA [owner, mint]).
+        ephemeral_ata: [writable, ephemeral_ata],
+        /// Withdrawal intent of the Ephemeral ATA (PDA ["withdrawal-intent",
+        /// ephemeral_ata]).
+        withdrawal_intent: [writable, withdrawal_intent],
+        /// Magic context account (as required by the delegation program).
+        magic_context: [writable],
+        /// Magic program.
+        magic_program: [magic_program],
+    }
+}
+
+/// Run in the ephemeral rollup; move `amount` from the Ephemeral ATA to its
+/// withdrawal intent, then commit the Ephemeral ATA and commit and undelegate the
+/// intent.
+///
+/// The Ephemeral ATA stays delegated. Once the intent is back on the base layer,
+/// `SettleWithdrawalIntent` pays it from the vault; until it is delegated again with
+/// `DelegateWithdrawalIntent`, no further intent can be created.
+pub fn process_create_withdrawal_intent(accounts: &[AccountView], amount: u64) -> ProgramResult {
+    let CreateWithdrawalIntentAccounts {
+        owner,
+        ephemeral_ata: ephemeral_ata_info,
+        withdrawal_intent: withdrawal_intent_info,
+        magic_context,
+        magic_program,
+    } = CreateWithdrawalIntentAccounts::try_from(accounts)?;
+
+    {
+        let ephemeral_ata = unsafe {
+            load_mut_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked_mut())?
+        };
+        let withdrawal_intent = unsafe {
+            load_mut_unchecked::<WithdrawalIntent>(withdrawal_intent_info.borrow_unchecked_mut())?
+        };
+
+        if ephemeral_ata.owner != *owner.address() {
+            return Err(EphemeralSplError::OwnerMismatch.into());
+        }
+        if withdrawal_intent.ephemeral_ata != *ephemeral_ata_info.address() {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        ephemeral_ata.amount = ephemeral_ata
+            .amount
+            .checked_sub(amount)
+            .ok_or(EphemeralSplError::InsufficientFunds)?;
+        withdrawal_intent.amount = withdrawal_intent
+            .amount
+            .checked_add(amount)
+            .ok_or(EphemeralSplError::Overflow)?;
+
+        emit(
+            EventKind::WithdrawalIntent,
+            ephemeral_ata_info.address(),
+            ephemeral_ata,
+            amount,
+        );
+    }
+
+    // Commit the debit of the Ephemeral ATA with the intent, so the base layer never
+    // holds an intent whose amount is still counted in the Ephemeral ATA
+    ephemeral_rollups_pinocchio::instruction::commit_accounts(
+        owner,
+        &[ephemeral_ata_info.clone()],
+        magic_context,
+        magic_program,
+        None,
+        None,
+    )?;
+
+    // The intent snapshot is checked by the undelegation callback when it is restored
+    ephemeral_rollups_pinocchio::instruction::commit_and_undelegate_accounts(
+        owner,
+        &[withdrawal_intent_info.clone()],
+        magic_context,
+        magic_program,
+        None,
+        None,
+    )
+}
@@ -0,0 +1,70 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use {
+    ephemeral_spl_api::{
+        event::EventKind,
+        state::{ephemeral_ata::EphemeralAta, load_mut_unchecked, pending_deposit::PendingDeposit},
+    },
+    pinocchio::{error::ProgramError, AccountView, ProgramResult},
+};
+
+use crate::{event::emit, validation::accounts};
+
+accounts! {
+    /// Accounts of `CreditPendingDeposit`.
+    pub struct CreditPendingDepositAccounts {
+        /// Ephemeral ATA credited with the pending deposits; it must be undelegated.
+        ephemeral_ata: [writable, undelegated, ephemeral_ata],
+        /// Pending deposits of the Ephemeral ATA (PDA ["pending-deposit", ephemeral_ata]).
+        pending_deposit: [writable, pending_deposit],
+        /// Payer that funded the pending deposit account, receiving its rent back.
+        payer: [writable],
+    }
+}
+
+/// Credit the deposits queued while the Ephemeral ATA was delegated and close the
+/// pending deposit account. Permissionless: the tokens are already in the vault.
+#[inline(always)]
+pub fn process_credit_pending_deposit(accounts: &[AccountView]) -> ProgramResult {
+    let CreditPendingDepositAccounts {
+        ephemeral_ata: ephemeral_ata_info,
+        pending_deposit: pending_deposit_info,
+        payer: payer_info,
+    } = CreditPendingDepositAccounts::try_from(accounts)?;
+
+    let amount = {
+        let pending_deposit = unsafe {
+            load_mut_unchecked::<PendingDeposit>(pending_deposit_info.borrow_unchecked_mut())?
+        };
+        if pending_deposit.ephemeral_ata != *ephemeral_ata_info.address() {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if pending_deposit.payer != *payer_info.address() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        pending_deposit.amount
+    };
+
+    let ephemeral_ata =
+        unsafe { load_mut_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked_mut())? };
+    ephemeral_ata.amount = ephemeral_ata
+        .amount
+        .checked_add(amount)
+        .ok_or(EphemeralSplError::Overflow)?;
+
+    let payer_lamports = payer_info
+        .lamports()
+        .checked_add(pending_deposit_info.lamports())
+        .ok_or(EphemeralSplError::Overflow)?;
+    payer_info.set_lamports(payer_lamports);
+    pending_deposit_info.set_lamports(0);
+    pending_deposit_info.close()?;
+
+    emit(
+        EventKind::Deposit,
+        ephemeral_ata_info.address(),
+        ephemeral_ata,
+        amount,
+    );
+
+    Ok(())
+}
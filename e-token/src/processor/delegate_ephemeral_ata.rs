@@ -1,44 +1,117 @@
 use ephemeral_rollups_pinocchio::instruction::DelegateAccountCpiBuilder;
 use ephemeral_rollups_pinocchio::types::DelegateConfig;
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::event::EventKind;
 use ephemeral_spl_api::state::ephemeral_ata::EphemeralAta;
 use ephemeral_spl_api::state::load_mut_unchecked;
-use pinocchio::{error::ProgramError, AccountView, Address, ProgramResult};
+use pinocchio::{AccountView, Address, ProgramResult};
 
+use crate::event::emit;
+use crate::validation::{accounts, signer};
+
+accounts! {
+    /// Accounts of `DelegateEphemeralAta`.
+    pub struct DelegateEphemeralAtaAccounts {
+        /// Payer of the delegation accounts; may differ from the owner.
+        payer: [signer],
+        /// Ephemeral ATA account (PDA derived from [owner, mint]) - signer via seeds.
+        ephemeral_ata: [writable, ephemeral_ata_pda],
+        /// Owner program (the program owning the delegated PDA).
+        owner_program: [this_program],
+        /// Buffer account (used by the delegation program).
+        buffer: [writable],
+        /// Delegation record account.
+        delegation_record: [writable],
+        /// Delegation metadata account.
+        delegation_metadata: [writable],
+        /// Delegation program.
+        delegation_program: [delegation_program],
+        /// System program.
+        system_program: [system_program],
+    }
+}
+
+/// Delegate an Ephemeral ATA to the ephemeral rollup.
+///
+/// The owner of the Ephemeral ATA must sign. It is passed as a trailing account after
+/// the system program; when omitted, the payer is taken as the owner.
+///
+/// `validator` and `commit_frequency_ms` override the matching `DelegateConfig` fields;
+/// these are the only delegation options the delegation program currently exposes.
 pub fn process_delegate_ephemeral_ata(
     accounts: &[AccountView],
-    instruction_data: &[u8],
+    validator: Option<&[u8; 32]>,
+    commit_frequency_ms: Option<u32>,
 ) -> ProgramResult {
-    // Expected accounts (in order used below):
-    // 0. [signer]   Payer (seed used to derive Ephemeral ATA PDA)
-    // 1. [writable] Ephemeral ATA account (PDA derived from [payer, mint]) - signer via seeds
-    // 2. []         Owner program (the program owning the delegated PDA)
-    // 3. [writable] Buffer account (used by the delegation program)
-    // 4. [writable] Delegation record account
-    // 5. [writable] Delegation metadata account
-    // 6. []         Delegation program
-    // 7. []         System program
+    let DelegateEphemeralAtaAccounts {
+        payer: payer_info,
+        ephemeral_ata: ephemeral_ata_info,
+        owner_program,
+        buffer: buffer_acc,
+        delegation_record,
+        delegation_metadata,
+        system_program,
+        ..
+    } = DelegateEphemeralAtaAccounts::try_from(accounts)?;
 
-    let args = DelegateArgs::try_from_bytes(instruction_data)?;
+    let owner_info = accounts.get(8).unwrap_or(payer_info);
+    signer(owner_info)?;
 
-    let [payer_info, ephemeral_ata_info, owner_program, buffer_acc, delegation_record, delegation_metadata, _delegation_program, system_program] =
-        accounts
-    else {
-        return Err(ProgramError::NotEnoughAccountKeys);
+    let delegation = DelegationAccounts {
+        ephemeral_ata: ephemeral_ata_info,
+        buffer: buffer_acc,
+        delegation_record,
+        delegation_metadata,
     };
+    delegate(
+        payer_info,
+        owner_info,
+        &delegation,
+        owner_program,
+        system_program,
+        validator,
+        commit_frequency_ms,
+    )
+}
 
-    // Load Ephemeral ATA account
+accounts! {
+    /// Accounts delegated for one Ephemeral ATA.
+    pub struct DelegationAccounts {
+        /// Ephemeral ATA account (PDA derived from [owner, mint]) - signer via seeds.
+        ephemeral_ata: [writable, ephemeral_ata_pda],
+        /// Buffer account (used by the delegation program).
+        buffer: [writable],
+        /// Delegation record account.
+        delegation_record: [writable],
+        /// Delegation metadata account.
+        delegation_metadata: [writable],
+    }
+}
+
+/// Delegate the Ephemeral ATA of `delegation`, which must belong to `owner`, a signer.
+pub(crate) fn delegate(
+    payer: &AccountView,
+    owner: &AccountView,
+    delegation: &DelegationAccounts,
+    owner_program: &AccountView,
+    system_program: &AccountView,
+    validator: Option<&[u8; 32]>,
+    commit_frequency_ms: Option<u32>,
+) -> ProgramResult {
+    let ephemeral_ata_info = delegation.ephemeral_ata;
     let ephemeral_ata =
         unsafe { load_mut_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked_mut())? };
+    if ephemeral_ata.owner != *owner.address() {
+        return Err(EphemeralSplError::OwnerMismatch.into());
+    }
 
-    let config = DelegateConfig {
-        validator: args.validator().map(Address::new_from_array),
-        ..DelegateConfig::default()
-    };
+    let config = delegate_config(validator, commit_frequency_ms);
 
     #[allow(clippy::clone_on_copy)]
     let mint = ephemeral_ata.mint.clone();
     #[allow(clippy::clone_on_copy)]
     let owner = ephemeral_ata.owner.clone();
+    let bump = ephemeral_ata.header.bump;
     let seeds: &[&[u8]] = &[owner.as_ref(), mint.as_ref()];
 
     #[cfg(feature = "logging")]
@@ -46,53 +119,37 @@ pub fn process_delegate_ephemeral_ata(
         pinocchio_log::log!("Delegating eata");
     }
 
+    emit(
+        EventKind::Delegate,
+        ephemeral_ata_info.address(),
+        ephemeral_ata,
+        0,
+    );
+
     DelegateAccountCpiBuilder::new(
-        payer_info,
+        payer,
         ephemeral_ata_info,
         owner_program,
-        buffer_acc,
-        delegation_record,
-        delegation_metadata,
+        delegation.buffer,
+        delegation.delegation_record,
+        delegation.delegation_metadata,
         system_program,
     )
     .seeds(seeds)
-    .bump(args.bump())
+    .bump(bump)
     .config(config)
     .invoke()
 }
 
-pub struct DelegateArgs {
-    bump: u8,
-    validator: Option<[u8; 32]>,
-}
-
-impl DelegateArgs {
-    #[inline]
-    pub fn try_from_bytes(bytes: &[u8]) -> Result<DelegateArgs, ProgramError> {
-        if bytes.is_empty() {
-            return Err(ProgramError::InvalidInstructionData);
-        }
-        let bump = bytes[0];
-        let rest = &bytes[1..];
-        let validator = if rest.is_empty() {
-            None
-        } else if rest.len() >= 32 {
-            let mut arr = [0u8; 32];
-            arr.copy_from_slice(&rest[..32]);
-            Some(arr)
-        } else {
-            return Err(ProgramError::InvalidInstructionData);
-        };
-        Ok(DelegateArgs { bump, validator })
-    }
-
-    #[inline]
-    pub fn validator(&self) -> Option<[u8; 32]> {
-        self.validator
-    }
-
-    #[inline]
-    pub fn bump(&self) -> u8 {
-        self.bump
+/// Return the `DelegateConfig` with the given overrides of its defaults.
+#[inline(always)]
+pub(crate) fn delegate_config(
+    validator: Option<&[u8; 32]>,
+    commit_frequency_ms: Option<u32>,
+) -> DelegateConfig {
+    let default = DelegateConfig::default();
+    DelegateConfig {
+        commit_frequency_ms: commit_frequency_ms.unwrap_or(default.commit_frequency_ms),
+        validator: validator.map(|validator| Address::new_from_array(*validator)),
     }
 }
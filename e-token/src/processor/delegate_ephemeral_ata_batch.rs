@@ -0,0 +1,59 @@
+use pinocchio::{error::ProgramError, AccountView, ProgramResult};
+
+use crate::processor::delegate_ephemeral_ata::{delegate, DelegationAccounts};
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `DelegateEphemeralAtaBatch`, followed by the [`DelegationAccounts`]
+    /// of each Ephemeral ATA.
+    pub struct DelegateEphemeralAtaBatchAccounts {
+        /// Payer of the delegation accounts; may differ from the owner.
+        payer: [signer],
+        /// Owner of every delegated Ephemeral ATA.
+        owner: [signer],
+        /// Owner program (the program owning the delegated PDAs).
+        owner_program: [this_program],
+        /// Delegation program.
+        delegation_program: [delegation_program],
+        /// System program.
+        system_program: [system_program],
+    }
+}
+
+/// Number of accounts in [`DelegationAccounts`].
+const DELEGATION_ACCOUNTS_LEN: usize = 4;
+
+/// Delegate several Ephemeral ATAs of one owner to the same validator, with the same
+/// options as `DelegateEphemeralAta`.
+pub fn process_delegate_ephemeral_ata_batch(
+    accounts: &[AccountView],
+    validator: Option<&[u8; 32]>,
+    commit_frequency_ms: Option<u32>,
+) -> ProgramResult {
+    let DelegateEphemeralAtaBatchAccounts {
+        payer,
+        owner,
+        owner_program,
+        system_program,
+        ..
+    } = DelegateEphemeralAtaBatchAccounts::try_from(accounts)?;
+
+    let delegations = &accounts[5..];
+    if delegations.is_empty() || delegations.len() % DELEGATION_ACCOUNTS_LEN != 0 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    for delegation in delegations.chunks_exact(DELEGATION_ACCOUNTS_LEN) {
+        delegate(
+            payer,
+            owner,
+            &DelegationAccounts::try_from(delegation)?,
+            owner_program,
+            system_program,
+            validator,
+            commit_frequency_ms,
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use ephemeral_rollups_pinocchio::instruction::DelegateAccountCpiBuilder;
+use ephemeral_spl_api::error::EphemeralSplError;
+use pinocchio::cpi::{Seed, Signer};
+use pinocchio::sysvars::rent::Rent;
+use pinocchio::sysvars::Sysvar;
+use pinocchio_system::instructions::CreateAccount;
+use {
+    ephemeral_spl_api::{
+        pda::{self, WITHDRAWAL_INTENT_SEED},
+        state::{load_mut_unchecked, withdrawal_intent::WithdrawalIntent, AccountHeader, RawType},
+    },
+    pinocchio::{AccountView, ProgramResult},
+};
+
+use crate::pda::find_withdrawal_intent;
+use crate::processor::delegate_ephemeral_ata::delegate_config;
+use crate::validation::{self, accounts};
+
+accounts! {
+    /// Accounts of `DelegateWithdrawalIntent`.
+    pub struct DelegateWithdrawalIntentAccounts {
+        /// Payer of the withdrawal intent account and of the delegation accounts.
+        payer: [writable, signer],
+        /// Owner of the Ephemeral ATA.
+        owner: [signer],
+        /// Mint of the Ephemeral ATA.
+        mint: [],
+        /// Withdrawal intent of the Ephemeral ATA (PDA ["withdrawal-intent",
+        /// ephemeral_ata]), created if missing - signer via seeds.
+        withdrawal_intent: [writable],
+        /// Owner program (the program owning the delegated PDA).
+        owner_program: [this_program],
+        /// Buffer account (used by the delegation program).
+        buffer: [writable],
+        /// Delegation record account.
+        delegation_record: [writable],
+        /// Delegation metadata account.
+        delegation_metadata: [writable],
+        /// Delegation program.
+        delegation_program: [delegation_program],
+        /// System program.
+        system_program: [system_program],
+    }
+}
+
+/// Delegate the withdrawal intent of the Ephemeral ATA of `owner` for `mint` to the
+/// ephemeral rollup, creating it on first use.
+///
+/// `CreateWithdrawalIntent` undelegates the intent, so it is delegated again after
+/// each settlement. It should be delegated to the validator of the Ephemeral ATA;
+/// `validator` and `commit_frequency_ms` are applied as for `DelegateEphemeralAta`.
+pub fn process_delegate_withdrawal_intent(
+    accounts: &[AccountView],
+    validator: Option<&[u8; 32]>,
+    commit_frequency_ms: Option<u32>,
+) -> ProgramResult {
+    let DelegateWithdrawalIntentAccounts {
+        payer: payer_info,
+        owner: owner_info,
+        mint: mint_info,
+        withdrawal_intent: withdrawal_intent_info,
+        owner_program,
+        buffer: buffer_acc,
+        delegation_record,
+        delegation_metadata,
+        system_program,
+        ..
+    } = DelegateWithdrawalIntentAccounts::try_from(accounts)?;
+
+    let (ephemeral_ata, _) = pda::find_ephemeral_ata(owner_info.address(), mint_info.address());
+
+    if !withdrawal_intent_info.owned_by(&ephemeral_spl_api::program::id_address()) {
+        let bump = find_withdrawal_intent(withdrawal_intent_info, &ephemeral_ata)?;
+
+        let bump_seed = [bump];
+        let seed = [
+            Seed::from(WITHDRAWAL_INTENT_SEED),
+            Seed::from(ephemeral_ata.as_ref()),
+            Seed::from(&bump_seed),
+        ];
+        let signer_seeds = Signer::from(&seed);
+
+        CreateAccount {
+            from: payer_info,
+            to: withdrawal_intent_info,
+            space: WithdrawalIntent::LEN as u64,
+            lamports: Rent::get()?.try_minimum_balance(WithdrawalIntent::LEN)?,
+            owner: &ephemeral_spl_api::program::id_address(),
+        }
+        .invoke_signed(&[signer_seeds])?;
+
+        let withdrawal_intent = unsafe {
+            load_mut_unchecked::<WithdrawalIntent>(withdrawal_intent_info.borrow_unchecked_mut())?
+        };
+        withdrawal_intent.header = AccountHeader::new::<WithdrawalIntent>(bump);
+        #[allow(clippy::clone_on_copy)]
+        {
+            withdrawal_intent.ephemeral_ata = ephemeral_ata.clone();
+            withdrawal_intent.owner = owner_info.address().clone();
+            withdrawal_intent.mint = mint_info.address().clone();
+        }
+        withdrawal_intent.amount = 0;
+    }
+
+    validation::withdrawal_intent(withdrawal_intent_info)?;
+    let bump = {
+        let withdrawal_intent = unsafe {
+            load_mut_unchecked::<WithdrawalIntent>(withdrawal_intent_info.borrow_unchecked_mut())?
+        };
+        if withdrawal_intent.owner != *owner_info.address() {
+            return Err(EphemeralSplError::OwnerMismatch.into());
+        }
+        if withdrawal_intent.mint != *mint_info.address() {
+            return Err(EphemeralSplError::MintMismatch.into());
+        }
+        withdrawal_intent.header.bump
+    };
+
+    #[cfg(feature = "logging")]
+    {
+        pinocchio_log::log!("Delegating withdrawal intent");
+    }
+
+    let seeds: &[&[u8]] = &[WITHDRAWAL_INTENT_SEED, ephemeral_ata.as_ref()];
+    DelegateAccountCpiBuilder::new(
+        payer_info,
+        withdrawal_intent_info,
+        owner_program,
+        buffer_acc,
+        delegation_record,
+        delegation_metadata,
+        system_program,
+    )
+    .seeds(seeds)
+    .bump(bump)
+    .config(delegate_config(validator, commit_frequency_ms))
+    .invoke()
+}
@@ -1,95 +1,239 @@
-use core::marker::PhantomData;
+use ephemeral_spl_api::error::EphemeralSplError;
+use pinocchio::cpi::{Seed, Signer};
+use pinocchio::sysvars::rent::Rent;
+use pinocchio::sysvars::Sysvar;
+use pinocchio_system::instructions::{Allocate, Assign, CreateAccount, Transfer};
 use {
-    ephemeral_spl_api::state::{
-        ephemeral_ata::EphemeralAta, global_vault::GlobalVault, load_mut_unchecked, load_unchecked,
+    ephemeral_spl_api::{
+        event::EventKind,
+        pda::PENDING_DEPOSIT_SEED,
+        program::DELEGATION_PROGRAM_ID,
+        state::{
+            ephemeral_ata::EphemeralAta, global_vault::GlobalVault, load_mut, load_mut_unchecked,
+            pending_deposit::PendingDeposit, AccountHeader, RawType,
+        },
     },
     pinocchio::{error::ProgramError, AccountView, ProgramResult},
 };
 
+use crate::event::emit;
+use crate::pda::find_pending_deposit;
+use crate::token_interface::{
+    mint_decimals, token_account_amount, transfer_checked, TOKEN_2022_PROGRAM_ID,
+};
+use crate::validation::{self, accounts};
+
+accounts! {
+    /// Accounts of `DepositSplTokens`.
+    pub struct DepositSplTokensAccounts {
+        /// Ephemeral ATA credited with the deposit (PDA [owner, mint]); writable
+        /// unless it is delegated.
+        ephemeral_ata: [],
+        /// Global Vault of the mint (PDA [mint]).
+        vault: [writable, global_vault],
+        /// Mint of the deposited tokens.
+        mint: [],
+        /// User source token account (SPL Token or Token-2022).
+        source_token_account: [writable],
+        /// Vault destination token account (ATA of the vault PDA).
+        vault_token_account: [writable, vault_token_account(vault, mint, token_program)],
+        /// Authority of the source token account.
+        authority: [signer],
+        /// Token program (SPL Token or Token-2022).
+        token_program: [token_program],
+    }
+}
+
+accounts! {
+    /// Trailing accounts of `DepositSplTokens` when the Ephemeral ATA is delegated.
+    pub struct QueueDepositAccounts {
+        /// Pending deposits of the Ephemeral ATA (PDA ["pending-deposit", ephemeral_ata]),
+        /// created on the first queued deposit.
+        pending_deposit: [writable],
+        /// Payer funding the pending deposit account.
+        payer: [writable, signer],
+        /// System program.
+        system_program: [system_program],
+    }
+}
+
 #[inline(always)]
-pub fn process_deposit_spl_tokens(
-    accounts: &[AccountView],
-    instruction_data: &[u8],
-) -> ProgramResult {
-    // Expected accounts:
-    // 0. [writable] Ephemeral ATA data account (PDA [payer, mint])
-    // 1. []         Global Vault data account (PDA [mint])
-    // 2. []         Mint account (readonly)
-    // 3. [writable] User source token account (SPL Token)
-    // 4. [writable] Vault destination token account (SPL Token)
-    // 5. [signer]   User authority (owner of source token account)
-    // 6. []         Token program
-
-    let args = DepositArgs::try_from_bytes(instruction_data)?;
-
-    let [ephemeral_ata_info, vault_info, mint_info, user_source_token_acc, vault_token_acc, user_authority, ..] =
-        accounts
-    else {
-        return Err(ProgramError::NotEnoughAccountKeys);
-    };
+pub fn process_deposit_spl_tokens(accounts: &[AccountView], amount: u64) -> ProgramResult {
+    let DepositSplTokensAccounts {
+        ephemeral_ata: ephemeral_ata_info,
+        vault: vault_info,
+        mint: mint_info,
+        source_token_account,
+        vault_token_account,
+        authority,
+        token_program,
+    } = DepositSplTokensAccounts::try_from(accounts)?;
+
+    // A delegated Ephemeral ATA cannot be written on the base layer: the deposit is
+    // queued and credited once the account is undelegated
+    let delegated = ephemeral_ata_info.owned_by(&DELEGATION_PROGRAM_ID);
+    if delegated {
+        validation::delegated_ephemeral_ata(ephemeral_ata_info)?;
+    } else {
+        validation::writable(ephemeral_ata_info)?;
+        validation::ephemeral_ata(ephemeral_ata_info)?;
+    }
 
-    // Validate EphemeralAta account
     let ephemeral_ata =
         unsafe { load_mut_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked_mut())? };
-
-    // Validate Vault data account
-    let vault = unsafe { load_unchecked::<GlobalVault>(vault_info.borrow_unchecked())? };
+    let vault = unsafe { load_mut_unchecked::<GlobalVault>(vault_info.borrow_unchecked_mut())? };
 
     // Check mint consistency
     if ephemeral_ata.mint != *mint_info.address() || vault.mint != *mint_info.address() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(EphemeralSplError::MintMismatch.into());
     }
 
-    // Perform the actual SPL Token transfer via CPI using pinocchio-token
-    // Read mint decimals and invoke transfer_checked
-    let decimals = pinocchio_token::state::Mint::from_account_view(mint_info)
-        .map_err(|_| ProgramError::InvalidAccountData)?
-        .decimals();
+    // Read mint decimals, rejecting mints the vault cannot hold safely
+    let decimals = mint_decimals(mint_info, token_program)?;
 
-    pinocchio_token::instructions::TransferChecked {
-        mint: mint_info,
-        from: user_source_token_acc,
-        to: vault_token_acc,
-        amount: args.amount(),
-        authority: user_authority,
+    // Token-2022 transfer fees are withheld in the destination account, so
+    // only credit what the vault actually received
+    let is_token_2022 = *token_program.address() == TOKEN_2022_PROGRAM_ID;
+    let vault_balance_before = if is_token_2022 {
+        token_account_amount(vault_token_account, token_program)?
+    } else {
+        0
+    };
+
+    transfer_checked(
+        source_token_account,
+        mint_info,
+        vault_token_account,
+        authority,
+        amount,
         decimals,
+        token_program,
+        &[],
+    )?;
+
+    let received = if is_token_2022 {
+        token_account_amount(vault_token_account, token_program)?
+            .checked_sub(vault_balance_before)
+            .ok_or(EphemeralSplError::Overflow)?
+    } else {
+        amount
+    };
+
+    // Track what the vault owes to Ephemeral ATA holders
+    vault.total_deposited = vault
+        .total_deposited
+        .checked_add(received)
+        .ok_or(EphemeralSplError::Overflow)?;
+
+    if delegated {
+        queue_deposit(&accounts[7..], ephemeral_ata_info, received)?;
+
+        emit(
+            EventKind::DepositQueued,
+            ephemeral_ata_info.address(),
+            ephemeral_ata,
+            received,
+        );
+        return Ok(());
     }
-    .invoke()?;
 
     // Safely increase the amount in the EphemeralAta
     ephemeral_ata.amount = ephemeral_ata
         .amount
-        .checked_add(args.amount())
-        .ok_or(ProgramError::InvalidArgument)?;
+        .checked_add(received)
+        .ok_or(EphemeralSplError::Overflow)?;
+
+    emit(
+        EventKind::Deposit,
+        ephemeral_ata_info.address(),
+        ephemeral_ata,
+        received,
+    );
 
     Ok(())
 }
 
-/// Instruction data for the `DepositSplTokens` instruction.
-pub struct DepositArgs<'a> {
-    raw: *const u8,
-    _data: PhantomData<&'a [u8]>,
-}
+/// Add `amount` to the pending deposits of the delegated `ephemeral_ata_info`,
+/// creating the pending deposit account on the first queued deposit.
+#[inline(always)]
+fn queue_deposit(
+    accounts: &[AccountView],
+    ephemeral_ata_info: &AccountView,
+    amount: u64,
+) -> ProgramResult {
+    let QueueDepositAccounts {
+        pending_deposit: pending_deposit_info,
+        payer: payer_info,
+        ..
+    } = QueueDepositAccounts::try_from(accounts)?;
 
-impl DepositArgs<'_> {
-    #[inline]
-    pub fn try_from_bytes(bytes: &[u8]) -> Result<DepositArgs, ProgramError> {
-        if bytes.len() < 8 {
-            return Err(ProgramError::InvalidInstructionData);
+    if !pending_deposit_info.owned_by(&ephemeral_spl_api::program::id_address()) {
+        let bump = find_pending_deposit(pending_deposit_info, ephemeral_ata_info.address())?;
+
+        let bump_seed = [bump];
+        let seed = [
+            Seed::from(PENDING_DEPOSIT_SEED),
+            Seed::from(ephemeral_ata_info.address().as_ref()),
+            Seed::from(&bump_seed),
+        ];
+        let signers = [Signer::from(&seed)];
+        let lamports = Rent::get()?.try_minimum_balance(PendingDeposit::LEN)?;
+
+        if pending_deposit_info.lamports() == 0 {
+            CreateAccount {
+                from: payer_info,
+                to: pending_deposit_info,
+                space: PendingDeposit::LEN as u64,
+                lamports,
+                owner: &ephemeral_spl_api::program::id_address(),
+            }
+            .invoke_signed(&signers)?;
+        } else {
+            // Anyone can send lamports to the PDA, which would make CreateAccount fail
+            // and block deposits; top it up to rent exemption and take it over instead
+            let top_up = lamports.saturating_sub(pending_deposit_info.lamports());
+            if top_up > 0 {
+                Transfer {
+                    from: payer_info,
+                    to: pending_deposit_info,
+                    lamports: top_up,
+                }
+                .invoke()?;
+            }
+            Allocate {
+                account: pending_deposit_info,
+                space: PendingDeposit::LEN as u64,
+            }
+            .invoke_signed(&signers)?;
+            Assign {
+                account: pending_deposit_info,
+                owner: &ephemeral_spl_api::program::id_address(),
+            }
+            .invoke_signed(&signers)?;
         }
-        Ok(DepositArgs {
-            raw: bytes.as_ptr(),
-            _data: PhantomData,
-        })
-    }
 
-    #[inline]
-    pub fn amount(&self) -> u64 {
-        // read LE u64 from bytes[0..8]
-        let mut buf = [0u8; 8];
-        unsafe {
-            core::ptr::copy_nonoverlapping(self.raw, buf.as_mut_ptr(), 8);
+        let pending_deposit = unsafe {
+            load_mut_unchecked::<PendingDeposit>(pending_deposit_info.borrow_unchecked_mut())?
+        };
+        pending_deposit.header = AccountHeader::new::<PendingDeposit>(bump);
+        #[allow(clippy::clone_on_copy)]
+        {
+            pending_deposit.ephemeral_ata = ephemeral_ata_info.address().clone();
+            pending_deposit.payer = payer_info.address().clone();
         }
-        u64::from_le_bytes(buf)
+        pending_deposit.amount = 0;
+    }
+
+    let pending_deposit =
+        unsafe { load_mut::<PendingDeposit>(pending_deposit_info.borrow_unchecked_mut())? };
+    if pending_deposit.ephemeral_ata != *ephemeral_ata_info.address() {
+        return Err(ProgramError::InvalidSeeds);
     }
+
+    pending_deposit.amount = pending_deposit
+        .amount
+        .checked_add(amount)
+        .ok_or(EphemeralSplError::Overflow)?;
+
+    Ok(())
 }
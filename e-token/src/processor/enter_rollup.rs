@@ -0,0 +1,135 @@
+use pinocchio::{AccountView, ProgramResult};
+
+use crate::processor::{
+    process_create_ephemeral_ata_permission, process_delegate_ephemeral_ata,
+    process_deposit_spl_tokens, process_initialize_ephemeral_ata, process_initialize_global_vault,
+};
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `EnterRollup`, optionally followed by the permission PDA and the
+    /// permission program. Each step validates the accounts it uses.
+    pub struct EnterRollupAccounts {
+        /// Owner of the Ephemeral ATA and authority of the source token account; funds
+        /// the accounts created along the way.
+        owner: [writable, signer],
+        /// Ephemeral ATA account (PDA derived from [owner, mint]).
+        ephemeral_ata: [writable, undelegated],
+        /// Global Vault of the mint (PDA [mint]).
+        vault: [writable],
+        /// Mint of the deposited tokens.
+        mint: [],
+        /// User source token account (SPL Token or Token-2022).
+        source_token_account: [writable],
+        /// Vault token account (ATA of the vault PDA for the mint).
+        vault_token_account: [writable],
+        /// Token program (SPL Token or Token-2022).
+        token_program: [],
+        /// Associated Token Account program.
+        associated_token_program: [],
+        /// System program.
+        system_program: [],
+        /// Owner program (the program owning the delegated PDA).
+        owner_program: [],
+        /// Buffer account (used by the delegation program).
+        buffer: [writable],
+        /// Delegation record account.
+        delegation_record: [writable],
+        /// Delegation metadata account.
+        delegation_metadata: [writable],
+        /// Delegation program.
+        delegation_program: [],
+    }
+}
+
+/// Bring an Ephemeral ATA into the ephemeral rollup: create the Ephemeral ATA and the
+/// Global Vault if missing, deposit `amount`, create the permission when its accounts
+/// are passed, and delegate.
+///
+/// Runs the processors of `InitializeEphemeralAta`, `InitializeGlobalVault`,
+/// `DepositSplTokens`, `CreateEphemeralAtaPermission` and `DelegateEphemeralAta` in
+/// turn, so the outcome is the same as sending those instructions.
+pub fn process_enter_rollup(
+    accounts: &[AccountView],
+    amount: u64,
+    flags: u8,
+    validator: Option<&[u8; 32]>,
+    commit_frequency_ms: Option<u32>,
+) -> ProgramResult {
+    let EnterRollupAccounts {
+        owner,
+        ephemeral_ata,
+        vault,
+        mint,
+        source_token_account,
+        vault_token_account,
+        token_program,
+        associated_token_program,
+        system_program,
+        owner_program,
+        buffer,
+        delegation_record,
+        delegation_metadata,
+        delegation_program,
+    } = EnterRollupAccounts::try_from(accounts)?;
+
+    process_initialize_ephemeral_ata(&[
+        ephemeral_ata.clone(),
+        owner.clone(),
+        owner.clone(),
+        mint.clone(),
+        system_program.clone(),
+    ])?;
+
+    process_initialize_global_vault(&[
+        vault.clone(),
+        owner.clone(),
+        mint.clone(),
+        system_program.clone(),
+        vault_token_account.clone(),
+        token_program.clone(),
+        associated_token_program.clone(),
+    ])?;
+
+    process_deposit_spl_tokens(
+        &[
+            ephemeral_ata.clone(),
+            vault.clone(),
+            mint.clone(),
+            source_token_account.clone(),
+            vault_token_account.clone(),
+            owner.clone(),
+            token_program.clone(),
+        ],
+        amount,
+    )?;
+
+    if let [permission, permission_program, ..] = &accounts[14..] {
+        process_create_ephemeral_ata_permission(
+            &[
+                ephemeral_ata.clone(),
+                permission.clone(),
+                owner.clone(),
+                system_program.clone(),
+                permission_program.clone(),
+            ],
+            flags,
+        )?;
+    }
+
+    process_delegate_ephemeral_ata(
+        &[
+            owner.clone(),
+            ephemeral_ata.clone(),
+            owner_program.clone(),
+            buffer.clone(),
+            delegation_record.clone(),
+            delegation_metadata.clone(),
+            delegation_program.clone(),
+            system_program.clone(),
+            owner.clone(),
+        ],
+        validator,
+        commit_frequency_ms,
+    )
+}
@@ -0,0 +1,107 @@
+use ephemeral_rollups_pinocchio::intent_bundle::{
+    ActionArgs, CallHandler, MagicIntentBundleBuilder, ShortAccountMeta,
+};
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::instruction::SETTLE_EXIT;
+use ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_unchecked};
+use pinocchio::{AccountView, ProgramResult};
+
+use crate::processor::undelegate_ephemeral_ata::prepare_undelegation;
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `ExitRollup`.
+    pub struct ExitRollupAccounts {
+        /// Owner of the Ephemeral ATA; also the escrow authority of the payout action.
+        payer: [signer],
+        /// Ephemeral ATA account (PDA derived from [payer, mint]).
+        ephemeral_ata: [writable, ephemeral_ata],
+        /// Magic context account (as required by the delegation program).
+        magic_context: [writable],
+        /// Magic program.
+        magic_program: [magic_program],
+        /// Global Vault of the mint (PDA [mint]), paying on the base layer.
+        vault: [],
+        /// Mint of the Ephemeral ATA.
+        mint: [],
+        /// Vault source token account (ATA of the vault PDA).
+        vault_token_account: [],
+        /// Token account of the owner receiving the balance.
+        destination_token_account: [],
+        /// Token program (SPL Token or Token-2022).
+        token_program: [token_program],
+    }
+}
+
+/// Compute units of the `SettleExit` action run on the base layer.
+const SETTLE_EXIT_COMPUTE_UNITS: u32 = 100_000;
+
+/// Size of the buffer the intent bundle is serialized into.
+const INTENT_BUNDLE_DATA_LEN: usize = 512;
+
+/// Leave the ephemeral rollup: commit and undelegate the Ephemeral ATA like
+/// `UndelegateEphemeralAta`, with a `SettleExit` action run on the base layer once it
+/// is undelegated, paying its whole balance to the owner's token account.
+///
+/// The action is signed by the escrow of the owner, the ephemeral balance PDA of the
+/// delegation program with index 255, which must exist on the base layer. The accounts
+/// of the action are only checked when it runs; if it fails, the exit flag stays set
+/// and anyone can send `SettleExit` again.
+pub fn process_exit_rollup(accounts: &[AccountView]) -> ProgramResult {
+    let ExitRollupAccounts {
+        payer,
+        ephemeral_ata: ephemeral_ata_info,
+        magic_context,
+        magic_program,
+        vault,
+        mint,
+        vault_token_account,
+        destination_token_account,
+        token_program,
+    } = ExitRollupAccounts::try_from(accounts)?;
+
+    {
+        let ephemeral_ata =
+            unsafe { load_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked())? };
+        if ephemeral_ata.mint != *mint.address() {
+            return Err(EphemeralSplError::MintMismatch.into());
+        }
+    }
+
+    prepare_undelegation(payer, ephemeral_ata_info, true)?;
+
+    // The accounts of `SettleExit`, in order
+    let settle_exit_accounts = [
+        short_account_meta(payer, false),
+        short_account_meta(ephemeral_ata_info, true),
+        short_account_meta(vault, true),
+        short_account_meta(mint, false),
+        short_account_meta(vault_token_account, true),
+        short_account_meta(destination_token_account, true),
+        short_account_meta(token_program, false),
+    ];
+    let settle_exit = [CallHandler {
+        destination_program: ephemeral_spl_api::program::id_address(),
+        escrow_authority: payer.clone(),
+        args: ActionArgs::new(&[SETTLE_EXIT]),
+        compute_units: SETTLE_EXIT_COMPUTE_UNITS,
+        accounts: &settle_exit_accounts,
+        callback: None,
+    }];
+
+    // Its snapshot is checked by the undelegation callback before the action runs
+    let mut data = [0u8; INTENT_BUNDLE_DATA_LEN];
+    MagicIntentBundleBuilder::new(payer.clone(), magic_context.clone(), magic_program.clone())
+        .commit_and_undelegate(core::slice::from_ref(ephemeral_ata_info))
+        .add_post_undelegate_actions(&settle_exit)
+        .build_and_invoke(&mut data)
+}
+
+#[inline(always)]
+fn short_account_meta(account: &AccountView, is_writable: bool) -> ShortAccountMeta {
+    #[allow(clippy::clone_on_copy)]
+    ShortAccountMeta {
+        pubkey: account.address().clone(),
+        is_writable,
+    }
+}
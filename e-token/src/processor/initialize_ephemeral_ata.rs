@@ -1,5 +1,4 @@
-use core::marker::PhantomData;
-use ephemeral_spl_api::state::RawType;
+use ephemeral_spl_api::state::{AccountHeader, RawType};
 use pinocchio::cpi::{Seed, Signer};
 use pinocchio::sysvars::rent::Rent;
 use pinocchio::sysvars::Sysvar;
@@ -7,25 +6,40 @@ use pinocchio_system::instructions::CreateAccount;
 use {
     ephemeral_spl_api::state::ephemeral_ata::EphemeralAta,
     ephemeral_spl_api::state::load_mut_unchecked,
-    pinocchio::{error::ProgramError, AccountView, ProgramResult},
+    pinocchio::{AccountView, ProgramResult},
 };
 
-#[inline(always)]
-pub fn process_initialize_ephemeral_ata(
-    accounts: &[AccountView],
-    instruction_data: &[u8],
-) -> ProgramResult {
-    // Expected accounts:
-    // 0. [writable] Ephemeral ATA account (PDA derived from [user, mint])
-    // 1. []         Payer (funding account)
-    // 2. []         User  (seed)
-    // 3. []         Mint  (seed)
+use crate::pda::find_ephemeral_ata;
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `InitializeEphemeralAta`.
+    pub struct InitializeEphemeralAtaAccounts {
+        /// Ephemeral ATA account (PDA derived from [user, mint]).
+        ephemeral_ata: [writable],
+        /// Payer funding the account creation.
+        payer: [writable, signer],
+        /// User owning the Ephemeral ATA (seed).
+        user: [],
+        /// Mint (seed).
+        mint: [],
+        /// System program.
+        system_program: [system_program],
+    }
+}
 
-    let args = InitializeEphemeralAta::try_from_bytes(instruction_data)?;
+#[inline(always)]
+pub fn process_initialize_ephemeral_ata(accounts: &[AccountView]) -> ProgramResult {
+    // The canonical bump is derived on-chain and recorded in the account; a bump
+    // byte sent by older clients is ignored.
 
-    let [ephemeral_ata_info, payer_info, user_info, mint_info, ..] = accounts else {
-        return Err(ProgramError::NotEnoughAccountKeys);
-    };
+    let InitializeEphemeralAtaAccounts {
+        ephemeral_ata: ephemeral_ata_info,
+        payer: payer_info,
+        user: user_info,
+        mint: mint_info,
+        ..
+    } = InitializeEphemeralAtaAccounts::try_from(accounts)?;
 
     unsafe {
         // Make init idempotent
@@ -37,11 +51,13 @@ pub fn process_initialize_ephemeral_ata(
         }
     }
 
-    let bump = [args.bump()];
+    let bump = find_ephemeral_ata(ephemeral_ata_info, user_info.address(), mint_info.address())?;
+
+    let bump_seed = [bump];
     let seed = [
         Seed::from(user_info.address().as_ref()),
         Seed::from(mint_info.address().as_ref()),
-        Seed::from(&bump),
+        Seed::from(&bump_seed),
     ];
     let signer_seeds = Signer::from(&seed);
 
@@ -60,34 +76,14 @@ pub fn process_initialize_ephemeral_ata(
 
     // Initialize the ephemeral ATA
     // Set the owner to the provided user; payer only funds account creation
-    ephemeral_ata.owner = user_info.address().clone();
-    ephemeral_ata.mint = mint_info.address().clone();
+    ephemeral_ata.header = AccountHeader::new::<EphemeralAta>(bump);
+    #[allow(clippy::clone_on_copy)]
+    {
+        ephemeral_ata.owner = user_info.address().clone();
+        ephemeral_ata.mint = mint_info.address().clone();
+    }
     ephemeral_ata.amount = 0;
+    ephemeral_ata.clear_delegate();
 
     Ok(())
 }
-
-/// Instruction data for the `InitializeMint` instruction.
-pub struct InitializeEphemeralAta<'a> {
-    raw: *const u8,
-    _data: PhantomData<&'a [u8]>,
-}
-
-impl InitializeEphemeralAta<'_> {
-    #[inline]
-    pub fn try_from_bytes(bytes: &[u8]) -> Result<InitializeEphemeralAta, ProgramError> {
-        if bytes.is_empty() {
-            return Err(ProgramError::InvalidInstructionData);
-        }
-
-        Ok(InitializeEphemeralAta {
-            raw: bytes.as_ptr(),
-            _data: PhantomData,
-        })
-    }
-
-    #[inline]
-    pub fn bump(&self) -> u8 {
-        unsafe { *self.raw }
-    }
-}
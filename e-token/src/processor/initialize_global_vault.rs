@@ -1,44 +1,83 @@
-use core::marker::PhantomData;
-use ephemeral_spl_api::state::RawType;
+use ephemeral_spl_api::state::{AccountHeader, RawType};
 use pinocchio::cpi::{Seed, Signer};
 use pinocchio::sysvars::rent::Rent;
 use pinocchio::sysvars::Sysvar;
+use pinocchio_associated_token_account::instructions::CreateIdempotent;
 use pinocchio_system::instructions::CreateAccount;
 use {
     ephemeral_spl_api::state::global_vault::GlobalVault,
     ephemeral_spl_api::state::load_mut_unchecked,
-    pinocchio::{error::ProgramError, AccountView, ProgramResult},
+    pinocchio::{AccountView, ProgramResult},
 };
 
-#[inline(always)]
-pub fn process_initialize_global_vault(
-    accounts: &[AccountView],
-    instruction_data: &[u8],
-) -> ProgramResult {
-    // Expected accounts:
-    // 0. [writable] Global Vault account (PDA derived from [mint])
-    // 1. [signer]   Payer (funds the account creation)
-    // 2. []         Mint  (seed)
-    // 3. []         System program
+use crate::{pda::find_global_vault, validation::accounts};
+
+accounts! {
+    /// Accounts of `InitializeGlobalVault`.
+    pub struct InitializeGlobalVaultAccounts {
+        /// Global Vault account (PDA derived from [mint]).
+        vault: [writable],
+        /// Payer funding the account creation.
+        payer: [writable, signer],
+        /// Mint (seed, SPL Token or Token-2022 mint).
+        mint: [],
+        /// System program.
+        system_program: [system_program],
+        /// Vault token account (ATA of the vault PDA for the mint).
+        vault_token_account: [writable],
+        /// Token program (SPL Token or Token-2022).
+        token_program: [token_program],
+        /// Associated Token Account program.
+        associated_token_program: [associated_token_program],
+    }
+}
 
-    let args = InitializeGlobalVault::try_from_bytes(instruction_data)?;
+#[inline(always)]
+pub fn process_initialize_global_vault(accounts: &[AccountView]) -> ProgramResult {
+    // The canonical bump is derived on-chain and recorded in the vault; a bump
+    // byte sent by older clients is ignored.
 
-    let [vault_info, payer_info, mint_info, ..] = accounts else {
-        return Err(ProgramError::NotEnoughAccountKeys);
-    };
+    let InitializeGlobalVaultAccounts {
+        vault: vault_info,
+        payer: payer_info,
+        mint: mint_info,
+        system_program,
+        vault_token_account: vault_token_acc,
+        token_program,
+        ..
+    } = InitializeGlobalVaultAccounts::try_from(accounts)?;
 
     // Make init idempotent
-    unsafe {
-        if vault_info
-            .owner()
-            .eq(&ephemeral_spl_api::program::id_address())
-        {
-            return Ok(());
-        }
+    if !vault_info.owned_by(&ephemeral_spl_api::program::id_address()) {
+        create_global_vault(vault_info, payer_info, mint_info)?;
     }
 
-    let bump = [args.bump()];
-    let seed = [Seed::from(mint_info.address().as_ref()), Seed::from(&bump)];
+    // The vault PDA is the authority of its token account, so deposits can only
+    // ever land in an account the program controls
+    CreateIdempotent {
+        funding_account: payer_info,
+        account: vault_token_acc,
+        wallet: vault_info,
+        mint: mint_info,
+        system_program,
+        token_program,
+    }
+    .invoke()
+}
+
+#[inline(always)]
+fn create_global_vault(
+    vault_info: &AccountView,
+    payer_info: &AccountView,
+    mint_info: &AccountView,
+) -> ProgramResult {
+    let bump = find_global_vault(vault_info, mint_info.address())?;
+
+    let bump_seed = [bump];
+    let seed = [
+        Seed::from(mint_info.address().as_ref()),
+        Seed::from(&bump_seed),
+    ];
     let signer_seeds = Signer::from(&seed);
 
     CreateAccount {
@@ -54,32 +93,12 @@ pub fn process_initialize_global_vault(
     let vault = unsafe { load_mut_unchecked::<GlobalVault>(vault_info.borrow_unchecked_mut())? };
 
     // Initialize the vault
-    vault.mint = mint_info.address().clone();
-
-    Ok(())
-}
-
-/// Instruction data for the `InitializeGlobalVault` instruction.
-pub struct InitializeGlobalVault<'a> {
-    raw: *const u8,
-    _data: PhantomData<&'a [u8]>,
-}
-
-impl InitializeGlobalVault<'_> {
-    #[inline]
-    pub fn try_from_bytes(bytes: &[u8]) -> Result<InitializeGlobalVault, ProgramError> {
-        if bytes.is_empty() {
-            return Err(ProgramError::InvalidInstructionData);
-        }
-
-        Ok(InitializeGlobalVault {
-            raw: bytes.as_ptr(),
-            _data: PhantomData,
-        })
+    vault.header = AccountHeader::new::<GlobalVault>(bump);
+    #[allow(clippy::clone_on_copy)]
+    {
+        vault.mint = mint_info.address().clone();
     }
+    vault.total_deposited = 0;
 
-    #[inline]
-    pub fn bump(&self) -> u8 {
-        unsafe { *self.raw }
-    }
+    Ok(())
 }
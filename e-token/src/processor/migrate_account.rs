@@ -0,0 +1,164 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use pinocchio::sysvars::rent::Rent;
+use pinocchio::sysvars::Sysvar;
+use pinocchio_system::instructions::Transfer;
+use {
+    ephemeral_spl_api::state::{
+        ephemeral_ata::EphemeralAta, global_vault::GlobalVault, load_mut_unchecked, AccountHeader,
+        RawType,
+    },
+    pinocchio::{error::ProgramError, AccountView, Address, ProgramResult},
+};
+
+use crate::{
+    pda::{find_ephemeral_ata, find_global_vault},
+    token_interface::{check_vault_token_account, token_account_amount},
+    validation::accounts,
+};
+
+accounts! {
+    /// Accounts of `MigrateAccount`.
+    pub struct MigrateAccountAccounts {
+        /// Payer (funds the additional rent).
+        payer: [signer, writable],
+        /// Ephemeral ATA or Global Vault account to migrate. A delegated account
+        /// must be undelegated before it can be migrated.
+        account: [writable, program_owned],
+        /// System program.
+        system_program: [system_program],
+    }
+}
+
+accounts! {
+    /// Trailing accounts of `MigrateAccount` for a Global Vault.
+    pub struct MigrateGlobalVaultAccounts {
+        /// Mint account.
+        mint: [],
+        /// Vault token account (ATA of the vault PDA).
+        vault_token_account: [],
+        /// Token program (SPL Token or Token-2022).
+        token_program: [token_program],
+    }
+}
+
+const HEADER_LEN: usize = core::mem::size_of::<AccountHeader>();
+
+/// Upgrade an Ephemeral ATA or Global Vault created before account versioning to
+/// the current layout, reallocating it in place. Accounts already at the current
+/// layout are left untouched.
+///
+/// Legacy vaults did not track deposits, so `total_deposited` is seeded from the
+/// balance of the vault token account. Legacy vaults also accepted any token account
+/// of the vault, but only the associated one is counted: balances backed by tokens
+/// held elsewhere fail with `ExceedsVaultDeposits` when they would exceed the count.
+/// Legacy accounts did not record their bump, so the canonical one is derived from the
+/// account seeds.
+///
+/// Expected accounts:
+/// 0. [signer, writable] Payer (funds the additional rent)
+/// 1. [writable] Ephemeral ATA or Global Vault account to migrate
+/// 2. []         System program
+///
+/// Global Vault only:
+/// 3. []         Mint account
+/// 4. []         Vault token account (ATA of the vault PDA)
+/// 5. []         Token program (SPL Token or Token-2022)
+#[inline(always)]
+pub fn process_migrate_account(accounts: &[AccountView]) -> ProgramResult {
+    let MigrateAccountAccounts {
+        payer: payer_info,
+        account: account_info,
+        ..
+    } = MigrateAccountAccounts::try_from(accounts)?;
+    let remaining = &accounts[3..];
+
+    match account_info.data_len() {
+        // Already at the current layout
+        EphemeralAta::LEN | GlobalVault::LEN => Ok(()),
+        EphemeralAta::LEGACY_LEN => migrate_ephemeral_ata(payer_info, account_info),
+        GlobalVault::LEGACY_LEN => migrate_global_vault(payer_info, account_info, remaining),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+/// Rewrite a legacy Ephemeral ATA into the current layout, funded by `payer_info`.
+#[inline(always)]
+pub(crate) fn migrate_ephemeral_ata(
+    payer_info: &AccountView,
+    ephemeral_ata_info: &AccountView,
+) -> ProgramResult {
+    // Legacy layout: owner, mint, amount. The current layout keeps them in the same
+    // order right after the header.
+    let mut legacy = [0u8; EphemeralAta::LEGACY_LEN];
+    legacy.copy_from_slice(unsafe { ephemeral_ata_info.borrow_unchecked() });
+
+    realloc(payer_info, ephemeral_ata_info, EphemeralAta::LEN)?;
+
+    let data = unsafe { ephemeral_ata_info.borrow_unchecked_mut() };
+    data[HEADER_LEN..HEADER_LEN + EphemeralAta::LEGACY_LEN].copy_from_slice(&legacy);
+
+    let ephemeral_ata = unsafe { load_mut_unchecked::<EphemeralAta>(data)? };
+    let bump = find_ephemeral_ata(
+        ephemeral_ata_info,
+        &ephemeral_ata.owner,
+        &ephemeral_ata.mint,
+    )?;
+    ephemeral_ata.header = AccountHeader::new::<EphemeralAta>(bump);
+    ephemeral_ata.clear_delegate();
+
+    Ok(())
+}
+
+#[inline(always)]
+fn migrate_global_vault(
+    payer_info: &AccountView,
+    vault_info: &AccountView,
+    remaining: &[AccountView],
+) -> ProgramResult {
+    let MigrateGlobalVaultAccounts {
+        mint: mint_info,
+        vault_token_account: vault_token_acc,
+        token_program,
+    } = MigrateGlobalVaultAccounts::try_from(remaining)?;
+
+    // Legacy layout: mint
+    let mint = Address::new_from_array(
+        unsafe { vault_info.borrow_unchecked() }
+            .try_into()
+            .map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if mint != *mint_info.address() {
+        return Err(EphemeralSplError::MintMismatch.into());
+    }
+
+    let bump = find_global_vault(vault_info, &mint)?;
+    check_vault_token_account(vault_token_acc, vault_info, mint_info, token_program)?;
+    let balance = token_account_amount(vault_token_acc, token_program)?;
+
+    realloc(payer_info, vault_info, GlobalVault::LEN)?;
+
+    let vault = unsafe { load_mut_unchecked::<GlobalVault>(vault_info.borrow_unchecked_mut())? };
+    vault.header = AccountHeader::new::<GlobalVault>(bump);
+    vault.mint = mint;
+    vault.total_deposited = balance;
+
+    Ok(())
+}
+
+/// Top up the rent from the payer and zero-extend the account data to `new_len`.
+#[inline(always)]
+fn realloc(payer_info: &AccountView, account_info: &AccountView, new_len: usize) -> ProgramResult {
+    let required = Rent::get()?.try_minimum_balance(new_len)?;
+    let top_up = required.saturating_sub(account_info.lamports());
+    if top_up > 0 {
+        Transfer {
+            from: payer_info,
+            to: account_info,
+            lamports: top_up,
+        }
+        .invoke()?;
+    }
+
+    account_info.resize(new_len)?;
+    Ok(())
+}
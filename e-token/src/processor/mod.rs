@@ -1,22 +1,54 @@
+pub mod approve_ephemeral;
+pub mod close_ephemeral_ata;
+pub mod commit_ephemeral_ata;
 pub mod create_ephemeral_ata_permission;
+pub mod create_withdrawal_intent;
+pub mod credit_pending_deposit;
 pub mod delegate_ephemeral_ata;
+pub mod delegate_ephemeral_ata_batch;
 pub mod delegate_ephemeral_ata_permission;
+pub mod delegate_withdrawal_intent;
 pub mod deposit_spl_tokens;
+pub mod enter_rollup;
+pub mod exit_rollup;
 pub mod initialize_ephemeral_ata;
 pub mod initialize_global_vault;
+pub mod migrate_account;
+pub mod reconcile_global_vault;
 pub mod reset_ephemeral_ata_permission;
+pub mod revoke_ephemeral;
+pub mod settle_exit;
+pub mod settle_withdrawal_intent;
+pub mod transfer_ephemeral;
+pub mod transfer_from_ephemeral;
 pub mod undelegate_ephemeral_ata;
 pub mod undelegate_ephemeral_ata_permission;
 pub mod undelegation_callback;
 pub mod withdraw_spl_tokens;
 
+pub use approve_ephemeral::process_approve_ephemeral;
+pub use close_ephemeral_ata::process_close_ephemeral_ata;
+pub use commit_ephemeral_ata::process_commit_ephemeral_ata;
 pub use create_ephemeral_ata_permission::process_create_ephemeral_ata_permission;
+pub use create_withdrawal_intent::process_create_withdrawal_intent;
+pub use credit_pending_deposit::process_credit_pending_deposit;
 pub use delegate_ephemeral_ata::process_delegate_ephemeral_ata;
+pub use delegate_ephemeral_ata_batch::process_delegate_ephemeral_ata_batch;
 pub use delegate_ephemeral_ata_permission::process_delegate_ephemeral_ata_permission;
+pub use delegate_withdrawal_intent::process_delegate_withdrawal_intent;
 pub use deposit_spl_tokens::process_deposit_spl_tokens;
+pub use enter_rollup::process_enter_rollup;
+pub use exit_rollup::process_exit_rollup;
 pub use initialize_ephemeral_ata::process_initialize_ephemeral_ata;
 pub use initialize_global_vault::process_initialize_global_vault;
+pub use migrate_account::process_migrate_account;
+pub use reconcile_global_vault::process_reconcile_global_vault;
 pub use reset_ephemeral_ata_permission::process_reset_ephemeral_ata_permission;
+pub use revoke_ephemeral::process_revoke_ephemeral;
+pub use settle_exit::process_settle_exit;
+pub use settle_withdrawal_intent::process_settle_withdrawal_intent;
+pub use transfer_ephemeral::process_transfer_ephemeral;
+pub use transfer_from_ephemeral::process_transfer_from_ephemeral;
 pub use undelegate_ephemeral_ata::process_undelegate_ephemeral_ata;
 pub use undelegate_ephemeral_ata_permission::process_undelegate_ephemeral_ata_permission;
 pub use undelegation_callback::process_undelegation_callback;
@@ -0,0 +1,58 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use {
+    ephemeral_spl_api::state::{global_vault::GlobalVault, load_unchecked},
+    pinocchio::{cpi::set_return_data, AccountView, ProgramResult},
+};
+
+use crate::token_interface::token_account_amount;
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `ReconcileGlobalVault`.
+    pub struct ReconcileGlobalVaultAccounts {
+        /// Global Vault data account (PDA [mint]).
+        vault: [global_vault],
+        /// Mint account.
+        mint: [],
+        /// Vault token account (ATA of the vault PDA).
+        vault_token_account: [vault_token_account(vault, mint, token_program)],
+        /// Token program (SPL Token or Token-2022).
+        token_program: [token_program],
+    }
+}
+
+/// Compare the vault token account balance with the vault's `total_deposited`
+/// counter. Permissionless and read-only; the result is reported via return data:
+///
+/// - `[0..8]`   vault token account balance (LE u64)
+/// - `[8..16]`  total deposited (LE u64)
+/// - `[16..24]` surplus, balance above total deposited (LE u64)
+/// - `[24..32]` shortfall, total deposited above balance (LE u64)
+#[inline(always)]
+pub fn process_reconcile_global_vault(accounts: &[AccountView]) -> ProgramResult {
+    let ReconcileGlobalVaultAccounts {
+        vault: vault_info,
+        mint: mint_info,
+        vault_token_account,
+        token_program,
+    } = ReconcileGlobalVaultAccounts::try_from(accounts)?;
+
+    let total_deposited = {
+        let vault = unsafe { load_unchecked::<GlobalVault>(vault_info.borrow_unchecked())? };
+        if vault.mint != *mint_info.address() {
+            return Err(EphemeralSplError::MintMismatch.into());
+        }
+        vault.total_deposited
+    };
+
+    let balance = token_account_amount(vault_token_account, token_program)?;
+
+    let mut report = [0u8; 32];
+    report[0..8].copy_from_slice(&balance.to_le_bytes());
+    report[8..16].copy_from_slice(&total_deposited.to_le_bytes());
+    report[16..24].copy_from_slice(&balance.saturating_sub(total_deposited).to_le_bytes());
+    report[24..32].copy_from_slice(&total_deposited.saturating_sub(balance).to_le_bytes());
+    set_return_data(&report);
+
+    Ok(())
+}
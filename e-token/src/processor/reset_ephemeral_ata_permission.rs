@@ -1,50 +1,50 @@
-use core::marker::PhantomData;
 use ephemeral_rollups_pinocchio::acl::{
     consts::PERMISSION_PROGRAM_ID,
     instruction::UpdatePermissionCpiBuilder,
     pda::permission_pda_from_permissioned_account,
     types::{Member, MemberFlags, MembersArgs},
 };
-use ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_unchecked, Initializable};
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::event::EventKind;
+use ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_unchecked};
 use pinocchio::{error::ProgramError, AccountView, ProgramResult};
 
+use crate::event::emit;
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `ResetEphemeralAtaPermission`.
+    pub struct ResetEphemeralAtaPermissionAccounts {
+        /// Ephemeral ATA account (PDA derived from [owner, mint]) - signer via seeds.
+        ephemeral_ata: [any_ephemeral_ata_pda],
+        /// Permission PDA (derived from ["permission:", ephemeral_ata]).
+        permission: [writable],
+        /// Owner (must match the Ephemeral ATA owner).
+        owner: [signer],
+        /// Permission program (ACL).
+        permission_program: [permission_program],
+    }
+}
+
 #[inline(always)]
 pub fn process_reset_ephemeral_ata_permission(
     accounts: &[AccountView],
-    instruction_data: &[u8],
+    flags: u8,
 ) -> ProgramResult {
-    // Expected accounts:
-    // 0. [writable] Ephemeral ATA account (PDA derived from [owner, mint]) - signer via seeds
-    // 1. [writable] Permission PDA (derived from ["permission:", ephemeral_ata])
-    // 2. [signer]   Owner (must match Ephemeral ATA owner)
-    // 3. []         Permission program (ACL)
+    // `flags` is the MemberFlags bitfield encoded via MemberFlags::to_acl_flag_byte.
 
-    // Instruction data layout:
-    // [0] bump
-    // [1] MemberFlags bitfield encoded via MemberFlags::to_acl_flag_byte.
-    let args = ResetEphemeralAtaPermission::try_from_bytes(instruction_data)?;
-
-    let [ephemeral_ata_info, permission_info, owner_info, permission_program, ..] = accounts else {
-        return Err(ProgramError::NotEnoughAccountKeys);
-    };
-
-    if !owner_info.is_signer() {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
-    if *permission_program.address() != PERMISSION_PROGRAM_ID {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    let ResetEphemeralAtaPermissionAccounts {
+        ephemeral_ata: ephemeral_ata_info,
+        permission: permission_info,
+        owner: owner_info,
+        ..
+    } = ResetEphemeralAtaPermissionAccounts::try_from(accounts)?;
 
     let ephemeral_ata =
         unsafe { load_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked())? };
 
-    if !ephemeral_ata.is_initialized() {
-        return Err(ProgramError::InvalidAccountData);
-    }
-
     if ephemeral_ata.owner != *owner_info.address() {
-        return Err(ProgramError::IncorrectAuthority);
+        return Err(EphemeralSplError::OwnerMismatch.into());
     }
 
     // TODO(GabrielePicco): pass bump once supported in the SDK
@@ -58,7 +58,7 @@ pub fn process_reset_ephemeral_ata_permission(
         return Err(ProgramError::InvalidAccountData);
     }
 
-    let mut members_flag = MemberFlags::from_acl_flag_byte(args.flag_byte());
+    let mut members_flag = MemberFlags::from_acl_flag_byte(flags);
     members_flag.set(MemberFlags::AUTHORITY);
     let members_buf = [Member {
         flags: members_flag,
@@ -76,36 +76,16 @@ pub fn process_reset_ephemeral_ata_permission(
         &PERMISSION_PROGRAM_ID,
     )
     .seeds(&[ephemeral_ata.owner.as_ref(), ephemeral_ata.mint.as_ref()])
-    .bump(args.bump())
+    .bump(ephemeral_ata.header.bump)
     .members(members_args)
-    .invoke()
-}
-
-pub struct ResetEphemeralAtaPermission<'a> {
-    raw: *const u8,
-    _data: PhantomData<&'a [u8]>,
-}
+    .invoke()?;
 
-impl ResetEphemeralAtaPermission<'_> {
-    #[inline]
-    pub fn try_from_bytes(bytes: &[u8]) -> Result<ResetEphemeralAtaPermission, ProgramError> {
-        if bytes.len() < 2 {
-            return Err(ProgramError::InvalidInstructionData);
-        }
+    emit(
+        EventKind::PermissionReset,
+        ephemeral_ata_info.address(),
+        ephemeral_ata,
+        0,
+    );
 
-        Ok(ResetEphemeralAtaPermission {
-            raw: bytes.as_ptr(),
-            _data: PhantomData,
-        })
-    }
-
-    #[inline]
-    pub fn bump(&self) -> u8 {
-        unsafe { *self.raw }
-    }
-
-    #[inline]
-    pub fn flag_byte(&self) -> u8 {
-        unsafe { *self.raw.add(1) }
-    }
+    Ok(())
 }
@@ -0,0 +1,36 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use {
+    ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_mut_unchecked},
+    pinocchio::{AccountView, ProgramResult},
+};
+
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `RevokeEphemeral`.
+    pub struct RevokeEphemeralAccounts {
+        /// Owner of the Ephemeral ATA.
+        owner: [signer],
+        /// Ephemeral ATA (PDA [owner, mint]).
+        ephemeral_ata: [writable, ephemeral_ata],
+    }
+}
+
+#[inline(always)]
+pub fn process_revoke_ephemeral(accounts: &[AccountView]) -> ProgramResult {
+    let RevokeEphemeralAccounts {
+        owner,
+        ephemeral_ata: ephemeral_ata_info,
+    } = RevokeEphemeralAccounts::try_from(accounts)?;
+
+    let ephemeral_ata =
+        unsafe { load_mut_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked_mut())? };
+
+    if ephemeral_ata.owner != *owner.address() {
+        return Err(EphemeralSplError::OwnerMismatch.into());
+    }
+
+    ephemeral_ata.clear_delegate();
+
+    Ok(())
+}
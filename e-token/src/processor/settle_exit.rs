@@ -0,0 +1,76 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_mut_unchecked};
+use pinocchio::{AccountView, ProgramResult};
+
+use crate::processor::withdraw_spl_tokens::{withdraw, WithdrawSplTokensAccounts};
+use crate::token_interface::check_token_account;
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `SettleExit`.
+    pub struct SettleExitAccounts {
+        /// Owner of the Ephemeral ATA; does not need to sign.
+        owner: [],
+        /// Ephemeral ATA back from the ephemeral rollup (PDA [owner, mint]).
+        ephemeral_ata: [writable, undelegated, ephemeral_ata],
+        /// Global Vault of the mint (PDA [mint]), signs the transfer.
+        vault: [writable, global_vault_pda],
+        /// Mint of the withdrawn tokens.
+        mint: [],
+        /// Vault source token account (ATA of the vault PDA).
+        vault_token_account: [writable, vault_token_account(vault, mint, token_program)],
+        /// Token account of the owner for the mint (SPL Token or Token-2022).
+        destination_token_account: [writable],
+        /// Token program (SPL Token or Token-2022).
+        token_program: [token_program],
+    }
+}
+
+/// Permissionless; pay the whole balance of an Ephemeral ATA that left the ephemeral
+/// rollup with `ExitRollup` to a token account of its owner. Run as the
+/// post-undelegation action of `ExitRollup`, or sent directly if the action failed.
+///
+/// The exit flag is cleared, so an exit is settled at most once.
+pub fn process_settle_exit(accounts: &[AccountView]) -> ProgramResult {
+    let SettleExitAccounts {
+        owner,
+        ephemeral_ata: ephemeral_ata_info,
+        vault,
+        mint,
+        vault_token_account,
+        destination_token_account,
+        token_program,
+    } = SettleExitAccounts::try_from(accounts)?;
+
+    check_token_account(
+        destination_token_account,
+        mint.address(),
+        owner.address(),
+        token_program,
+    )?;
+
+    let amount = {
+        let ephemeral_ata = unsafe {
+            load_mut_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked_mut())?
+        };
+        if !ephemeral_ata.withdraw_on_exit() {
+            return Err(EphemeralSplError::ExitNotRequested.into());
+        }
+        ephemeral_ata.set_withdraw_on_exit(false);
+        ephemeral_ata.amount
+    };
+
+    // The owner did not sign, but the destination is checked to be theirs
+    withdraw(
+        &WithdrawSplTokensAccounts {
+            owner,
+            ephemeral_ata: ephemeral_ata_info,
+            vault,
+            mint,
+            vault_token_account,
+            destination_token_account,
+            token_program,
+        },
+        amount,
+    )
+}
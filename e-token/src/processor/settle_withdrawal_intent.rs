@@ -0,0 +1,87 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::state::{
+    global_vault::GlobalVault, load_mut_unchecked, withdrawal_intent::WithdrawalIntent,
+};
+use pinocchio::{AccountView, ProgramResult};
+
+use crate::processor::withdraw_spl_tokens::pay_from_vault;
+use crate::token_interface::check_token_account;
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `SettleWithdrawalIntent`.
+    pub struct SettleWithdrawalIntentAccounts {
+        /// Withdrawal intent back from the ephemeral rollup (PDA ["withdrawal-intent",
+        /// ephemeral_ata]).
+        withdrawal_intent: [writable, withdrawal_intent],
+        /// Owner of the Ephemeral ATA; does not need to sign.
+        owner: [],
+        /// Global Vault of the mint (PDA [mint]), signs the transfer.
+        vault: [writable, global_vault_pda],
+        /// Mint of the withdrawn tokens.
+        mint: [],
+        /// Vault source token account (ATA of the vault PDA).
+        vault_token_account: [writable, vault_token_account(vault, mint, token_program)],
+        /// Token account of the owner for the mint (SPL Token or Token-2022).
+        destination_token_account: [writable],
+        /// Token program (SPL Token or Token-2022).
+        token_program: [token_program],
+    }
+}
+
+/// Permissionless; pay a withdrawal intent committed by `CreateWithdrawalIntent` to a
+/// token account of the owner of its Ephemeral ATA.
+///
+/// The intent must be undelegated: while it is delegated, its state on the base layer
+/// may be stale and belongs to the delegation program. The intent amount is reset, so
+/// each intent is paid at most once. The Ephemeral ATA was already debited in the
+/// ephemeral rollup and is not touched.
+pub fn process_settle_withdrawal_intent(accounts: &[AccountView]) -> ProgramResult {
+    let SettleWithdrawalIntentAccounts {
+        withdrawal_intent: withdrawal_intent_info,
+        owner,
+        vault: vault_info,
+        mint,
+        vault_token_account,
+        destination_token_account,
+        token_program,
+    } = SettleWithdrawalIntentAccounts::try_from(accounts)?;
+
+    let withdrawal_intent = unsafe {
+        load_mut_unchecked::<WithdrawalIntent>(withdrawal_intent_info.borrow_unchecked_mut())?
+    };
+    let vault = unsafe { load_mut_unchecked::<GlobalVault>(vault_info.borrow_unchecked_mut())? };
+
+    if withdrawal_intent.owner != *owner.address() {
+        return Err(EphemeralSplError::OwnerMismatch.into());
+    }
+    if withdrawal_intent.mint != *mint.address() || vault.mint != *mint.address() {
+        return Err(EphemeralSplError::MintMismatch.into());
+    }
+    // The owner did not sign, but the destination is checked to be theirs
+    check_token_account(
+        destination_token_account,
+        mint.address(),
+        owner.address(),
+        token_program,
+    )?;
+
+    let amount = withdrawal_intent.amount;
+    if amount == 0 {
+        return Err(EphemeralSplError::NoWithdrawalIntent.into());
+    }
+
+    pay_from_vault(
+        vault_info,
+        vault,
+        mint,
+        vault_token_account,
+        destination_token_account,
+        token_program,
+        amount,
+    )?;
+
+    withdrawal_intent.amount = 0;
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::event::EventKind;
+use {
+    ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_mut_unchecked, load_unchecked},
+    pinocchio::{AccountView, ProgramResult},
+};
+
+use crate::event::emit;
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `TransferEphemeral`.
+    pub struct TransferEphemeralAccounts {
+        /// Owner of the source Ephemeral ATA.
+        owner: [signer],
+        /// Source Ephemeral ATA (PDA [owner, mint]).
+        source: [writable, ephemeral_ata],
+        /// Destination Ephemeral ATA (PDA [recipient, mint]).
+        destination: [writable, ephemeral_ata],
+    }
+}
+
+#[inline(always)]
+pub fn process_transfer_ephemeral(accounts: &[AccountView], amount: u64) -> ProgramResult {
+    let TransferEphemeralAccounts {
+        owner,
+        source: source_info,
+        destination: destination_info,
+    } = TransferEphemeralAccounts::try_from(accounts)?;
+
+    // Self-transfer: validate ownership and balance, but leave the account untouched
+    if source_info.address() == destination_info.address() {
+        let source = unsafe { load_unchecked::<EphemeralAta>(source_info.borrow_unchecked())? };
+        if source.owner != *owner.address() {
+            return Err(EphemeralSplError::OwnerMismatch.into());
+        }
+        if source.amount < amount {
+            return Err(EphemeralSplError::InsufficientFunds.into());
+        }
+        return Ok(());
+    }
+
+    let source = unsafe { load_mut_unchecked::<EphemeralAta>(source_info.borrow_unchecked_mut())? };
+    let destination =
+        unsafe { load_mut_unchecked::<EphemeralAta>(destination_info.borrow_unchecked_mut())? };
+
+    // Check eata consistency
+    if source.owner != *owner.address() {
+        return Err(EphemeralSplError::OwnerMismatch.into());
+    }
+    if source.mint != destination.mint {
+        return Err(EphemeralSplError::MintMismatch.into());
+    }
+
+    source.amount = source
+        .amount
+        .checked_sub(amount)
+        .ok_or(EphemeralSplError::InsufficientFunds)?;
+    destination.amount = destination
+        .amount
+        .checked_add(amount)
+        .ok_or(EphemeralSplError::Overflow)?;
+
+    emit(
+        EventKind::TransferOut,
+        source_info.address(),
+        source,
+        amount,
+    );
+    emit(
+        EventKind::TransferIn,
+        destination_info.address(),
+        destination,
+        amount,
+    );
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::event::EventKind;
+use {
+    ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_mut_unchecked},
+    pinocchio::{error::ProgramError, AccountView, ProgramResult},
+};
+
+use crate::event::emit;
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of `TransferFromEphemeral`.
+    pub struct TransferFromEphemeralAccounts {
+        /// Delegate approved on the source Ephemeral ATA.
+        delegate: [signer],
+        /// Source Ephemeral ATA (PDA [owner, mint]).
+        source: [writable, ephemeral_ata],
+        /// Destination Ephemeral ATA (PDA [recipient, mint]).
+        destination: [writable, ephemeral_ata],
+    }
+}
+
+#[inline(always)]
+pub fn process_transfer_from_ephemeral(accounts: &[AccountView], amount: u64) -> ProgramResult {
+    let TransferFromEphemeralAccounts {
+        delegate,
+        source: source_info,
+        destination: destination_info,
+    } = TransferFromEphemeralAccounts::try_from(accounts)?;
+
+    // A delegated self-transfer would only burn allowance; reject it outright
+    if source_info.address() == destination_info.address() {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let source = unsafe { load_mut_unchecked::<EphemeralAta>(source_info.borrow_unchecked_mut())? };
+    let destination =
+        unsafe { load_mut_unchecked::<EphemeralAta>(destination_info.borrow_unchecked_mut())? };
+
+    if source.mint != destination.mint {
+        return Err(EphemeralSplError::MintMismatch.into());
+    }
+
+    if source.delegate() != Some(delegate.address()) {
+        return Err(EphemeralSplError::InvalidDelegate.into());
+    }
+
+    source.delegated_amount = source
+        .delegated_amount
+        .checked_sub(amount)
+        .ok_or(EphemeralSplError::InsufficientFunds)?;
+    source.amount = source
+        .amount
+        .checked_sub(amount)
+        .ok_or(EphemeralSplError::InsufficientFunds)?;
+    destination.amount = destination
+        .amount
+        .checked_add(amount)
+        .ok_or(EphemeralSplError::Overflow)?;
+
+    // An exhausted allowance clears the delegate, mirroring SPL Token
+    if source.delegated_amount == 0 {
+        source.clear_delegate();
+    }
+
+    emit(
+        EventKind::TransferOut,
+        source_info.address(),
+        source,
+        amount,
+    );
+    emit(
+        EventKind::TransferIn,
+        destination_info.address(),
+        destination,
+        amount,
+    );
+
+    Ok(())
+}
@@ -1,64 +1,106 @@
-use ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_unchecked};
-use pinocchio::{error::ProgramError, AccountView, ProgramResult};
-use pinocchio_token::state::TokenAccount;
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::event::EventKind;
+use ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_mut_unchecked};
+use pinocchio::{AccountView, ProgramResult};
 
-/// Undelegate an Ephemeral ATA by calling into the delegation program helper that
-/// schedules a commit and performs undelegation.
-///
-/// Expected accounts (in order used below):
-/// 0. [signer]   Payer
-/// 1. [writable] User ATA account (SPL ATA for [payer, mint])
-/// 2. [writable] Ephemeral ATA account (PDA derived from [payer, mint])
-/// 3. [writable] Magic context account (as required by the delegation program)
-/// 4. []         Delegation program ID (aka magic program)
-pub fn process_undelegate_ephemeral_ata(
-    accounts: &[AccountView],
-    _instruction_data: &[u8],
-) -> ProgramResult {
-    let [payer, ata_info, ephemeral_ata_info, magic_context, magic_program, ..] = accounts else {
-        return Err(ProgramError::NotEnoughAccountKeys);
-    };
+use crate::event::emit;
+use crate::validation::accounts;
 
-    // Ensure the payer signed the transaction
-    if !payer.is_signer() {
-        return Err(ProgramError::MissingRequiredSignature);
+accounts! {
+    /// Accounts of `UndelegateEphemeralAta`.
+    pub struct UndelegateEphemeralAtaAccounts {
+        /// Owner of the Ephemeral ATA.
+        payer: [signer],
+        /// Ephemeral ATA account (PDA derived from [payer, mint]).
+        ephemeral_ata: [writable, ephemeral_ata],
+        /// Magic context account (as required by the delegation program).
+        magic_context: [writable],
+        /// Magic program.
+        magic_program: [magic_program],
     }
+}
 
-    // Read the Ephemeral ATA to get the mint and verify the PDA derivation for this payer.
-    // Scope the borrow so it's released before any CPI.
-    let mint = {
-        let eata_data =
-            unsafe { load_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked())? };
-        #[allow(clippy::clone_on_copy)]
-        let mint = eata_data.mint.clone();
-        mint
-    };
-
-    // Derive PDA: seeds = [payer, mint], program id = e-token program id (ephemeral_spl_api::program::ID)
-    let (derived_pda, _) = ephemeral_spl_api::Address::find_program_address(
-        &[payer.address().as_ref(), mint.as_ref()],
-        &ephemeral_spl_api::program::id_address(),
-    );
-
-    if derived_pda != *ephemeral_ata_info.address() {
-        return Err(ProgramError::InvalidSeeds);
+accounts! {
+    /// Accounts of `UndelegateEphemeralAta` before the user token account was dropped.
+    pub struct LegacyUndelegateEphemeralAtaAccounts {
+        /// Owner of the Ephemeral ATA.
+        payer: [signer],
+        /// User token account; ignored.
+        user_ata: [],
+        /// Ephemeral ATA account (PDA derived from [payer, mint]).
+        ephemeral_ata: [writable, ephemeral_ata],
+        /// Magic context account (as required by the delegation program).
+        magic_context: [writable],
+        /// Magic program.
+        magic_program: [magic_program],
     }
+}
 
-    // Validate that the provided ATA account is a valid SPL token account for [payer, mint].
-    {
-        let token_acc = TokenAccount::from_account_view(ata_info)
-            .map_err(|_| ProgramError::InvalidAccountData)?;
+/// Number of accounts of [`LegacyUndelegateEphemeralAtaAccounts`].
+const LEGACY_ACCOUNTS_LEN: usize = 5;
 
-        if token_acc.mint() != &mint || token_acc.owner() != payer.address() {
-            return Err(ProgramError::InvalidAccountData);
+impl<'a> From<LegacyUndelegateEphemeralAtaAccounts<'a>> for UndelegateEphemeralAtaAccounts<'a> {
+    fn from(accounts: LegacyUndelegateEphemeralAtaAccounts<'a>) -> Self {
+        Self {
+            payer: accounts.payer,
+            ephemeral_ata: accounts.ephemeral_ata,
+            magic_context: accounts.magic_context,
+            magic_program: accounts.magic_program,
         }
     }
+}
+
+/// Undelegate an Ephemeral ATA by calling into the delegation program helper that
+/// schedules a commit and performs undelegation.
+///
+/// Clients built for the legacy layout still pass the user token account at index 1;
+/// five accounts are read as that layout and the token account is ignored.
+pub fn process_undelegate_ephemeral_ata(accounts: &[AccountView]) -> ProgramResult {
+    let UndelegateEphemeralAtaAccounts {
+        payer,
+        ephemeral_ata: ephemeral_ata_info,
+        magic_context,
+        magic_program,
+    } = if accounts.len() == LEGACY_ACCOUNTS_LEN {
+        LegacyUndelegateEphemeralAtaAccounts::try_from(accounts)?.into()
+    } else {
+        UndelegateEphemeralAtaAccounts::try_from(accounts)?
+    };
 
-    // Commit and undelegate with the user's ATA and the ephemeral ATA as the account set
+    prepare_undelegation(payer, ephemeral_ata_info, false)?;
+
+    // Commit and undelegate the Ephemeral ATA itself; its snapshot is checked by the
+    // undelegation callback when it is restored on the base layer
     ephemeral_rollups_pinocchio::instruction::commit_and_undelegate_accounts(
         payer,
-        &[ata_info.clone()],
+        &[ephemeral_ata_info.clone()],
         magic_context,
         magic_program,
+        None,
+        None,
     )
 }
+
+/// Check that `payer` owns the Ephemeral ATA, record in the state about to be
+/// committed whether its balance is to be paid out to the owner once it is back on
+/// the base layer, and emit the `Undelegate` event.
+#[inline(always)]
+pub(crate) fn prepare_undelegation(
+    payer: &AccountView,
+    ephemeral_ata_info: &AccountView,
+    withdraw_on_exit: bool,
+) -> ProgramResult {
+    let ephemeral_ata =
+        unsafe { load_mut_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked_mut())? };
+    if ephemeral_ata.owner != *payer.address() {
+        return Err(EphemeralSplError::OwnerMismatch.into());
+    }
+    ephemeral_ata.set_withdraw_on_exit(withdraw_on_exit);
+    emit(
+        EventKind::Undelegate,
+        ephemeral_ata_info.address(),
+        ephemeral_ata,
+        0,
+    );
+    Ok(())
+}
@@ -2,45 +2,48 @@ use ephemeral_rollups_pinocchio::acl::{
     consts::PERMISSION_PROGRAM_ID, instruction::commit_and_undelegate_permission,
     pda::permission_pda_from_permissioned_account,
 };
-use ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_unchecked, Initializable};
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::event::EventKind;
+use ephemeral_spl_api::state::{ephemeral_ata::EphemeralAta, load_unchecked};
 use pinocchio::{error::ProgramError, AccountView, ProgramResult};
 
-/// Commit and undelegate the permission PDA associated with an Ephemeral ATA.
-///
-/// Expected accounts:
-/// 0. [signer]   Payer (authority)
-/// 1. [writable] Ephemeral ATA account (permissioned account)
-/// 2. [writable] Permission PDA (derived from ["permission:", ephemeral_ata])
-/// 3. []         Permission program (ACL)
-/// 4. []         Delegation program (magic program)
-/// 5. [writable] Magic context account
-pub fn process_undelegate_ephemeral_ata_permission(
-    accounts: &[AccountView],
-    _instruction_data: &[u8],
-) -> ProgramResult {
-    let [payer_info, ephemeral_ata_info, permission_info, permission_program, magic_program, magic_context, ..] =
-        accounts
-    else {
-        return Err(ProgramError::NotEnoughAccountKeys);
-    };
+use crate::event::emit;
+use crate::validation::accounts;
 
-    if !payer_info.is_signer() {
-        return Err(ProgramError::MissingRequiredSignature);
+accounts! {
+    /// Accounts of `UndelegateEphemeralAtaPermission`.
+    pub struct UndelegateEphemeralAtaPermissionAccounts {
+        /// Payer (authority).
+        payer: [signer],
+        /// Ephemeral ATA account (permissioned account).
+        ephemeral_ata: [writable, ephemeral_ata],
+        /// Permission PDA (derived from ["permission:", ephemeral_ata]).
+        permission: [writable],
+        /// Permission program (ACL).
+        permission_program: [permission_program],
+        /// Magic program.
+        magic_program: [magic_program],
+        /// Magic context account.
+        magic_context: [writable],
     }
+}
 
-    if *permission_program.address() != PERMISSION_PROGRAM_ID {
-        return Err(ProgramError::InvalidAccountData);
-    }
+/// Commit and undelegate the permission PDA associated with an Ephemeral ATA.
+pub fn process_undelegate_ephemeral_ata_permission(accounts: &[AccountView]) -> ProgramResult {
+    let UndelegateEphemeralAtaPermissionAccounts {
+        payer: payer_info,
+        ephemeral_ata: ephemeral_ata_info,
+        permission: permission_info,
+        magic_program,
+        magic_context,
+        ..
+    } = UndelegateEphemeralAtaPermissionAccounts::try_from(accounts)?;
 
     let ephemeral_ata =
         unsafe { load_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked())? };
 
-    if !ephemeral_ata.is_initialized() {
-        return Err(ProgramError::InvalidAccountData);
-    }
-
     if ephemeral_ata.owner != *payer_info.address() {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(EphemeralSplError::OwnerMismatch.into());
     }
 
     let expected_permission =
@@ -61,5 +64,14 @@ pub fn process_undelegate_ephemeral_ata_permission(
         true,
         false,
         None,
-    )
+    )?;
+
+    emit(
+        EventKind::PermissionUndelegate,
+        ephemeral_ata_info.address(),
+        ephemeral_ata,
+        0,
+    );
+
+    Ok(())
 }
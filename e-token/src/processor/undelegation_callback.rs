@@ -1,26 +1,129 @@
+use ephemeral_spl_api::pda::WITHDRAWAL_INTENT_SEED;
+use ephemeral_spl_api::state::{
+    ephemeral_ata::EphemeralAta, load, withdrawal_intent::WithdrawalIntent,
+};
 use pinocchio::{error::ProgramError, AccountView, Address, ProgramResult};
 
+use crate::pda::{
+    check_ephemeral_ata, check_withdrawal_intent, find_ephemeral_ata, find_withdrawal_intent,
+};
+use crate::processor::migrate_account::migrate_ephemeral_ata;
+use crate::validation::accounts;
+
+accounts! {
+    /// Accounts of the undelegation callback, in the order the delegation program
+    /// passes them.
+    pub struct UndelegationCallbackAccounts {
+        /// Delegated PDA account to be restored (Ephemeral ATA or withdrawal intent PDA).
+        delegated: [writable],
+        /// Undelegate buffer PDA (holds the snapshot of the delegated account).
+        buffer: [undelegate_buffer(delegated)],
+        /// Payer (original authority for the delegated PDA), funds the restored account.
+        payer: [signer, writable],
+        /// System program.
+        system_program: [system_program],
+    }
+}
+
+/// Length of the callback arguments for an Ephemeral ATA: the Borsh `Vec<Vec<u8>>` of
+/// the two 32-byte seeds [owner, mint].
+const ARGS_LEN: usize = 4 + 2 * (4 + 32);
+
+/// Length of the callback arguments for a withdrawal intent: the Borsh `Vec<Vec<u8>>`
+/// of the seeds ["withdrawal-intent", ephemeral_ata].
+const WITHDRAWAL_INTENT_ARGS_LEN: usize = 4 + (4 + WITHDRAWAL_INTENT_SEED.len()) + (4 + 32);
+
 /// Undelegation callback invoked by the delegation program.
 ///
-/// Expected accounts (in order used below):
-/// 0. []         Payer (original authority for the delegated PDA)
-/// 1. [writable] Delegated PDA account to be restored (Ephemeral ATA PDA)
-/// 2. []         Owner program (this program ID)
-/// 3. [signer]   Undelegate buffer PDA (holds the snapshot of the delegated account)
-/// 4. []         System program
-pub fn process_undelegation_callback(
-    accounts: &[AccountView],
-    instruction_data: &[u8],
-) -> ProgramResult {
-    let [delegated_acc, buffer_acc, payer, _system_program, ..] = accounts else {
-        return Err(ProgramError::NotEnoughAccountKeys);
-    };
+/// The snapshot committed by the ephemeral rollup is restored, then rejected unless it
+/// is an Ephemeral ATA or a withdrawal intent at the current layout whose stored seeds
+/// and bump derive the restored address. A legacy Ephemeral ATA snapshot is migrated
+/// to the current layout first, funded by the payer. The owner and mint of a withdrawal
+/// intent must also derive its Ephemeral ATA. Failing here aborts the undelegation and
+/// the account stays delegated.
+///
+/// The Global Vault is not among the accounts passed by the delegation program, so
+/// the restored balance is checked against the vault deposits when it is paid out:
+/// an Ephemeral ATA holding more than the vault's `total_deposited` cannot withdraw.
+pub fn process_undelegation_callback(accounts: &[AccountView], args: &[u8]) -> ProgramResult {
+    let UndelegationCallbackAccounts {
+        delegated: delegated_acc,
+        buffer: buffer_acc,
+        payer,
+        ..
+    } = UndelegationCallbackAccounts::try_from(accounts)?;
+
+    // Only Ephemeral ATAs and withdrawal intents are delegated: the seeds must be
+    // [owner, mint] or ["withdrawal-intent", ephemeral_ata] of the delegated address
+    let is_withdrawal_intent = args.len() == WITHDRAWAL_INTENT_ARGS_LEN;
+    if is_withdrawal_intent {
+        let ephemeral_ata = unpack_withdrawal_intent_seeds(args)?;
+        find_withdrawal_intent(delegated_acc, &ephemeral_ata)?;
+    } else {
+        let (owner, mint) = unpack_seeds(args)?;
+        find_ephemeral_ata(delegated_acc, &owner, &mint)?;
+    }
 
     ephemeral_rollups_pinocchio::instruction::undelegate(
         delegated_acc,
         &Address::new_from_array(crate::ID),
         buffer_acc,
         payer,
-        &instruction_data[7..],
-    )
+        args,
+    )?;
+
+    if is_withdrawal_intent {
+        let withdrawal_intent =
+            unsafe { load::<WithdrawalIntent>(delegated_acc.borrow_unchecked())? };
+        check_withdrawal_intent(delegated_acc, withdrawal_intent)
+    } else {
+        // An Ephemeral ATA delegated before account versioning comes back in the
+        // legacy layout; rewrite it so it does not stay delegated for good
+        if delegated_acc.data_len() == EphemeralAta::LEGACY_LEN {
+            migrate_ephemeral_ata(payer, delegated_acc)?;
+        }
+        let ephemeral_ata = unsafe { load::<EphemeralAta>(delegated_acc.borrow_unchecked())? };
+        check_ephemeral_ata(delegated_acc, ephemeral_ata)
+    }
+}
+
+/// Read the owner and mint seeds from the callback arguments.
+#[inline(always)]
+fn unpack_seeds(args: &[u8]) -> Result<(Address, Address), ProgramError> {
+    const SEED_LEN: [u8; 4] = 32u32.to_le_bytes();
+
+    let args: &[u8; ARGS_LEN] = args
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    if args[..4] != 2u32.to_le_bytes() || args[4..8] != SEED_LEN || args[40..44] != SEED_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    Ok((address(&args[8..40]), address(&args[44..76])))
+}
+
+/// Read the Ephemeral ATA seed of a withdrawal intent from the callback arguments.
+#[inline(always)]
+fn unpack_withdrawal_intent_seeds(args: &[u8]) -> Result<Address, ProgramError> {
+    const PREFIX_END: usize = 8 + WITHDRAWAL_INTENT_SEED.len();
+
+    let args: &[u8; WITHDRAWAL_INTENT_ARGS_LEN] = args
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    if args[..4] != 2u32.to_le_bytes()
+        || args[4..8] != (WITHDRAWAL_INTENT_SEED.len() as u32).to_le_bytes()
+        || &args[8..PREFIX_END] != WITHDRAWAL_INTENT_SEED
+        || args[PREFIX_END..PREFIX_END + 4] != 32u32.to_le_bytes()
+    {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    Ok(address(&args[PREFIX_END + 4..]))
+}
+
+#[inline(always)]
+fn address(seed: &[u8]) -> Address {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(seed);
+    Address::new_from_array(bytes)
 }
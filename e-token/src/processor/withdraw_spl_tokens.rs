@@ -1,120 +1,149 @@
-use core::marker::PhantomData;
 use ephemeral_spl_api::error::EphemeralSplError;
 use pinocchio::cpi::{Seed, Signer};
 use {
-    ephemeral_spl_api::state::{
-        ephemeral_ata::EphemeralAta, global_vault::GlobalVault, load_mut_unchecked, load_unchecked,
+    ephemeral_spl_api::{
+        event::EventKind,
+        state::{ephemeral_ata::EphemeralAta, global_vault::GlobalVault, load_mut_unchecked},
     },
-    pinocchio::{error::ProgramError, AccountView, ProgramResult},
+    pinocchio::{AccountView, ProgramResult},
 };
 
-#[inline(always)]
-pub fn process_withdraw_spl_tokens(
-    accounts: &[AccountView],
-    instruction_data: &[u8],
-) -> ProgramResult {
-    // Expected accounts:
-    // 0. [writable] Ephemeral ATA data account (PDA [payer, mint])
-    // 1. []         Global Vault data account (PDA [mint])
-    // 2. []         Mint account (readonly)
-    // 3. [writable] Vault source token account (SPL Token)
-    // 4. [writable] User destination token account (SPL Token)
-    // 5. []         Token program
-
-    let args = WithdrawArgs::try_from_bytes(instruction_data)?;
-
-    let [owner, ephemeral_ata_info, vault_info, mint_info, vault_source_token_acc, user_dest_token_acc, ..] =
-        accounts
-    else {
-        return Err(ProgramError::NotEnoughAccountKeys);
-    };
-
-    if !owner.is_signer() {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+use crate::{
+    event::emit,
+    token_interface::{mint_decimals, transfer_checked},
+    validation::accounts,
+};
 
-    // Validate EphemeralAta account (writable)
-    unsafe {
-        if ephemeral_ata_info
-            .owner()
-            .ne(&ephemeral_spl_api::program::id_address())
-        {
-            return Err(ProgramError::IllegalOwner);
-        }
+accounts! {
+    /// Accounts of `WithdrawSplTokens`.
+    pub struct WithdrawSplTokensAccounts {
+        /// Owner of the Ephemeral ATA.
+        owner: [signer],
+        /// Ephemeral ATA debited by the withdrawal (PDA [owner, mint]). Its balance
+        /// lives in the ephemeral rollup while delegated, so it must be undelegated.
+        ephemeral_ata: [writable, undelegated, ephemeral_ata],
+        /// Global Vault of the mint (PDA [mint]), signs the transfer.
+        vault: [writable, global_vault_pda],
+        /// Mint of the withdrawn tokens.
+        mint: [],
+        /// Vault source token account (ATA of the vault PDA).
+        vault_token_account: [writable, vault_token_account(vault, mint, token_program)],
+        /// User destination token account (SPL Token or Token-2022).
+        destination_token_account: [writable],
+        /// Token program (SPL Token or Token-2022).
+        token_program: [token_program],
     }
+}
+
+#[inline(always)]
+pub fn process_withdraw_spl_tokens(accounts: &[AccountView], amount: u64) -> ProgramResult {
+    // With a Token-2022 transfer-fee mint the Ephemeral ATA is debited the full
+    // `amount` and the fee is withheld from what the user receives.
+    //
+    // The vault signs with the bump recorded at initialization; a trailing bump
+    // byte sent by older clients is ignored.
+
+    withdraw(&WithdrawSplTokensAccounts::try_from(accounts)?, amount)
+}
+
+/// Pay `amount` from the vault to the destination token account and debit the
+/// Ephemeral ATA, once its accounts are validated.
+#[inline(always)]
+pub(crate) fn withdraw(accounts: &WithdrawSplTokensAccounts, amount: u64) -> ProgramResult {
+    let WithdrawSplTokensAccounts {
+        owner,
+        ephemeral_ata: ephemeral_ata_info,
+        vault: vault_info,
+        mint: mint_info,
+        vault_token_account,
+        destination_token_account,
+        token_program,
+    } = *accounts;
+
     let ephemeral_ata =
         unsafe { load_mut_unchecked::<EphemeralAta>(ephemeral_ata_info.borrow_unchecked_mut())? };
-
-    // Validate Vault data account
-    let vault = unsafe { load_unchecked::<GlobalVault>(vault_info.borrow_unchecked())? };
+    let vault = unsafe { load_mut_unchecked::<GlobalVault>(vault_info.borrow_unchecked_mut())? };
 
     // Check eata consistency
-    if ephemeral_ata.mint != *mint_info.address()
-        || vault.mint != *mint_info.address()
-        || ephemeral_ata.owner != *owner.address()
-    {
-        return Err(EphemeralSplError::EphemeralAtaMismatch.into());
+    if ephemeral_ata.owner != *owner.address() {
+        return Err(EphemeralSplError::OwnerMismatch.into());
+    }
+    if ephemeral_ata.mint != *mint_info.address() || vault.mint != *mint_info.address() {
+        return Err(EphemeralSplError::MintMismatch.into());
     }
 
-    // read mint decimals
-    let decimals = pinocchio_token::state::Mint::from_account_view(mint_info)
-        .map_err(|_| ProgramError::InvalidAccountData)?
-        .decimals();
-
-    // Perform transfer from vault token account to user destination, signed by vault PDA
-    let bump = [args.bump()];
-    let seeds = [Seed::from(mint_info.address().as_ref()), Seed::from(&bump)];
-    let signer = Signer::from(&seeds);
-
-    pinocchio_token::instructions::TransferChecked {
-        mint: mint_info,
-        from: vault_source_token_acc,
-        to: user_dest_token_acc,
-        amount: args.amount(),
-        authority: vault_info, // PDA authority over the vault token account
-        decimals,
+    // A balance restored from the ephemeral rollup is bounded by what the vault
+    // actually holds for this mint, even for a partial withdrawal
+    if ephemeral_ata.amount > vault.total_deposited {
+        return Err(EphemeralSplError::ExceedsVaultDeposits.into());
     }
-    .invoke_signed(&[signer])?;
+
+    pay_from_vault(
+        vault_info,
+        vault,
+        mint_info,
+        vault_token_account,
+        destination_token_account,
+        token_program,
+        amount,
+    )?;
 
     // Safely decrease the amount in the EphemeralAta
     ephemeral_ata.amount = ephemeral_ata
         .amount
-        .checked_sub(args.amount())
-        .ok_or(ProgramError::InvalidArgument)?;
+        .checked_sub(amount)
+        .ok_or(EphemeralSplError::InsufficientFunds)?;
 
-    Ok(())
-}
+    emit(
+        EventKind::Withdraw,
+        ephemeral_ata_info.address(),
+        ephemeral_ata,
+        amount,
+    );
 
-/// Instruction data for the `WithdrawSplTokens` instruction.
-pub struct WithdrawArgs<'a> {
-    raw: *const u8,
-    _data: PhantomData<&'a [u8]>,
+    Ok(())
 }
 
-impl WithdrawArgs<'_> {
-    #[inline]
-    pub fn try_from_bytes(bytes: &[u8]) -> Result<WithdrawArgs, ProgramError> {
-        if bytes.len() < 9 {
-            return Err(ProgramError::InvalidInstructionData);
-        }
-        Ok(WithdrawArgs {
-            raw: bytes.as_ptr(),
-            _data: PhantomData,
-        })
+/// Transfer `amount` from the vault token account to `destination_token_account`,
+/// signed by the vault, and remove it from the vault deposits.
+#[inline(always)]
+pub(crate) fn pay_from_vault(
+    vault_info: &AccountView,
+    vault: &mut GlobalVault,
+    mint_info: &AccountView,
+    vault_token_account: &AccountView,
+    destination_token_account: &AccountView,
+    token_program: &AccountView,
+    amount: u64,
+) -> ProgramResult {
+    // The vault never pays out more than it recorded as deposited for the mint
+    if amount > vault.total_deposited {
+        return Err(EphemeralSplError::ExceedsVaultDeposits.into());
     }
 
-    #[inline]
-    pub fn amount(&self) -> u64 {
-        // read LE u64 from bytes[0..8]
-        let mut buf = [0u8; 8];
-        unsafe {
-            core::ptr::copy_nonoverlapping(self.raw, buf.as_mut_ptr(), 8);
-        }
-        u64::from_le_bytes(buf)
-    }
+    // read mint decimals
+    let decimals = mint_decimals(mint_info, token_program)?;
 
-    #[inline]
-    pub fn bump(&self) -> u8 {
-        unsafe { *self.raw.add(8) }
-    }
+    // Perform transfer from vault token account to user destination, signed by vault PDA
+    let bump = [vault.header.bump];
+    let seeds = [Seed::from(mint_info.address().as_ref()), Seed::from(&bump)];
+    let signer = Signer::from(&seeds);
+
+    transfer_checked(
+        vault_token_account,
+        mint_info,
+        destination_token_account,
+        vault_info, // PDA authority over the vault token account
+        amount,
+        decimals,
+        token_program,
+        &[signer],
+    )?;
+
+    vault.total_deposited = vault
+        .total_deposited
+        .checked_sub(amount)
+        .ok_or(EphemeralSplError::Overflow)?;
+
+    Ok(())
 }
@@ -0,0 +1,200 @@
+use {
+    ephemeral_spl_api::{error::EphemeralSplError, pda},
+    pinocchio::{cpi::Signer, error::ProgramError, AccountView, Address, ProgramResult},
+};
+
+/// SPL Token program id.
+pub const TOKEN_PROGRAM_ID: Address = pinocchio_token::ID;
+/// Token-2022 program id.
+pub const TOKEN_2022_PROGRAM_ID: Address = pinocchio_token_2022::ID;
+/// Associated Token Account program id.
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Address = pinocchio_associated_token_account::ID;
+
+/// Size of the base mint layout shared by SPL Token and Token-2022.
+const MINT_BASE_LEN: usize = 82;
+/// Size of the base token account layout shared by SPL Token and Token-2022.
+const TOKEN_ACCOUNT_BASE_LEN: usize = 165;
+/// Offset of the `decimals` field in the base mint layout.
+const MINT_DECIMALS_OFFSET: usize = 44;
+/// Offset of the `is_initialized` field in the base mint layout.
+const MINT_INITIALIZED_OFFSET: usize = 45;
+/// Offset of the `owner` field in the base token account layout.
+const TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+/// Offset of the `amount` field in the base token account layout.
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+/// Offset of the Token-2022 account type byte, shared by mints and token accounts.
+const ACCOUNT_TYPE_OFFSET: usize = TOKEN_ACCOUNT_BASE_LEN;
+/// Token-2022 `AccountType::Mint`.
+const ACCOUNT_TYPE_MINT: u8 = 1;
+
+// Token-2022 `ExtensionType` values the vault cannot hold safely.
+const EXTENSION_NON_TRANSFERABLE: u16 = 9;
+const EXTENSION_PERMANENT_DELEGATE: u16 = 12;
+const EXTENSION_TRANSFER_HOOK: u16 = 14;
+
+/// Ensure the token program account is either SPL Token or Token-2022.
+#[inline(always)]
+pub fn check_token_program(token_program: &AccountView) -> ProgramResult {
+    let address = token_program.address();
+    if *address != TOKEN_PROGRAM_ID && *address != TOKEN_2022_PROGRAM_ID {
+        return Err(EphemeralSplError::InvalidTokenProgram.into());
+    }
+    Ok(())
+}
+
+/// Ensure the associated token program account has the expected address.
+#[inline(always)]
+pub fn check_associated_token_program(associated_token_program: &AccountView) -> ProgramResult {
+    if *associated_token_program.address() != ASSOCIATED_TOKEN_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Ensure `vault_token_acc` is the associated token account of the vault PDA
+/// for `mint` under `token_program`.
+#[inline(always)]
+pub fn check_vault_token_account(
+    vault_token_acc: &AccountView,
+    vault_info: &AccountView,
+    mint_info: &AccountView,
+    token_program: &AccountView,
+) -> ProgramResult {
+    let (expected, _) = pda::find_associated_token_account(
+        vault_info.address(),
+        mint_info.address(),
+        token_program.address(),
+    );
+    if *vault_token_acc.address() != expected {
+        return Err(EphemeralSplError::VaultMismatch.into());
+    }
+    Ok(())
+}
+
+/// Ensure `token_account` is a token account of `owner` for `mint` under `token_program`.
+#[inline(always)]
+pub fn check_token_account(
+    token_account: &AccountView,
+    mint: &Address,
+    owner: &Address,
+    token_program: &AccountView,
+) -> ProgramResult {
+    if !token_account.owned_by(token_program.address()) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let data = token_account.try_borrow()?;
+    if data.len() < TOKEN_ACCOUNT_BASE_LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if data[..TOKEN_ACCOUNT_OWNER_OFFSET] != *mint.as_ref() {
+        return Err(EphemeralSplError::MintMismatch.into());
+    }
+    if data[TOKEN_ACCOUNT_OWNER_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET] != *owner.as_ref() {
+        return Err(EphemeralSplError::OwnerMismatch.into());
+    }
+    Ok(())
+}
+
+/// Validate a mint owned by `token_program` and return its decimals.
+///
+/// Token-2022 mints are rejected when they carry an extension that would let
+/// tokens leave the vault without the vault's signature (permanent delegate),
+/// make them impossible to move (non-transferable), or require extra accounts
+/// on every transfer (transfer hook).
+pub fn mint_decimals(
+    mint_info: &AccountView,
+    token_program: &AccountView,
+) -> Result<u8, ProgramError> {
+    if !mint_info.owned_by(token_program.address()) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let data = mint_info.try_borrow()?;
+    if data.len() < MINT_BASE_LEN || data[MINT_INITIALIZED_OFFSET] != 1 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if data.len() > MINT_BASE_LEN {
+        // Only Token-2022 mints carry extensions
+        if *token_program.address() != TOKEN_2022_PROGRAM_ID
+            || data.len() <= ACCOUNT_TYPE_OFFSET
+            || data[ACCOUNT_TYPE_OFFSET] != ACCOUNT_TYPE_MINT
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_mint_extensions(&data[ACCOUNT_TYPE_OFFSET + 1..])?;
+    }
+
+    Ok(data[MINT_DECIMALS_OFFSET])
+}
+
+/// Walk the Token-2022 TLV extension area of a mint.
+fn check_mint_extensions(mut tlv: &[u8]) -> ProgramResult {
+    while tlv.len() >= 4 {
+        let extension_type = u16::from_le_bytes([tlv[0], tlv[1]]);
+        let length = u16::from_le_bytes([tlv[2], tlv[3]]) as usize;
+
+        match extension_type {
+            // Uninitialized marks the end of the written extensions
+            0 => break,
+            EXTENSION_NON_TRANSFERABLE => return Err(EphemeralSplError::NonTransferableMint.into()),
+            EXTENSION_PERMANENT_DELEGATE => {
+                return Err(EphemeralSplError::PermanentDelegateMint.into())
+            }
+            EXTENSION_TRANSFER_HOOK => {
+                return Err(EphemeralSplError::UnsupportedMintExtension.into())
+            }
+            _ => {}
+        }
+
+        tlv = tlv
+            .get(4 + length..)
+            .ok_or(ProgramError::InvalidAccountData)?;
+    }
+    Ok(())
+}
+
+/// Read the balance of a token account owned by `token_program`.
+pub fn token_account_amount(
+    token_account: &AccountView,
+    token_program: &AccountView,
+) -> Result<u64, ProgramError> {
+    if !token_account.owned_by(token_program.address()) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let data = token_account.try_borrow()?;
+    if data.len() < TOKEN_ACCOUNT_BASE_LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut amount = [0u8; 8];
+    amount.copy_from_slice(&data[TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8]);
+    Ok(u64::from_le_bytes(amount))
+}
+
+/// `TransferChecked` through whichever token program owns the mint.
+#[allow(clippy::too_many_arguments)]
+#[inline(always)]
+pub fn transfer_checked(
+    from: &AccountView,
+    mint: &AccountView,
+    to: &AccountView,
+    authority: &AccountView,
+    amount: u64,
+    decimals: u8,
+    token_program: &AccountView,
+    signers: &[Signer],
+) -> ProgramResult {
+    pinocchio_token_2022::instructions::TransferChecked {
+        from,
+        mint,
+        to,
+        authority,
+        amount,
+        decimals,
+        token_program: token_program.address(),
+    }
+    .invoke_signed(signers)
+}
@@ -0,0 +1,282 @@
+//! Declarative account validation.
+//!
+//! Each instruction declares its accounts with [`accounts!`]: the macro generates a
+//! struct holding one `&AccountView` per account and a `TryFrom<&[AccountView]>`
+//! implementation that destructures the accounts in order and runs the listed
+//! constraints. Constraints are the `#[inline(always)]` functions of this module,
+//! so a declaration compiles down to the checks a processor would write by hand.
+//!
+//! ```ignore
+//! accounts! {
+//!     pub struct ApproveEphemeralAccounts {
+//!         owner: [signer],
+//!         ephemeral_ata: [writable, ephemeral_ata],
+//!         delegate: [],
+//!     }
+//! }
+//! ```
+//!
+//! A constraint may take other accounts of the struct as arguments, e.g.
+//! `vault_token_account: [writable, vault_token_account(vault, mint, token_program)]`.
+
+use {
+    crate::{
+        pda::{check_ephemeral_ata, check_global_vault, check_withdrawal_intent},
+        token_interface,
+    },
+    ephemeral_rollups_pinocchio::{
+        acl::consts::PERMISSION_PROGRAM_ID,
+        consts::{DELEGATION_PROGRAM_ID, MAGIC_PROGRAM_ID},
+        pda::undelegate_buffer_pda_from_delegated_account,
+    },
+    ephemeral_spl_api::{
+        error::EphemeralSplError,
+        state::{
+            ephemeral_ata::EphemeralAta, global_vault::GlobalVault, load,
+            pending_deposit::PendingDeposit, withdrawal_intent::WithdrawalIntent,
+        },
+    },
+    pinocchio::{error::ProgramError, AccountView, ProgramResult},
+};
+
+/// Declare the accounts of an instruction and the constraints each must satisfy.
+///
+/// See the [module documentation](self).
+macro_rules! accounts {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field:ident: [$($constraint:ident $(($($arg:ident),*))?),* $(,)?]
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[allow(dead_code)] // accounts only declared for their constraints
+        pub struct $name<'a> {
+            $(
+                $(#[$field_meta])*
+                pub $field: &'a pinocchio::AccountView,
+            )*
+        }
+
+        impl<'a> TryFrom<&'a [pinocchio::AccountView]> for $name<'a> {
+            type Error = pinocchio::error::ProgramError;
+
+            #[inline(always)]
+            fn try_from(accounts: &'a [pinocchio::AccountView]) -> Result<Self, Self::Error> {
+                let [$($field,)* ..] = accounts else {
+                    return Err(pinocchio::error::ProgramError::NotEnoughAccountKeys);
+                };
+                $($(
+                    $crate::validation::$constraint($field $($(, $arg)*)?)?;
+                )*)*
+                Ok(Self { $($field,)* })
+            }
+        }
+    };
+}
+
+pub(crate) use accounts;
+
+/// The account signed the transaction.
+#[inline(always)]
+pub fn signer(account: &AccountView) -> ProgramResult {
+    if !account.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// The account is writable.
+#[inline(always)]
+pub fn writable(account: &AccountView) -> ProgramResult {
+    if !account.is_writable() {
+        return Err(ProgramError::Immutable);
+    }
+    Ok(())
+}
+
+/// The account is owned by this program.
+#[inline(always)]
+pub fn program_owned(account: &AccountView) -> ProgramResult {
+    if !account.owned_by(&ephemeral_spl_api::program::id_address()) {
+        return Err(ProgramError::IllegalOwner);
+    }
+    Ok(())
+}
+
+/// The account is not delegated to the ephemeral rollup, i.e. not owned by the
+/// delegation program.
+#[inline(always)]
+pub fn undelegated(account: &AccountView) -> ProgramResult {
+    if account.owned_by(&DELEGATION_PROGRAM_ID) {
+        return Err(EphemeralSplError::AccountDelegated.into());
+    }
+    Ok(())
+}
+
+/// The account is an initialized Ephemeral ATA at the current layout version.
+///
+/// Only the program can create accounts it owns, and it only creates Ephemeral
+/// ATAs at their PDA, so the owner and discriminator checks pin the address.
+#[inline(always)]
+pub fn ephemeral_ata(account: &AccountView) -> ProgramResult {
+    program_owned(account)?;
+    unsafe { load::<EphemeralAta>(account.borrow_unchecked())? };
+    Ok(())
+}
+
+/// Like [`ephemeral_ata`], and the address is derived from the stored seeds and
+/// bump; required before the program signs for the Ephemeral ATA.
+#[inline(always)]
+pub fn ephemeral_ata_pda(account: &AccountView) -> ProgramResult {
+    program_owned(account)?;
+    let ephemeral_ata = unsafe { load::<EphemeralAta>(account.borrow_unchecked())? };
+    check_ephemeral_ata(account, ephemeral_ata)
+}
+
+/// The account is an Ephemeral ATA delegated to the ephemeral rollup.
+///
+/// Its data is the snapshot taken at delegation. Any program can delegate its own
+/// accounts, so the address is checked against the stored seeds and bump.
+#[inline(always)]
+pub fn delegated_ephemeral_ata(account: &AccountView) -> ProgramResult {
+    if !account.owned_by(&DELEGATION_PROGRAM_ID) {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let ephemeral_ata = unsafe { load::<EphemeralAta>(account.borrow_unchecked())? };
+    check_ephemeral_ata(account, ephemeral_ata)
+}
+
+/// The account is an Ephemeral ATA owned by this program or delegated to the
+/// ephemeral rollup, and the address is derived from the stored seeds and bump.
+///
+/// The permission of an Ephemeral ATA is managed on the base layer whether or not
+/// the Ephemeral ATA itself is delegated.
+#[inline(always)]
+pub fn any_ephemeral_ata_pda(account: &AccountView) -> ProgramResult {
+    if !account.owned_by(&DELEGATION_PROGRAM_ID) {
+        program_owned(account)?;
+    }
+    let ephemeral_ata = unsafe { load::<EphemeralAta>(account.borrow_unchecked())? };
+    check_ephemeral_ata(account, ephemeral_ata)
+}
+
+/// The account is an initialized Global Vault at the current layout version.
+#[inline(always)]
+pub fn global_vault(account: &AccountView) -> ProgramResult {
+    program_owned(account)?;
+    unsafe { load::<GlobalVault>(account.borrow_unchecked())? };
+    Ok(())
+}
+
+/// Like [`global_vault`], and the address is derived from the stored seeds and
+/// bump; required before the program signs for the Global Vault.
+#[inline(always)]
+pub fn global_vault_pda(account: &AccountView) -> ProgramResult {
+    program_owned(account)?;
+    let vault = unsafe { load::<GlobalVault>(account.borrow_unchecked())? };
+    check_global_vault(account, vault)
+}
+
+/// The account holds the initialized pending deposits of an Ephemeral ATA.
+#[inline(always)]
+pub fn pending_deposit(account: &AccountView) -> ProgramResult {
+    program_owned(account)?;
+    unsafe { load::<PendingDeposit>(account.borrow_unchecked())? };
+    Ok(())
+}
+
+/// The account holds the initialized withdrawal intent of an Ephemeral ATA, and the
+/// address is derived from the stored seeds and bump.
+#[inline(always)]
+pub fn withdrawal_intent(account: &AccountView) -> ProgramResult {
+    program_owned(account)?;
+    let withdrawal_intent = unsafe { load::<WithdrawalIntent>(account.borrow_unchecked())? };
+    check_withdrawal_intent(account, withdrawal_intent)
+}
+
+/// The account is the undelegate buffer of `delegated`, signed by the delegation
+/// program. Only the delegation program can sign for its PDA, so this proves the
+/// call comes from a genuine undelegation.
+#[inline(always)]
+pub fn undelegate_buffer(account: &AccountView, delegated: &AccountView) -> ProgramResult {
+    signer(account)?;
+    if !account.owned_by(&DELEGATION_PROGRAM_ID) {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if undelegate_buffer_pda_from_delegated_account(delegated.address()) != *account.address() {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+/// The account is the associated token account of `vault` for `mint` under `token_program`.
+#[inline(always)]
+pub fn vault_token_account(
+    account: &AccountView,
+    vault: &AccountView,
+    mint: &AccountView,
+    token_program: &AccountView,
+) -> ProgramResult {
+    token_interface::check_vault_token_account(account, vault, mint, token_program)
+}
+
+/// The account is the SPL Token or the Token-2022 program.
+#[inline(always)]
+pub fn token_program(account: &AccountView) -> ProgramResult {
+    token_interface::check_token_program(account)
+}
+
+/// The account is the Associated Token Account program.
+#[inline(always)]
+pub fn associated_token_program(account: &AccountView) -> ProgramResult {
+    token_interface::check_associated_token_program(account)
+}
+
+/// The account is the system program.
+#[inline(always)]
+pub fn system_program(account: &AccountView) -> ProgramResult {
+    if *account.address() != pinocchio_system::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// The account is the ACL permission program.
+#[inline(always)]
+pub fn permission_program(account: &AccountView) -> ProgramResult {
+    if *account.address() != PERMISSION_PROGRAM_ID {
+        return Err(EphemeralSplError::InvalidPermissionProgram.into());
+    }
+    Ok(())
+}
+
+/// The account is the delegation program.
+#[inline(always)]
+pub fn delegation_program(account: &AccountView) -> ProgramResult {
+    if *account.address() != DELEGATION_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// The account is the magic program of the ephemeral rollup.
+#[inline(always)]
+pub fn magic_program(account: &AccountView) -> ProgramResult {
+    if *account.address() != MAGIC_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// The account is this program.
+#[inline(always)]
+pub fn this_program(account: &AccountView) -> ProgramResult {
+    if *account.address() != ephemeral_spl_api::program::id_address() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
@@ -0,0 +1,183 @@
+use ephemeral_spl_api::program::ID;
+use solana_instruction::Instruction;
+use {
+    ephemeral_spl_api::{
+        error::EphemeralSplError,
+        instruction, pda,
+        state::{load_mut_unchecked, pending_deposit::PendingDeposit, AccountHeader, RawType},
+    },
+    solana_account::Account,
+    solana_instruction::{error::InstructionError, AccountMeta},
+    solana_program_test::{tokio, ProgramTest},
+    solana_pubkey::Pubkey,
+    solana_signer::Signer,
+    solana_transaction::{Transaction, TransactionError},
+};
+
+pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
+
+fn init_ix(ephemeral_ata: Pubkey, payer: Pubkey, user: Pubkey, mint: Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(ephemeral_ata, false),
+            AccountMeta::new_readonly(payer, false),
+            AccountMeta::new_readonly(user, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA],
+    }
+}
+
+fn close_ix(owner: Pubkey, ephemeral_ata: Pubkey, destination: Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new_readonly(owner, true), // [signer] eata owner
+            AccountMeta::new(ephemeral_ata, false), // [writable] eata
+            AccountMeta::new(destination, false),   // [writable] lamports destination
+            AccountMeta::new_readonly(pda::find_pending_deposit(&ephemeral_ata).0, false), // pending deposit
+        ],
+        data: vec![instruction::CLOSE_EPHEMERAL_ATA],
+    }
+}
+
+#[tokio::test]
+async fn close_ephemeral_ata_reclaims_rent() {
+    let context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+
+    let payer = context.payer.pubkey();
+    let mint = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+
+    let (ephemeral_ata, _) =
+        Pubkey::find_program_address(&[payer.as_ref(), mint.as_ref()], &PROGRAM);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[init_ix(ephemeral_ata, payer, payer, mint)],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let rent = context
+        .banks_client
+        .get_account(ephemeral_ata)
+        .await
+        .unwrap()
+        .expect("ephemeral ata account must exist")
+        .lamports;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[close_ix(payer, ephemeral_ata, destination)],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let closed = context
+        .banks_client
+        .get_account(ephemeral_ata)
+        .await
+        .unwrap();
+    assert!(closed.is_none());
+
+    let destination_account = context
+        .banks_client
+        .get_account(destination)
+        .await
+        .unwrap()
+        .expect("destination must receive the rent");
+    assert_eq!(destination_account.lamports, rent);
+}
+
+#[tokio::test]
+async fn close_ephemeral_ata_requires_owner() {
+    let context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+
+    let payer = context.payer.pubkey();
+    let user = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    let (ephemeral_ata, _) =
+        Pubkey::find_program_address(&[user.as_ref(), mint.as_ref()], &PROGRAM);
+
+    // The payer funds the eata but does not own it, so it cannot close it
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            init_ix(ephemeral_ata, payer, user, mint),
+            close_ix(payer, ephemeral_ata, payer),
+        ],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+}
+
+#[tokio::test]
+async fn close_ephemeral_ata_rejects_pending_deposit() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+
+    let payer = context.payer.pubkey();
+    let mint = Pubkey::new_unique();
+
+    let (ephemeral_ata, _) =
+        Pubkey::find_program_address(&[payer.as_ref(), mint.as_ref()], &PROGRAM);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[init_ix(ephemeral_ata, payer, payer, mint)],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // A deposit queued while the Ephemeral ATA was delegated, not credited yet
+    let (pending_deposit, bump) = pda::find_pending_deposit(&ephemeral_ata);
+    let mut data = vec![0u8; PendingDeposit::LEN];
+    let pending = unsafe { load_mut_unchecked::<PendingDeposit>(&mut data).unwrap() };
+    pending.header = AccountHeader::new::<PendingDeposit>(bump);
+    pending.ephemeral_ata = ephemeral_ata;
+    pending.payer = payer;
+    pending.amount = 50;
+    context.set_account(
+        &pending_deposit,
+        &Account {
+            lamports: solana_program::rent::Rent::default().minimum_balance(data.len()),
+            data,
+            owner: PROGRAM,
+            executable: false,
+            rent_epoch: 0,
+        }
+        .into(),
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[close_ix(payer, ephemeral_ata, payer)],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(EphemeralSplError::NonZeroBalance as u32)
+        )
+    );
+}
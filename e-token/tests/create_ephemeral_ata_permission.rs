@@ -38,7 +38,7 @@ async fn create_ephemeral_ata_permission() {
     let user = payer;
     let mint = Pubkey::new_unique();
 
-    let (ephemeral_ata, bump) =
+    let (ephemeral_ata, _) =
         Pubkey::find_program_address(&[user.as_ref(), mint.as_ref()], &PROGRAM);
     let (permission_pda, _) = Pubkey::find_program_address(
         &[b"permission:", ephemeral_ata.as_ref()],
@@ -54,7 +54,7 @@ async fn create_ephemeral_ata_permission() {
             AccountMeta::new_readonly(mint, false),
             AccountMeta::new_readonly(solana_system_interface::program::ID, false),
         ],
-        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA, bump],
+        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA],
     };
 
     let ix_create_permission = Instruction {
@@ -69,7 +69,7 @@ async fn create_ephemeral_ata_permission() {
         data: {
             let flag =
                 ephemeral_rollups_pinocchio::acl::types::MemberFlags::default().to_acl_flag_byte();
-            vec![instruction::CREATE_EPHEMERAL_ATA_PERMISSION, bump, flag]
+            vec![instruction::CREATE_EPHEMERAL_ATA_PERMISSION, flag]
         },
     };
 
@@ -120,7 +120,7 @@ async fn create_ephemeral_ata_permission_permissionless_default() {
     let user = Pubkey::new_unique();
     let mint = Pubkey::new_unique();
 
-    let (ephemeral_ata, bump) =
+    let (ephemeral_ata, _) =
         Pubkey::find_program_address(&[user.as_ref(), mint.as_ref()], &PROGRAM);
     let (permission_pda, _) = Pubkey::find_program_address(
         &[b"permission:", ephemeral_ata.as_ref()],
@@ -136,7 +136,7 @@ async fn create_ephemeral_ata_permission_permissionless_default() {
             AccountMeta::new_readonly(mint, false),
             AccountMeta::new_readonly(solana_system_interface::program::ID, false),
         ],
-        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA, bump],
+        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA],
     };
 
     let ix_create_permission = Instruction {
@@ -151,7 +151,7 @@ async fn create_ephemeral_ata_permission_permissionless_default() {
         data: {
             let flag =
                 ephemeral_rollups_pinocchio::acl::types::MemberFlags::default().to_acl_flag_byte();
-            vec![instruction::CREATE_EPHEMERAL_ATA_PERMISSION, bump, flag]
+            vec![instruction::CREATE_EPHEMERAL_ATA_PERMISSION, flag]
         },
     };
 
@@ -1,15 +1,24 @@
-use ephemeral_spl_api::instruction;
+use ephemeral_rollups_pinocchio::pda::delegate_buffer_pda_from_delegated_account_and_owner_program;
+use ephemeral_rollups_pinocchio::pda::delegation_metadata_pda_from_delegated_account;
+use ephemeral_rollups_pinocchio::pda::delegation_record_pda_from_delegated_account;
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::event::EventKind;
+use ephemeral_spl_api::pda;
 use ephemeral_spl_api::program::ID;
-use ephemeral_spl_api::state::RawType;
+use ephemeral_spl_api::state::{
+    decode, ephemeral_ata::EphemeralAta, global_vault::GlobalVault,
+    pending_deposit::PendingDeposit, RawType,
+};
+use ephemeral_spl_client as client;
 use solana_account::Account;
-use solana_instruction::{AccountMeta, Instruction};
+use solana_instruction::{error::InstructionError, AccountMeta};
 use solana_keypair::Keypair;
 use solana_program::bpf_loader;
 use solana_program::rent::Rent;
-use solana_program_test::{read_file, tokio, ProgramTest};
+use solana_program_test::{read_file, tokio, ProgramTest, ProgramTestContext};
 use solana_pubkey::Pubkey;
 use solana_signer::Signer;
-use solana_transaction::Transaction;
+use solana_transaction::{Transaction, TransactionError};
 
 mod utils;
 
@@ -23,7 +32,7 @@ async fn delegate_ephemeral_ata_succeeds() {
     // Setup the delegation program
     let data = read_file("tests/fixtures/dlp.so");
     pt.add_account(
-        ephemeral_rollups_pinocchio::ID.into(),
+        ephemeral_rollups_pinocchio::ID,
         Account {
             lamports: Rent::default().minimum_balance(data.len()).max(1),
             data,
@@ -42,7 +51,7 @@ async fn delegate_ephemeral_ata_succeeds() {
     let mint = mint_kp.pubkey();
 
     // Derive the PDAs for our program and setup token accounts
-    let pdas = utils::derive_pdas(PROGRAM, user, mint);
+    let pdas = utils::derive_pdas(user, mint);
     let setup = utils::setup_mint_and_token_accounts(
         &mut context,
         payer,
@@ -55,28 +64,8 @@ async fn delegate_ephemeral_ata_succeeds() {
     .await;
 
     // Initialize the Ephemeral ATA and Global Vault (required by the program state)
-    let ix_init_ata = Instruction {
-        program_id: PROGRAM,
-        accounts: vec![
-            AccountMeta::new(pdas.ephemeral_ata, false),
-            AccountMeta::new_readonly(payer, false),
-            AccountMeta::new_readonly(user, false),
-            AccountMeta::new_readonly(mint, false),
-            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
-        ],
-        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA, pdas.bump_ata],
-    };
-
-    let ix_init_vault = Instruction {
-        program_id: PROGRAM,
-        accounts: vec![
-            AccountMeta::new(pdas.vault, false),
-            AccountMeta::new_readonly(payer, false),
-            AccountMeta::new_readonly(mint, false),
-            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
-        ],
-        data: vec![instruction::INITIALIZE_GLOBAL_VAULT, pdas.bump_vault],
-    };
+    let ix_init_ata = client::initialize_ephemeral_ata(&payer, &user, &mint);
+    let ix_init_vault = client::initialize_global_vault(&payer, &mint, &spl_token_interface::ID);
 
     let tx_init = Transaction::new_signed_with_payer(
         &[ix_init_ata, ix_init_vault],
@@ -97,39 +86,9 @@ async fn delegate_ephemeral_ata_succeeds() {
         .await
         .unwrap();
     assert!(ephemeral_ata_account.is_some());
-    assert_eq!(
-        ephemeral_ata_account.unwrap().data.len(),
-        ephemeral_spl_api::state::ephemeral_ata::EphemeralAta::LEN
-    );
+    assert_eq!(ephemeral_ata_account.unwrap().data.len(), EphemeralAta::LEN);
 
-    // Derive required PDAs
-    let (buffer_pda, _) = Pubkey::find_program_address(
-        &[b"buffer", pdas.ephemeral_ata.as_ref()],
-        &ephemeral_spl_api::program::id().into(),
-    );
-    let (delegation_record_pda, _) = Pubkey::find_program_address(
-        &[b"delegation", pdas.ephemeral_ata.as_ref()],
-        &ephemeral_spl_api::program::DELEGATION_PROGRAM_ID.into(),
-    );
-    let (delegation_metadata_pda, _) = Pubkey::find_program_address(
-        &[b"delegation-metadata", pdas.ephemeral_ata.as_ref()],
-        &ephemeral_spl_api::program::DELEGATION_PROGRAM_ID.into(),
-    );
-
-    let ix_delegate = Instruction {
-        program_id: PROGRAM,
-        accounts: vec![
-            AccountMeta::new_readonly(payer, true),      // payer (signer)
-            AccountMeta::new(pdas.ephemeral_ata, false), // ephemeral_ata (PDA)
-            AccountMeta::new_readonly(PROGRAM, false),   // owner_program (this program)
-            AccountMeta::new(buffer_pda, false),         // buffer PDA (created in CPI)
-            AccountMeta::new(delegation_record_pda, false), // delegation record PDA
-            AccountMeta::new(delegation_metadata_pda, false), // delegation metadata PDA
-            AccountMeta::new_readonly(ephemeral_rollups_pinocchio::ID.into(), false), // delegation program
-            AccountMeta::new_readonly(solana_system_interface::program::ID, false), // system program
-        ],
-        data: vec![instruction::DELEGATE_EPHEMERAL_ATA, pdas.bump_ata],
-    };
+    let ix_delegate = client::delegate_ephemeral_ata(&payer, &user, &mint, None);
 
     let tx = Transaction::new_signed_with_payer(
         &[ix_delegate],
@@ -149,20 +108,21 @@ async fn delegate_ephemeral_ata_succeeds() {
     assert!(ata_account.is_some());
     assert_eq!(
         ata_account.unwrap().owner,
-        ephemeral_spl_api::program::DELEGATION_PROGRAM_ID.into()
+        ephemeral_spl_api::program::DELEGATION_PROGRAM_ID
     );
 
     let _ = setup;
 }
 
-#[tokio::test]
-async fn delegate_ephemeral_ata_non_owner_succeeds() {
+/// Start a test with the delegation program, the Global Vault of a new mint and the
+/// Ephemeral ATA of `user`, funded by the context payer.
+async fn start_with_ephemeral_ata(user: Pubkey) -> (ProgramTestContext, Pubkey) {
     let mut pt = ProgramTest::new("ephemeral_token_program", PROGRAM, None);
     pt.prefer_bpf(true);
 
     let data = read_file("tests/fixtures/dlp.so");
     pt.add_account(
-        ephemeral_rollups_pinocchio::ID.into(),
+        ephemeral_rollups_pinocchio::ID,
         Account {
             lamports: Rent::default().minimum_balance(data.len()).max(1),
             data,
@@ -175,12 +135,278 @@ async fn delegate_ephemeral_ata_non_owner_succeeds() {
     let mut context = pt.start_with_context().await;
 
     let payer = context.payer.pubkey();
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+
+    let pdas = utils::derive_pdas(user, mint);
+    utils::setup_mint_and_token_accounts(&mut context, payer, &mint_kp, pdas.vault, 6, 1_000, 1)
+        .await;
+
+    let tx_init = Transaction::new_signed_with_payer(
+        &[
+            client::initialize_ephemeral_ata(&payer, &user, &mint),
+            client::initialize_global_vault(&payer, &mint, &spl_token_interface::ID),
+        ],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(tx_init)
+        .await
+        .unwrap();
+
+    (context, mint)
+}
+
+#[tokio::test]
+async fn delegate_ephemeral_ata_owner_with_separate_fee_payer_succeeds() {
+    let user = Keypair::new();
+    let (context, mint) = start_with_ephemeral_ata(user.pubkey()).await;
+    let payer = context.payer.pubkey();
+
+    let ix_delegate = client::delegate_ephemeral_ata(&payer, &user.pubkey(), &mint, None);
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_delegate],
+        Some(&payer),
+        &[&context.payer, &user],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let (ephemeral_ata, _) = pda::find_ephemeral_ata(&user.pubkey(), &mint);
+    let ata_account = context
+        .banks_client
+        .get_account(ephemeral_ata)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        ata_account.owner,
+        ephemeral_spl_api::program::DELEGATION_PROGRAM_ID
+    );
+}
+
+#[tokio::test]
+async fn delegate_ephemeral_ata_with_commit_frequency_sets_delegation_record() {
+    let user = Keypair::new();
+    let (context, mint) = start_with_ephemeral_ata(user.pubkey()).await;
+    let payer = context.payer.pubkey();
+    let validator = Pubkey::new_unique();
+
+    let ix_delegate = client::delegate_ephemeral_ata_with_commit_frequency(
+        &payer,
+        &user.pubkey(),
+        &mint,
+        Some(&validator),
+        Some(1_500),
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_delegate],
+        Some(&payer),
+        &[&context.payer, &user],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Delegation record: [discriminator, authority, owner, delegation_slot, lamports,
+    // commit_frequency_ms]
+    let (ephemeral_ata, _) = pda::find_ephemeral_ata(&user.pubkey(), &mint);
+    let delegation_record = delegation_record_pda_from_delegated_account(&ephemeral_ata);
+    let record = context
+        .banks_client
+        .get_account(delegation_record)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(&record.data[8..40], validator.as_ref());
+    assert_eq!(&record.data[88..96], &1_500u64.to_le_bytes());
+}
+
+#[tokio::test]
+async fn delegate_ephemeral_ata_by_third_party_fails() {
     let user = Pubkey::new_unique();
+    let (context, mint) = start_with_ephemeral_ata(user).await;
+    let payer = context.payer.pubkey();
+
+    // The owner did not sign
+    let mut unsigned = client::delegate_ephemeral_ata(&payer, &user, &mint, None);
+    unsigned.accounts[8].is_signer = false;
+
+    // The payer signs as the owner, or omits the owner account
+    let mut impersonated = client::delegate_ephemeral_ata(&payer, &user, &mint, None);
+    impersonated.accounts[8].pubkey = payer;
+    let mut omitted = client::delegate_ephemeral_ata(&payer, &user, &mint, None);
+    omitted.accounts.truncate(8);
+
+    for (ix, error) in [
+        (unsigned, InstructionError::MissingRequiredSignature),
+        (
+            impersonated,
+            InstructionError::Custom(EphemeralSplError::OwnerMismatch as u32),
+        ),
+        (
+            omitted,
+            InstructionError::Custom(EphemeralSplError::OwnerMismatch as u32),
+        ),
+    ] {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(tx)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(0, error)
+        );
+    }
+
+    let (ephemeral_ata, _) = pda::find_ephemeral_ata(&user, &mint);
+    let ata_account = context
+        .banks_client
+        .get_account(ephemeral_ata)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(ata_account.owner, PROGRAM);
+}
+
+#[tokio::test]
+async fn delegate_ephemeral_ata_batch_delegates_every_ephemeral_ata() {
+    let user = Keypair::new();
+    let (mut context, first_mint) = start_with_ephemeral_ata(user.pubkey()).await;
+    let payer = context.payer.pubkey();
+
+    let mint_kp = Keypair::new();
+    let second_mint = mint_kp.pubkey();
+    let pdas = utils::derive_pdas(user.pubkey(), second_mint);
+    utils::setup_mint_and_token_accounts(&mut context, payer, &mint_kp, pdas.vault, 6, 1_000, 1)
+        .await;
+
+    // The payer also holds an Ephemeral ATA for the first mint
+    let tx_init = Transaction::new_signed_with_payer(
+        &[
+            client::initialize_ephemeral_ata(&payer, &user.pubkey(), &second_mint),
+            client::initialize_ephemeral_ata(&payer, &payer, &first_mint),
+        ],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(tx_init)
+        .await
+        .unwrap();
+
+    // An Ephemeral ATA of another owner fails the whole batch
+    let mut foreign = client::delegate_ephemeral_ata_batch(
+        &payer,
+        &user.pubkey(),
+        &[first_mint, second_mint],
+        None,
+        None,
+    );
+    let payer_ephemeral_ata = pda::find_ephemeral_ata(&payer, &first_mint).0;
+    let buffer = delegate_buffer_pda_from_delegated_account_and_owner_program(
+        &payer_ephemeral_ata,
+        &PROGRAM,
+    );
+    let delegation_record = delegation_record_pda_from_delegated_account(&payer_ephemeral_ata);
+    let delegation_metadata = delegation_metadata_pda_from_delegated_account(&payer_ephemeral_ata);
+    foreign.accounts[9].pubkey = payer_ephemeral_ata;
+    foreign.accounts[10].pubkey = buffer;
+    foreign.accounts[11].pubkey = delegation_record;
+    foreign.accounts[12].pubkey = delegation_metadata;
+    let tx = Transaction::new_signed_with_payer(
+        &[foreign],
+        Some(&payer),
+        &[&context.payer, &user],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(EphemeralSplError::OwnerMismatch as u32)
+        )
+    );
+
+    let ix_delegate = client::delegate_ephemeral_ata_batch(
+        &payer,
+        &user.pubkey(),
+        &[first_mint, second_mint],
+        None,
+        None,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_delegate],
+        Some(&payer),
+        &[&context.payer, &user],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    for mint in [first_mint, second_mint] {
+        let (ephemeral_ata, _) = pda::find_ephemeral_ata(&user.pubkey(), &mint);
+        let ata_account = context
+            .banks_client
+            .get_account(ephemeral_ata)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            ata_account.owner,
+            ephemeral_spl_api::program::DELEGATION_PROGRAM_ID
+        );
+    }
+    let payer_ata_account = context
+        .banks_client
+        .get_account(payer_ephemeral_ata)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(payer_ata_account.owner, PROGRAM);
+}
+
+#[tokio::test]
+async fn delegated_ephemeral_ata_blocks_withdraw_and_queues_deposits() {
+    let mut pt = ProgramTest::new("ephemeral_token_program", PROGRAM, None);
+    pt.prefer_bpf(true);
+
+    let data = read_file("tests/fixtures/dlp.so");
+    pt.add_account(
+        ephemeral_rollups_pinocchio::ID,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()).max(1),
+            data,
+            owner: bpf_loader::id(),
+            executable: true,
+            rent_epoch: 0,
+        },
+    );
+
+    let mut context = pt.start_with_context().await;
+
+    let payer = context.payer.pubkey();
+    let user = payer;
 
     let mint_kp = Keypair::new();
     let mint = mint_kp.pubkey();
 
-    let pdas = utils::derive_pdas(PROGRAM, user, mint);
+    let pdas = utils::derive_pdas(user, mint);
     let setup = utils::setup_mint_and_token_accounts(
         &mut context,
         payer,
@@ -191,89 +417,226 @@ async fn delegate_ephemeral_ata_non_owner_succeeds() {
         1,
     )
     .await;
+    let user_token = setup.user_tokens[0];
+    let token_program = spl_token_interface::ID;
 
-    let ix_init_ata = Instruction {
-        program_id: PROGRAM,
-        accounts: vec![
-            AccountMeta::new(pdas.ephemeral_ata, false),
-            AccountMeta::new_readonly(payer, false),
-            AccountMeta::new_readonly(user, false),
-            AccountMeta::new_readonly(mint, false),
-            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
-        ],
-        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA, pdas.bump_ata],
-    };
-
-    let ix_init_vault = Instruction {
-        program_id: PROGRAM,
-        accounts: vec![
-            AccountMeta::new(pdas.vault, false),
-            AccountMeta::new_readonly(payer, false),
-            AccountMeta::new_readonly(mint, false),
-            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+    // Initialize, deposit 100 and delegate
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            client::initialize_ephemeral_ata(&payer, &user, &mint),
+            client::initialize_global_vault(&payer, &mint, &token_program),
+            client::deposit_spl_tokens(&user, &payer, &mint, &user_token, &token_program, 100),
+            client::delegate_ephemeral_ata(&payer, &user, &mint, None),
         ],
-        data: vec![instruction::INITIALIZE_GLOBAL_VAULT, pdas.bump_vault],
-    };
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
 
-    let tx_init = Transaction::new_signed_with_payer(
-        &[ix_init_ata, ix_init_vault],
+    // Withdraw and close are rejected while delegated
+    for ix in [
+        client::withdraw_spl_tokens(&user, &mint, &user_token, &token_program, 10),
+        client::close_ephemeral_ata(&user, &mint, &payer),
+    ] {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(tx)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(EphemeralSplError::AccountDelegated as u32)
+            )
+        );
+    }
+
+    // A deposit is held by the vault and queued for the delegated Ephemeral ATA
+    let tx = Transaction::new_signed_with_payer(
+        &[client::deposit_spl_tokens_delegated(
+            &payer,
+            &user,
+            &payer,
+            &mint,
+            &user_token,
+            &token_program,
+            50,
+        )],
         Some(&payer),
         &[&context.payer],
         context.last_blockhash,
     );
-    context
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let (pending_deposit, _) = pda::find_pending_deposit(&pdas.ephemeral_ata);
+    let pending = decode::<PendingDeposit>(
+        &context
+            .banks_client
+            .get_account(pending_deposit)
+            .await
+            .unwrap()
+            .unwrap()
+            .data,
+    )
+    .unwrap();
+    assert_eq!(pending.ephemeral_ata, pdas.ephemeral_ata);
+    assert_eq!(pending.amount, 50);
+
+    let vault = decode::<GlobalVault>(
+        &context
+            .banks_client
+            .get_account(pdas.vault)
+            .await
+            .unwrap()
+            .unwrap()
+            .data,
+    )
+    .unwrap();
+    assert_eq!(vault.total_deposited, 150);
+
+    // Simulate the undelegation: the Ephemeral ATA is owned by the program again
+    let mut ephemeral_ata = context
         .banks_client
-        .process_transaction(tx_init)
+        .get_account(pdas.ephemeral_ata)
         .await
+        .unwrap()
         .unwrap();
-
-    let (buffer_pda, _) = Pubkey::find_program_address(
-        &[b"buffer", pdas.ephemeral_ata.as_ref()],
-        &ephemeral_spl_api::program::id().into(),
-    );
-    let (delegation_record_pda, _) = Pubkey::find_program_address(
-        &[b"delegation", pdas.ephemeral_ata.as_ref()],
-        &ephemeral_spl_api::program::DELEGATION_PROGRAM_ID.into(),
-    );
-    let (delegation_metadata_pda, _) = Pubkey::find_program_address(
-        &[b"delegation-metadata", pdas.ephemeral_ata.as_ref()],
-        &ephemeral_spl_api::program::DELEGATION_PROGRAM_ID.into(),
-    );
-
-    let ix_delegate = Instruction {
-        program_id: PROGRAM,
-        accounts: vec![
-            AccountMeta::new_readonly(payer, true),
-            AccountMeta::new(pdas.ephemeral_ata, false),
-            AccountMeta::new_readonly(PROGRAM, false),
-            AccountMeta::new(buffer_pda, false),
-            AccountMeta::new(delegation_record_pda, false),
-            AccountMeta::new(delegation_metadata_pda, false),
-            AccountMeta::new_readonly(ephemeral_rollups_pinocchio::ID.into(), false),
-            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
-        ],
-        data: vec![instruction::DELEGATE_EPHEMERAL_ATA, pdas.bump_ata],
-    };
+    ephemeral_ata.owner = PROGRAM;
+    context.set_account(&pdas.ephemeral_ata, &ephemeral_ata.into());
 
     let tx = Transaction::new_signed_with_payer(
-        &[ix_delegate],
+        &[client::credit_pending_deposit(&payer, &user, &mint)],
         Some(&payer),
         &[&context.payer],
         context.last_blockhash,
     );
-
     context.banks_client.process_transaction(tx).await.unwrap();
 
-    let ata_account = context
+    let ephemeral_ata = decode::<EphemeralAta>(
+        &context
+            .banks_client
+            .get_account(pdas.ephemeral_ata)
+            .await
+            .unwrap()
+            .unwrap()
+            .data,
+    )
+    .unwrap();
+    assert_eq!(ephemeral_ata.amount, 150);
+    assert!(context
         .banks_client
-        .get_account(pdas.ephemeral_ata)
+        .get_account(pending_deposit)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn commit_ephemeral_ata_emits_commit_event() {
+    let mut pt = ProgramTest::new("ephemeral_token_program", PROGRAM, None);
+    pt.prefer_bpf(true);
+    utils::add_magic_program(&mut pt);
+    let mut context = pt.start_with_context().await;
+
+    // The Ephemeral ATA as seen in the ephemeral rollup
+    let owner = context.payer.pubkey();
+    let mint = Pubkey::new_unique();
+    let pdas = utils::derive_pdas(owner, mint);
+    context.set_account(
+        &pdas.ephemeral_ata,
+        &utils::ephemeral_ata_account(owner, mint, 250, PROGRAM).into(),
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[client::commit_ephemeral_ata(&owner, &mint)],
+        Some(&owner),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let result = context
+        .banks_client
+        .process_transaction_with_metadata(tx)
         .await
         .unwrap();
-    assert!(ata_account.is_some());
+    result.result.unwrap();
+
+    let events = utils::events(&result.metadata.unwrap().log_messages);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, EventKind::Commit);
+    assert_eq!(events[0].ephemeral_ata, pdas.ephemeral_ata);
+    assert_eq!(events[0].owner, owner);
+    assert_eq!(events[0].mint, mint);
+    assert_eq!(events[0].amount, 0);
+    assert_eq!(events[0].post_balance, 250);
+
+    // Only the owner can commit the Ephemeral ATA
+    let other = Keypair::new();
+    let mut ix = client::commit_ephemeral_ata(&owner, &mint);
+    ix.accounts[0] = AccountMeta::new(other.pubkey(), true);
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&owner),
+        &[&context.payer, &other],
+        context.last_blockhash,
+    );
     assert_eq!(
-        ata_account.unwrap().owner,
-        ephemeral_spl_api::program::DELEGATION_PROGRAM_ID.into()
+        context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(EphemeralSplError::OwnerMismatch as u32)
+        )
     );
+}
 
-    let _ = setup;
+#[tokio::test]
+async fn undelegate_ephemeral_ata_accepts_legacy_accounts() {
+    let mut pt = ProgramTest::new("ephemeral_token_program", PROGRAM, None);
+    pt.prefer_bpf(true);
+    utils::add_magic_program(&mut pt);
+    let mut context = pt.start_with_context().await;
+
+    // The Ephemeral ATA as seen in the ephemeral rollup
+    let owner = context.payer.pubkey();
+    let mint = Pubkey::new_unique();
+    let pdas = utils::derive_pdas(owner, mint);
+    context.set_account(
+        &pdas.ephemeral_ata,
+        &utils::ephemeral_ata_account(owner, mint, 250, PROGRAM).into(),
+    );
+
+    // Legacy clients pass the user token account after the payer
+    let mut ix = client::undelegate_ephemeral_ata(&owner, &mint);
+    ix.accounts
+        .insert(1, AccountMeta::new(Pubkey::new_unique(), false));
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&owner),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let result = context
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await
+        .unwrap();
+    result.result.unwrap();
+
+    let events = utils::events(&result.metadata.unwrap().log_messages);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, EventKind::Undelegate);
+    assert_eq!(events[0].ephemeral_ata, pdas.ephemeral_ata);
+    assert_eq!(events[0].post_balance, 250);
 }
@@ -1,14 +1,22 @@
 use ephemeral_rollups_pinocchio::acl::consts::PERMISSION_PROGRAM_ID;
+use ephemeral_rollups_pinocchio::acl::pda::permission_pda_from_permissioned_account;
+use ephemeral_rollups_pinocchio::pda::delegate_buffer_pda_from_delegated_account_and_owner_program;
+use ephemeral_rollups_pinocchio::pda::delegation_metadata_pda_from_delegated_account;
+use ephemeral_rollups_pinocchio::pda::delegation_record_pda_from_delegated_account;
 use ephemeral_spl_api::instruction;
+use ephemeral_spl_api::pda;
 use ephemeral_spl_api::program::ID;
+use ephemeral_spl_client as client;
 use solana_account::Account;
-use solana_instruction::{AccountMeta, Instruction};
+use solana_instruction::{error::InstructionError, AccountMeta, Instruction};
 use solana_program::bpf_loader;
 use solana_program::rent::Rent;
-use solana_program_test::{read_file, tokio, ProgramTest};
+use solana_program_test::{read_file, tokio, ProgramTest, ProgramTestContext};
 use solana_pubkey::Pubkey;
 use solana_signer::Signer;
-use solana_transaction::Transaction;
+use solana_transaction::{Transaction, TransactionError};
+
+mod utils;
 
 pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
 
@@ -19,7 +27,7 @@ async fn delegate_ephemeral_ata_permission_succeeds() {
 
     let acl_data = read_file("tests/fixtures/acl.so");
     pt.add_account(
-        PERMISSION_PROGRAM_ID.into(),
+        PERMISSION_PROGRAM_ID,
         Account {
             lamports: Rent::default().minimum_balance(acl_data.len()).max(1),
             data: acl_data,
@@ -31,7 +39,7 @@ async fn delegate_ephemeral_ata_permission_succeeds() {
 
     let dlp_data = read_file("tests/fixtures/dlp.so");
     pt.add_account(
-        ephemeral_rollups_pinocchio::ID.into(),
+        ephemeral_rollups_pinocchio::ID,
         Account {
             lamports: Rent::default().minimum_balance(dlp_data.len()).max(1),
             data: dlp_data,
@@ -59,14 +67,8 @@ async fn delegate_ephemeral_ata_permission_succeeds() {
     let user = payer;
     let mint = Pubkey::new_unique();
 
-    let (ephemeral_ata, bump) = Pubkey::find_program_address(
-        &[user.to_bytes().as_slice(), mint.to_bytes().as_slice()],
-        &PROGRAM,
-    );
-    let (permission_pda, _) = Pubkey::find_program_address(
-        &[b"permission:", ephemeral_ata.as_ref()],
-        &PERMISSION_PROGRAM_ID.into(),
-    );
+    let (ephemeral_ata, _) = pda::find_ephemeral_ata(&user, &mint);
+    let permission_pda = permission_pda_from_permissioned_account(&ephemeral_ata);
 
     let ix_init_ata = Instruction {
         program_id: PROGRAM,
@@ -77,7 +79,7 @@ async fn delegate_ephemeral_ata_permission_succeeds() {
             AccountMeta::new_readonly(mint, false),
             AccountMeta::new_readonly(solana_system_interface::program::ID, false),
         ],
-        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA, bump],
+        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA],
     };
 
     let ix_create_permission = Instruction {
@@ -87,12 +89,12 @@ async fn delegate_ephemeral_ata_permission_succeeds() {
             AccountMeta::new(permission_pda, false),
             AccountMeta::new(payer, true),
             AccountMeta::new_readonly(solana_system_interface::program::ID, false),
-            AccountMeta::new_readonly(PERMISSION_PROGRAM_ID.into(), false),
+            AccountMeta::new_readonly(PERMISSION_PROGRAM_ID, false),
         ],
         data: {
             let flag =
                 ephemeral_rollups_pinocchio::acl::types::MemberFlags::default().to_acl_flag_byte();
-            vec![instruction::CREATE_EPHEMERAL_ATA_PERMISSION, bump, flag]
+            vec![instruction::CREATE_EPHEMERAL_ATA_PERMISSION, flag]
         },
     };
 
@@ -108,34 +110,28 @@ async fn delegate_ephemeral_ata_permission_succeeds() {
         .await
         .unwrap();
 
-    let (buffer_pda, _) = Pubkey::find_program_address(
-        &[b"buffer", permission_pda.as_ref()],
-        &PERMISSION_PROGRAM_ID.into(),
-    );
-    let (delegation_record_pda, _) = Pubkey::find_program_address(
-        &[b"delegation", permission_pda.as_ref()],
-        &ephemeral_spl_api::program::DELEGATION_PROGRAM_ID.into(),
-    );
-    let (delegation_metadata_pda, _) = Pubkey::find_program_address(
-        &[b"delegation-metadata", permission_pda.as_ref()],
-        &ephemeral_spl_api::program::DELEGATION_PROGRAM_ID.into(),
+    let buffer_pda = delegate_buffer_pda_from_delegated_account_and_owner_program(
+        &permission_pda,
+        &PERMISSION_PROGRAM_ID,
     );
+    let delegation_record_pda = delegation_record_pda_from_delegated_account(&permission_pda);
+    let delegation_metadata_pda = delegation_metadata_pda_from_delegated_account(&permission_pda);
 
     let ix_delegate_permission = Instruction {
         program_id: PROGRAM,
         accounts: vec![
             AccountMeta::new(payer, true),
             AccountMeta::new(ephemeral_ata, false),
-            AccountMeta::new_readonly(PERMISSION_PROGRAM_ID.into(), false),
+            AccountMeta::new_readonly(PERMISSION_PROGRAM_ID, false),
             AccountMeta::new(permission_pda, false),
             AccountMeta::new_readonly(solana_system_interface::program::ID, false),
             AccountMeta::new(buffer_pda, false),
             AccountMeta::new(delegation_record_pda, false),
             AccountMeta::new(delegation_metadata_pda, false),
-            AccountMeta::new_readonly(ephemeral_rollups_pinocchio::ID.into(), false),
+            AccountMeta::new_readonly(ephemeral_rollups_pinocchio::ID, false),
             AccountMeta::new_readonly(validator, false),
         ],
-        data: vec![instruction::DELEGATE_EPHEMERAL_ATA_PERMISSION, bump],
+        data: vec![instruction::DELEGATE_EPHEMERAL_ATA_PERMISSION],
     };
 
     let tx_delegate = Transaction::new_signed_with_payer(
@@ -158,7 +154,7 @@ async fn delegate_ephemeral_ata_permission_succeeds() {
         .expect("permission account must exist");
     assert_eq!(
         permission_account.owner,
-        ephemeral_spl_api::program::DELEGATION_PROGRAM_ID.into()
+        ephemeral_spl_api::program::DELEGATION_PROGRAM_ID
     );
 }
 
@@ -169,7 +165,7 @@ async fn delegate_ephemeral_ata_permission_non_owner_succeeds() {
 
     let acl_data = read_file("tests/fixtures/acl.so");
     pt.add_account(
-        PERMISSION_PROGRAM_ID.into(),
+        PERMISSION_PROGRAM_ID,
         Account {
             lamports: Rent::default().minimum_balance(acl_data.len()).max(1),
             data: acl_data,
@@ -181,7 +177,7 @@ async fn delegate_ephemeral_ata_permission_non_owner_succeeds() {
 
     let dlp_data = read_file("tests/fixtures/dlp.so");
     pt.add_account(
-        ephemeral_rollups_pinocchio::ID.into(),
+        ephemeral_rollups_pinocchio::ID,
         Account {
             lamports: Rent::default().minimum_balance(dlp_data.len()).max(1),
             data: dlp_data,
@@ -209,14 +205,8 @@ async fn delegate_ephemeral_ata_permission_non_owner_succeeds() {
     let user = Pubkey::new_unique();
     let mint = Pubkey::new_unique();
 
-    let (ephemeral_ata, bump) = Pubkey::find_program_address(
-        &[user.to_bytes().as_slice(), mint.to_bytes().as_slice()],
-        &PROGRAM,
-    );
-    let (permission_pda, _) = Pubkey::find_program_address(
-        &[b"permission:", ephemeral_ata.as_ref()],
-        &PERMISSION_PROGRAM_ID.into(),
-    );
+    let (ephemeral_ata, _) = pda::find_ephemeral_ata(&user, &mint);
+    let permission_pda = permission_pda_from_permissioned_account(&ephemeral_ata);
 
     let ix_init_ata = Instruction {
         program_id: PROGRAM,
@@ -227,7 +217,7 @@ async fn delegate_ephemeral_ata_permission_non_owner_succeeds() {
             AccountMeta::new_readonly(mint, false),
             AccountMeta::new_readonly(solana_system_interface::program::ID, false),
         ],
-        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA, bump],
+        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA],
     };
 
     let ix_create_permission = Instruction {
@@ -237,12 +227,12 @@ async fn delegate_ephemeral_ata_permission_non_owner_succeeds() {
             AccountMeta::new(permission_pda, false),
             AccountMeta::new(payer, true),
             AccountMeta::new_readonly(solana_system_interface::program::ID, false),
-            AccountMeta::new_readonly(PERMISSION_PROGRAM_ID.into(), false),
+            AccountMeta::new_readonly(PERMISSION_PROGRAM_ID, false),
         ],
         data: {
             let flag =
                 ephemeral_rollups_pinocchio::acl::types::MemberFlags::default().to_acl_flag_byte();
-            vec![instruction::CREATE_EPHEMERAL_ATA_PERMISSION, bump, flag]
+            vec![instruction::CREATE_EPHEMERAL_ATA_PERMISSION, flag]
         },
     };
 
@@ -258,34 +248,28 @@ async fn delegate_ephemeral_ata_permission_non_owner_succeeds() {
         .await
         .unwrap();
 
-    let (buffer_pda, _) = Pubkey::find_program_address(
-        &[b"buffer", permission_pda.as_ref()],
-        &PERMISSION_PROGRAM_ID.into(),
-    );
-    let (delegation_record_pda, _) = Pubkey::find_program_address(
-        &[b"delegation", permission_pda.as_ref()],
-        &ephemeral_spl_api::program::DELEGATION_PROGRAM_ID.into(),
-    );
-    let (delegation_metadata_pda, _) = Pubkey::find_program_address(
-        &[b"delegation-metadata", permission_pda.as_ref()],
-        &ephemeral_spl_api::program::DELEGATION_PROGRAM_ID.into(),
+    let buffer_pda = delegate_buffer_pda_from_delegated_account_and_owner_program(
+        &permission_pda,
+        &PERMISSION_PROGRAM_ID,
     );
+    let delegation_record_pda = delegation_record_pda_from_delegated_account(&permission_pda);
+    let delegation_metadata_pda = delegation_metadata_pda_from_delegated_account(&permission_pda);
 
     let ix_delegate_permission = Instruction {
         program_id: PROGRAM,
         accounts: vec![
             AccountMeta::new(payer, true),
             AccountMeta::new(ephemeral_ata, false),
-            AccountMeta::new_readonly(PERMISSION_PROGRAM_ID.into(), false),
+            AccountMeta::new_readonly(PERMISSION_PROGRAM_ID, false),
             AccountMeta::new(permission_pda, false),
             AccountMeta::new_readonly(solana_system_interface::program::ID, false),
             AccountMeta::new(buffer_pda, false),
             AccountMeta::new(delegation_record_pda, false),
             AccountMeta::new(delegation_metadata_pda, false),
-            AccountMeta::new_readonly(ephemeral_rollups_pinocchio::ID.into(), false),
+            AccountMeta::new_readonly(ephemeral_rollups_pinocchio::ID, false),
             AccountMeta::new_readonly(validator, false),
         ],
-        data: vec![instruction::DELEGATE_EPHEMERAL_ATA_PERMISSION, bump],
+        data: vec![instruction::DELEGATE_EPHEMERAL_ATA_PERMISSION],
     };
 
     let tx_delegate = Transaction::new_signed_with_payer(
@@ -308,6 +292,81 @@ async fn delegate_ephemeral_ata_permission_non_owner_succeeds() {
         .expect("permission account must exist");
     assert_eq!(
         permission_account.owner,
-        ephemeral_spl_api::program::DELEGATION_PROGRAM_ID.into()
+        ephemeral_spl_api::program::DELEGATION_PROGRAM_ID
+    );
+}
+
+async fn delegate_permission(
+    context: &mut ProgramTestContext,
+    ix: Instruction,
+) -> Result<(), TransactionError> {
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+#[tokio::test]
+async fn delegate_ephemeral_ata_permission_rejects_invalid_ephemeral_ata() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+
+    let payer = context.payer.pubkey();
+    let mint = Pubkey::new_unique();
+    let validator = Pubkey::new_unique();
+    let ephemeral_ata = utils::derive_pdas(payer, mint).ephemeral_ata;
+
+    // Owned by neither this program nor the delegation program
+    context.set_account(
+        &ephemeral_ata,
+        &utils::ephemeral_ata_account(payer, mint, 0, Pubkey::new_unique()).into(),
+    );
+    let ix = client::delegate_ephemeral_ata_permission(&payer, &payer, &mint, &validator);
+    assert_eq!(
+        delegate_permission(&mut context, ix.clone()).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::IllegalOwner
+        ))
+    );
+
+    // Not writable
+    context.set_account(
+        &ephemeral_ata,
+        &utils::ephemeral_ata_account(payer, mint, 0, PROGRAM).into(),
+    );
+    let mut readonly = ix.clone();
+    readonly.accounts[1] = AccountMeta::new_readonly(ephemeral_ata, false);
+    assert_eq!(
+        delegate_permission(&mut context, readonly).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Immutable
+        ))
+    );
+
+    // A valid Ephemeral ATA at an address that is not its PDA
+    let other = Pubkey::new_unique();
+    context.set_account(
+        &other,
+        &utils::ephemeral_ata_account(payer, mint, 0, PROGRAM).into(),
+    );
+    let mut wrong_pda = ix;
+    wrong_pda.accounts[1] = AccountMeta::new(other, false);
+    assert_eq!(
+        delegate_permission(&mut context, wrong_pda).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidSeeds
+        ))
     );
 }
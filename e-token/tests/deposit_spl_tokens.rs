@@ -1,8 +1,10 @@
-use ephemeral_spl_api::instruction;
 use ephemeral_spl_api::program::ID;
 use ephemeral_spl_api::state::ephemeral_ata::EphemeralAta;
-use ephemeral_spl_api::state::{load_mut_unchecked, RawType};
-use solana_instruction::{AccountMeta, Instruction};
+use ephemeral_spl_api::state::pending_deposit::PendingDeposit;
+use ephemeral_spl_api::state::{decode, load_mut_unchecked, RawType};
+use ephemeral_spl_api::{instruction, pda};
+use ephemeral_spl_client as client;
+use solana_instruction::{error::InstructionError, AccountMeta, Instruction};
 use solana_keypair::Keypair;
 use solana_program_pack::Pack;
 use spl_token_interface::state::Account;
@@ -10,7 +12,7 @@ use {
     solana_program_test::{tokio, ProgramTest},
     solana_pubkey::Pubkey,
     solana_signer::Signer,
-    solana_transaction::Transaction,
+    solana_transaction::{Transaction, TransactionError},
 };
 
 mod utils;
@@ -33,7 +35,7 @@ async fn deposit_spl_tokens_increments_ephemeral_amount() {
     let mint = mint_kp.pubkey();
 
     // Derive PDAs and setup mint/accounts via utils
-    let pdas = utils::derive_pdas(PROGRAM, user, mint);
+    let pdas = utils::derive_pdas(user, mint);
     let setup = utils::setup_mint_and_token_accounts(
         &mut context,
         payer,
@@ -46,9 +48,7 @@ async fn deposit_spl_tokens_increments_ephemeral_amount() {
     .await;
 
     let ephemeral_ata = pdas.ephemeral_ata;
-    let bump_ata = pdas.bump_ata;
     let vault = pdas.vault;
-    let bump_vault = pdas.bump_vault;
     let user_ata = setup.user_tokens[0];
     let vault_ata = setup.vault_token;
 
@@ -62,15 +62,6 @@ async fn deposit_spl_tokens_increments_ephemeral_amount() {
     let user_token_state_before = Account::unpack(&user_token_acc_before.data).unwrap();
     assert_eq!(user_token_state_before.amount, STARTING_BALANCE);
 
-    let vault_token_acc_before = context
-        .banks_client
-        .get_account(vault_ata)
-        .await
-        .unwrap()
-        .expect("vault token account must exist");
-    let vault_token_state_before = Account::unpack(&vault_token_acc_before.data).unwrap();
-    assert_eq!(vault_token_state_before.amount, 0);
-
     // 1) Initialize Ephemeral ATA
     let ix_init_ata = Instruction {
         program_id: PROGRAM,
@@ -81,7 +72,7 @@ async fn deposit_spl_tokens_increments_ephemeral_amount() {
             AccountMeta::new_readonly(mint, false),
             AccountMeta::new_readonly(solana_system_interface::program::ID, false),
         ],
-        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA, bump_ata],
+        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA],
     };
 
     // 2) Initialize Global Vault
@@ -92,8 +83,11 @@ async fn deposit_spl_tokens_increments_ephemeral_amount() {
             AccountMeta::new_readonly(payer, false),
             AccountMeta::new_readonly(mint, false),
             AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+            AccountMeta::new(vault_ata, false),
+            AccountMeta::new_readonly(spl_token_interface::ID, false),
+            AccountMeta::new_readonly(utils::ASSOCIATED_TOKEN_PROGRAM_ID, false),
         ],
-        data: vec![instruction::INITIALIZE_GLOBAL_VAULT, bump_vault],
+        data: vec![instruction::INITIALIZE_GLOBAL_VAULT],
     };
 
     // Send both initializations in one tx
@@ -109,6 +103,17 @@ async fn deposit_spl_tokens_increments_ephemeral_amount() {
         .await
         .unwrap();
 
+    // The vault token account is created by InitializeGlobalVault
+    let vault_token_acc_before = context
+        .banks_client
+        .get_account(vault_ata)
+        .await
+        .unwrap()
+        .expect("vault token account must exist");
+    let vault_token_state_before = Account::unpack(&vault_token_acc_before.data).unwrap();
+    assert_eq!(vault_token_state_before.amount, 0);
+    assert_eq!(vault_token_state_before.owner, vault);
+
     // 3) Deposit amount from payer's token to vault's token and increment Ephemeral ATA amount
     let amount: u64 = 100 * 10u64.pow(DECIMALS as u32);
     let mut data = vec![instruction::DEPOSIT_SPL_TOKENS];
@@ -118,7 +123,7 @@ async fn deposit_spl_tokens_increments_ephemeral_amount() {
         program_id: PROGRAM,
         accounts: vec![
             AccountMeta::new(ephemeral_ata, false), // [writable] Ephemeral ATA data
-            AccountMeta::new_readonly(vault, false), // [] Global vault data
+            AccountMeta::new(vault, false),         // [writable] Global vault data
             AccountMeta::new_readonly(mint, false), // [] Mint pubkey (seed/consistency)
             AccountMeta::new(user_ata, false),      // [writable] user source token acc
             AccountMeta::new(vault_ata, false),     // [writable] vault token acc
@@ -170,3 +175,231 @@ async fn deposit_spl_tokens_increments_ephemeral_amount() {
     let ata_data = unsafe { load_mut_unchecked::<EphemeralAta>(mut_acc.as_mut_slice()).unwrap() };
     assert_eq!(ata_data.amount, amount);
 }
+
+#[tokio::test]
+async fn deposit_spl_tokens_rejects_foreign_vault_token_account() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+
+    let payer = context.payer.pubkey();
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+
+    let pdas = utils::derive_pdas(payer, mint);
+    let setup = utils::setup_mint_and_token_accounts(
+        &mut context,
+        payer,
+        &mint_kp,
+        pdas.vault,
+        DECIMALS,
+        STARTING_BALANCE,
+        2,
+    )
+    .await;
+
+    let ix_init_ata = Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(pdas.ephemeral_ata, false),
+            AccountMeta::new_readonly(payer, false),
+            AccountMeta::new_readonly(payer, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA],
+    };
+    let ix_init_vault = Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(pdas.vault, false),
+            AccountMeta::new_readonly(payer, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+            AccountMeta::new(setup.vault_token, false),
+            AccountMeta::new_readonly(spl_token_interface::ID, false),
+            AccountMeta::new_readonly(utils::ASSOCIATED_TOKEN_PROGRAM_ID, false),
+        ],
+        data: vec![instruction::INITIALIZE_GLOBAL_VAULT],
+    };
+
+    // Deposit into a token account that is not the vault ATA
+    let mut data = vec![instruction::DEPOSIT_SPL_TOKENS];
+    data.extend_from_slice(&(10u64.pow(DECIMALS as u32)).to_le_bytes());
+    let ix_deposit = Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(pdas.ephemeral_ata, false),
+            AccountMeta::new(pdas.vault, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(setup.user_tokens[0], false),
+            AccountMeta::new(setup.user_tokens[1], false),
+            AccountMeta::new_readonly(payer, true),
+            AccountMeta::new_readonly(spl_token_interface::ID, false),
+        ],
+        data,
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_init_ata, ix_init_vault, ix_deposit],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+}
+
+#[tokio::test]
+async fn deposit_spl_tokens_rejects_foreign_ephemeral_ata() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+
+    let payer = context.payer.pubkey();
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+
+    let pdas = utils::derive_pdas(payer, mint);
+    let setup = utils::setup_mint_and_token_accounts(
+        &mut context,
+        payer,
+        &mint_kp,
+        pdas.vault,
+        DECIMALS,
+        STARTING_BALANCE,
+        1,
+    )
+    .await;
+
+    let tx_init = Transaction::new_signed_with_payer(
+        &[client::initialize_global_vault(
+            &payer,
+            &mint,
+            &spl_token_interface::ID,
+        )],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(tx_init)
+        .await
+        .unwrap();
+
+    // A well-formed Ephemeral ATA at the PDA, but owned by another program
+    context.set_account(
+        &pdas.ephemeral_ata,
+        &utils::ephemeral_ata_account(payer, mint, 0, Pubkey::new_unique()).into(),
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[client::deposit_spl_tokens(
+            &payer,
+            &payer,
+            &mint,
+            &setup.user_tokens[0],
+            &spl_token_interface::ID,
+            1,
+        )],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IllegalOwner)
+    );
+}
+
+#[tokio::test]
+async fn deposit_spl_tokens_delegated_takes_over_prefunded_pending_deposit() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+
+    let payer = context.payer.pubkey();
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+
+    let pdas = utils::derive_pdas(payer, mint);
+    let setup = utils::setup_mint_and_token_accounts(
+        &mut context,
+        payer,
+        &mint_kp,
+        pdas.vault,
+        DECIMALS,
+        STARTING_BALANCE,
+        1,
+    )
+    .await;
+
+    let tx_init = Transaction::new_signed_with_payer(
+        &[client::initialize_global_vault(
+            &payer,
+            &mint,
+            &spl_token_interface::ID,
+        )],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(tx_init)
+        .await
+        .unwrap();
+
+    // The Ephemeral ATA is delegated, and someone already sent lamports to its
+    // pending deposit PDA
+    context.set_account(
+        &pdas.ephemeral_ata,
+        &utils::ephemeral_ata_account(payer, mint, 0, ephemeral_rollups_pinocchio::ID).into(),
+    );
+    let (pending_deposit, _) = pda::find_pending_deposit(&pdas.ephemeral_ata);
+    context.set_account(
+        &pending_deposit,
+        &solana_account::Account {
+            lamports: 1,
+            owner: solana_system_interface::program::ID,
+            ..Default::default()
+        }
+        .into(),
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[client::deposit_spl_tokens_delegated(
+            &payer,
+            &payer,
+            &payer,
+            &mint,
+            &setup.user_tokens[0],
+            &spl_token_interface::ID,
+            10,
+        )],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(pending_deposit)
+        .await
+        .unwrap()
+        .expect("pending deposit must exist");
+    assert_eq!(account.owner, PROGRAM);
+    assert_eq!(
+        account.lamports,
+        solana_program::rent::Rent::default().minimum_balance(PendingDeposit::LEN)
+    );
+    let pending = decode::<PendingDeposit>(&account.data).unwrap();
+    assert_eq!(pending.ephemeral_ata, pdas.ephemeral_ata);
+    assert_eq!(pending.amount, 10);
+}
@@ -0,0 +1,304 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::instruction;
+use ephemeral_spl_api::program::ID;
+use ephemeral_spl_api::state::ephemeral_ata::EphemeralAta;
+use ephemeral_spl_api::state::{decode, global_vault::GlobalVault, load_mut_unchecked};
+use ephemeral_spl_client as client;
+use solana_instruction::error::InstructionError;
+use solana_instruction::{AccountMeta, Instruction};
+use solana_keypair::Keypair;
+use solana_program_test::ProgramTestContext;
+use solana_system_interface::instruction::create_account;
+use solana_transaction::TransactionError;
+use spl_token_2022_interface::extension::transfer_fee::instruction::initialize_transfer_fee_config;
+use spl_token_2022_interface::extension::{ExtensionType, StateWithExtensions};
+use spl_token_2022_interface::instruction::{
+    initialize_account3, initialize_mint2, initialize_non_transferable_mint,
+    initialize_permanent_delegate, mint_to,
+};
+use spl_token_2022_interface::state::{Account, Mint};
+use {
+    solana_program_test::{tokio, ProgramTest},
+    solana_pubkey::Pubkey,
+    solana_signer::Signer,
+    solana_transaction::Transaction,
+};
+
+mod utils;
+
+pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
+
+const DECIMALS: u8 = 6;
+const STARTING_BALANCE: u64 = 10_000 * 10u64.pow(DECIMALS as u32);
+const DEPOSIT_AMOUNT: u64 = 100 * 10u64.pow(DECIMALS as u32);
+const FEE_BASIS_POINTS: u16 = 100; // 1%
+
+struct Setup {
+    mint: Pubkey,
+    ephemeral_ata: Pubkey,
+    vault: Pubkey,
+    user_token: Pubkey,
+    vault_token: Pubkey,
+}
+
+// Creates a Token-2022 mint with the given extension and a funded user token account, and
+// initializes the payer's Ephemeral ATA and the Global Vault (which creates the vault ATA).
+async fn setup(context: &mut ProgramTestContext, extension: ExtensionType) -> Setup {
+    let token_program = spl_token_2022_interface::ID;
+    let payer = context.payer.pubkey();
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+    let user_token_kp = Keypair::new();
+    let pdas = utils::derive_pdas(payer, mint);
+    let vault_token = utils::vault_ata(pdas.vault, mint, token_program);
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let mint_len = ExtensionType::try_calculate_account_len::<Mint>(&[extension]).unwrap();
+    let account_len = ExtensionType::try_calculate_account_len::<Account>(
+        &ExtensionType::get_required_init_account_extensions(&[extension]),
+    )
+    .unwrap();
+
+    let init_extension = match extension {
+        ExtensionType::TransferFeeConfig => initialize_transfer_fee_config(
+            &token_program,
+            &mint,
+            None,
+            None,
+            FEE_BASIS_POINTS,
+            u64::MAX,
+        )
+        .unwrap(),
+        ExtensionType::NonTransferable => {
+            initialize_non_transferable_mint(&token_program, &mint).unwrap()
+        }
+        ExtensionType::PermanentDelegate => {
+            initialize_permanent_delegate(&token_program, &mint, &payer).unwrap()
+        }
+        ext => panic!("unsupported extension {ext:?}"),
+    };
+
+    let mut instructions = vec![
+        create_account(
+            &payer,
+            &mint,
+            rent.minimum_balance(mint_len),
+            mint_len as u64,
+            &token_program,
+        ),
+        init_extension,
+        initialize_mint2(&token_program, &mint, &payer, None, DECIMALS).unwrap(),
+    ];
+    instructions.push(create_account(
+        &payer,
+        &user_token_kp.pubkey(),
+        rent.minimum_balance(account_len),
+        account_len as u64,
+        &token_program,
+    ));
+    instructions
+        .push(initialize_account3(&token_program, &user_token_kp.pubkey(), &mint, &payer).unwrap());
+    instructions.push(
+        mint_to(
+            &token_program,
+            &mint,
+            &user_token_kp.pubkey(),
+            &payer,
+            &[],
+            STARTING_BALANCE,
+        )
+        .unwrap(),
+    );
+    instructions.push(Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(pdas.ephemeral_ata, false),
+            AccountMeta::new_readonly(payer, false),
+            AccountMeta::new_readonly(payer, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA],
+    });
+    instructions.push(Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(pdas.vault, false),
+            AccountMeta::new_readonly(payer, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+            AccountMeta::new(vault_token, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(utils::ASSOCIATED_TOKEN_PROGRAM_ID, false),
+        ],
+        data: vec![instruction::INITIALIZE_GLOBAL_VAULT],
+    });
+
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer),
+        &[&context.payer, &mint_kp, &user_token_kp],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    Setup {
+        mint,
+        ephemeral_ata: pdas.ephemeral_ata,
+        vault: pdas.vault,
+        user_token: user_token_kp.pubkey(),
+        vault_token,
+    }
+}
+
+fn deposit_ix(setup: &Setup, authority: Pubkey, amount: u64) -> Instruction {
+    let mut data = vec![instruction::DEPOSIT_SPL_TOKENS];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(setup.ephemeral_ata, false),
+            AccountMeta::new(setup.vault, false),
+            AccountMeta::new_readonly(setup.mint, false),
+            AccountMeta::new(setup.user_token, false),
+            AccountMeta::new(setup.vault_token, false),
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new_readonly(spl_token_2022_interface::ID, false),
+        ],
+        data,
+    }
+}
+
+#[tokio::test]
+async fn deposit_token_2022_credits_amount_after_transfer_fee() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+    let payer = context.payer.pubkey();
+    let setup = setup(&mut context, ExtensionType::TransferFeeConfig).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_ix(&setup, payer, DEPOSIT_AMOUNT)],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let fee = DEPOSIT_AMOUNT * FEE_BASIS_POINTS as u64 / 10_000;
+    let mut data = context
+        .banks_client
+        .get_account(setup.ephemeral_ata)
+        .await
+        .unwrap()
+        .expect("ephemeral ata account must exist")
+        .data;
+    let ephemeral_ata = unsafe { load_mut_unchecked::<EphemeralAta>(data.as_mut_slice()).unwrap() };
+    assert_eq!(ephemeral_ata.amount, DEPOSIT_AMOUNT - fee);
+}
+
+#[tokio::test]
+async fn deposit_token_2022_rejects_non_transferable_mint() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+    let payer = context.payer.pubkey();
+    let setup = setup(&mut context, ExtensionType::NonTransferable).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_ix(&setup, payer, DEPOSIT_AMOUNT)],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let err = context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(EphemeralSplError::NonTransferableMint as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn deposit_token_2022_rejects_permanent_delegate_mint() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+    let payer = context.payer.pubkey();
+    let setup = setup(&mut context, ExtensionType::PermanentDelegate).await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[deposit_ix(&setup, payer, DEPOSIT_AMOUNT)],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let err = context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(EphemeralSplError::PermanentDelegateMint as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn withdraw_token_2022_debits_full_amount_after_transfer_fee() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+    let payer = context.payer.pubkey();
+    let setup = setup(&mut context, ExtensionType::TransferFeeConfig).await;
+
+    let fee = |amount: u64| amount * FEE_BASIS_POINTS as u64 / 10_000;
+    let credited = DEPOSIT_AMOUNT - fee(DEPOSIT_AMOUNT);
+    let withdraw_amount = credited / 2;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            deposit_ix(&setup, payer, DEPOSIT_AMOUNT),
+            client::withdraw_spl_tokens(
+                &payer,
+                &setup.mint,
+                &setup.user_token,
+                &spl_token_2022_interface::ID,
+                withdraw_amount,
+            ),
+        ],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The Ephemeral ATA and the vault deposits are debited the full amount
+    let account = |address| {
+        let banks_client = context.banks_client.clone();
+        async move { banks_client.get_account(address).await.unwrap().unwrap() }
+    };
+    let ephemeral_ata = decode::<EphemeralAta>(&account(setup.ephemeral_ata).await.data).unwrap();
+    assert_eq!(ephemeral_ata.amount, credited - withdraw_amount);
+    let vault = decode::<GlobalVault>(&account(setup.vault).await.data).unwrap();
+    assert_eq!(vault.total_deposited, credited - withdraw_amount);
+
+    // The fee is withheld from what the user receives
+    let user_token = account(setup.user_token).await;
+    let user_token = StateWithExtensions::<Account>::unpack(&user_token.data).unwrap();
+    assert_eq!(
+        user_token.base.amount,
+        STARTING_BALANCE - DEPOSIT_AMOUNT + withdraw_amount - fee(withdraw_amount)
+    );
+}
@@ -0,0 +1,258 @@
+use ephemeral_rollups_pinocchio::acl::consts::PERMISSION_PROGRAM_ID;
+use ephemeral_rollups_pinocchio::acl::pda::permission_pda_from_permissioned_account;
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::program::{DELEGATION_PROGRAM_ID, ID};
+use ephemeral_spl_api::state::{decode, ephemeral_ata::EphemeralAta, global_vault::GlobalVault};
+use ephemeral_spl_client as client;
+use solana_account::Account;
+use solana_instruction::error::InstructionError;
+use solana_keypair::Keypair;
+use solana_program::bpf_loader;
+use solana_program::rent::Rent;
+use solana_program_pack::Pack;
+use solana_program_test::{read_file, tokio, ProgramTest, ProgramTestContext};
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+use solana_transaction::{Transaction, TransactionError};
+use spl_token_interface::state::Account as SplAccount;
+
+mod utils;
+
+pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
+
+/// Start a test with the delegation and permission programs and a new mint, with
+/// 1,000 tokens in a token account of the context payer.
+async fn start() -> (ProgramTestContext, Pubkey, Pubkey) {
+    let mut pt = ProgramTest::new("ephemeral_token_program", PROGRAM, None);
+    pt.prefer_bpf(true);
+
+    for (program_id, fixture) in [
+        (DELEGATION_PROGRAM_ID, "tests/fixtures/dlp.so"),
+        (PERMISSION_PROGRAM_ID, "tests/fixtures/acl.so"),
+    ] {
+        let data = read_file(fixture);
+        pt.add_account(
+            program_id,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()).max(1),
+                data,
+                owner: bpf_loader::id(),
+                executable: true,
+                rent_epoch: 0,
+            },
+        );
+    }
+
+    let mut context = pt.start_with_context().await;
+
+    let payer = context.payer.pubkey();
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+    let pdas = utils::derive_pdas(payer, mint);
+    let setup = utils::setup_mint_and_token_accounts(
+        &mut context,
+        payer,
+        &mint_kp,
+        pdas.vault,
+        6,
+        1_000,
+        1,
+    )
+    .await;
+
+    (context, mint, setup.user_tokens[0])
+}
+
+async fn account(context: &mut ProgramTestContext, address: Pubkey) -> Account {
+    context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn enter_rollup_creates_deposits_and_delegates() {
+    let (mut context, mint, source) = start().await;
+    let owner = context.payer.pubkey();
+
+    let ix = client::enter_rollup(
+        &owner,
+        &mint,
+        &source,
+        &spl_token_interface::ID,
+        400,
+        Some(0),
+        None,
+        None,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&owner),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let pdas = utils::derive_pdas(owner, mint);
+    let ata_account = account(&mut context, pdas.ephemeral_ata).await;
+    assert_eq!(ata_account.owner, DELEGATION_PROGRAM_ID);
+    let ephemeral_ata = decode::<EphemeralAta>(&ata_account.data).unwrap();
+    assert_eq!(ephemeral_ata.owner, owner);
+    assert_eq!(ephemeral_ata.mint, mint);
+    assert_eq!(ephemeral_ata.amount, 400);
+
+    let vault = decode::<GlobalVault>(&account(&mut context, pdas.vault).await.data).unwrap();
+    assert_eq!(vault.total_deposited, 400);
+    let vault_token = utils::vault_ata(pdas.vault, mint, spl_token_interface::ID);
+    let vault_token = SplAccount::unpack(&account(&mut context, vault_token).await.data).unwrap();
+    assert_eq!(vault_token.amount, 400);
+
+    let permission = permission_pda_from_permissioned_account(&pdas.ephemeral_ata);
+    assert_eq!(
+        account(&mut context, permission).await.owner,
+        PERMISSION_PROGRAM_ID
+    );
+}
+
+#[tokio::test]
+async fn enter_rollup_reuses_existing_accounts() {
+    let (mut context, mint, source) = start().await;
+    let owner = context.payer.pubkey();
+
+    let tx_init = Transaction::new_signed_with_payer(
+        &[
+            client::initialize_ephemeral_ata(&owner, &owner, &mint),
+            client::initialize_global_vault(&owner, &mint, &spl_token_interface::ID),
+            client::deposit_spl_tokens(
+                &owner,
+                &owner,
+                &mint,
+                &source,
+                &spl_token_interface::ID,
+                100,
+            ),
+        ],
+        Some(&owner),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(tx_init)
+        .await
+        .unwrap();
+
+    let ix = client::enter_rollup(
+        &owner,
+        &mint,
+        &source,
+        &spl_token_interface::ID,
+        250,
+        None,
+        None,
+        None,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&owner),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let pdas = utils::derive_pdas(owner, mint);
+    let ata_account = account(&mut context, pdas.ephemeral_ata).await;
+    assert_eq!(ata_account.owner, DELEGATION_PROGRAM_ID);
+    assert_eq!(
+        decode::<EphemeralAta>(&ata_account.data).unwrap().amount,
+        350
+    );
+    let vault = decode::<GlobalVault>(&account(&mut context, pdas.vault).await.data).unwrap();
+    assert_eq!(vault.total_deposited, 350);
+
+    // Already in the rollup
+    let ix = client::enter_rollup(
+        &owner,
+        &mint,
+        &source,
+        &spl_token_interface::ID,
+        1,
+        None,
+        None,
+        None,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&owner),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(EphemeralSplError::AccountDelegated as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn delegate_permission_after_enter_rollup() {
+    let (mut context, mint, source) = start().await;
+    let owner = context.payer.pubkey();
+
+    let ix = client::enter_rollup(
+        &owner,
+        &mint,
+        &source,
+        &spl_token_interface::ID,
+        100,
+        Some(0),
+        None,
+        None,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&owner),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The Ephemeral ATA is now owned by the delegation program
+    let validator = Pubkey::new_unique();
+    context.set_account(
+        &validator,
+        &Account {
+            lamports: Rent::default().minimum_balance(0).max(1),
+            data: vec![],
+            owner: solana_system_interface::program::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+        .into(),
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[client::delegate_ephemeral_ata_permission(
+            &owner, &owner, &mint, &validator,
+        )],
+        Some(&owner),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let pdas = utils::derive_pdas(owner, mint);
+    let permission = permission_pda_from_permissioned_account(&pdas.ephemeral_ata);
+    assert_eq!(
+        account(&mut context, permission).await.owner,
+        DELEGATION_PROGRAM_ID
+    );
+}
@@ -1,7 +1,11 @@
 use ephemeral_spl_api::program::ID;
 use ephemeral_spl_api::state::ephemeral_ata::EphemeralAta;
-use ephemeral_spl_api::state::{load_mut_unchecked, Initializable, RawType};
+use ephemeral_spl_api::state::{
+    load_mut_unchecked, AccountDiscriminator, Discriminated, Initializable, RawType,
+};
+use solana_instruction::error::InstructionError;
 use solana_instruction::Instruction;
+use solana_transaction::TransactionError;
 use {
     ephemeral_spl_api::instruction,
     solana_instruction::AccountMeta,
@@ -40,7 +44,7 @@ async fn initialize_ephemeral_ata() {
             AccountMeta::new_readonly(mint, false),  // mint seed  (readonly)
             AccountMeta::new_readonly(solana_system_interface::program::ID, false), // system program (readonly)
         ],
-        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA, bump], // instruction data: discriminator + bump
+        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA], // instruction data: discriminator
     };
 
     let tx = Transaction::new_signed_with_payer(
@@ -66,6 +70,67 @@ async fn initialize_ephemeral_ata() {
     let ephemeral_ata =
         unsafe { load_mut_unchecked::<EphemeralAta>(mut_acc.as_mut_slice()).unwrap() };
     assert!(ephemeral_ata.is_initialized());
+    assert_eq!(ephemeral_ata.header.bump, bump);
+    assert_eq!(
+        ephemeral_ata.header.discriminator,
+        AccountDiscriminator::EphemeralAta as u8
+    );
+    assert_eq!(ephemeral_ata.header.version, EphemeralAta::VERSION);
     assert_eq!(ephemeral_ata.amount, 0);
     assert_eq!(ephemeral_ata.owner.as_array(), &user.to_bytes());
 }
+
+#[tokio::test]
+async fn initialize_ephemeral_ata_rejects_non_canonical_bump() {
+    let context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+
+    let payer = context.payer.pubkey();
+    let user = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    // Any valid PDA for [user, mint] other than the canonical one
+    let seeds = [user.to_bytes(), mint.to_bytes()];
+    let (_, canonical_bump) =
+        Pubkey::find_program_address(&[seeds[0].as_slice(), seeds[1].as_slice()], &PROGRAM);
+    let non_canonical = (0..canonical_bump)
+        .rev()
+        .find_map(|bump| {
+            Pubkey::create_program_address(
+                &[seeds[0].as_slice(), seeds[1].as_slice(), &[bump]],
+                &PROGRAM,
+            )
+            .ok()
+        })
+        .expect("a non-canonical PDA must exist");
+
+    let ix = Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(non_canonical, false),
+            AccountMeta::new_readonly(payer, false),
+            AccountMeta::new_readonly(user, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA],
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let err = context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+}
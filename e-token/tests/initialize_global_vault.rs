@@ -2,6 +2,9 @@ use ephemeral_spl_api::program::ID;
 use ephemeral_spl_api::state::global_vault::GlobalVault;
 use ephemeral_spl_api::state::{load_mut_unchecked, Initializable, RawType};
 use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_program_pack::Pack;
+use spl_token_interface::state::Account;
 use {
     ephemeral_spl_api::instruction,
     solana_instruction::AccountMeta,
@@ -11,19 +14,25 @@ use {
     solana_transaction::Transaction,
 };
 
+mod utils;
+
 pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
 
 #[tokio::test]
 async fn initialize_global_vault() {
-    let context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
         .start_with_context()
         .await;
 
     let payer = context.payer.pubkey();
-    let mint = Pubkey::new_unique();
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
 
     // PDA derived only from [mint]
-    let (vault, bump) = Pubkey::find_program_address(&[mint.to_bytes().as_slice()], &PROGRAM);
+    let pdas = utils::derive_pdas(payer, mint);
+    let (vault, bump) = (pdas.vault, pdas.bump_vault);
+    let setup =
+        utils::setup_mint_and_token_accounts(&mut context, payer, &mint_kp, vault, 6, 0, 1).await;
 
     // Build instruction
     let ix = Instruction {
@@ -33,8 +42,11 @@ async fn initialize_global_vault() {
             AccountMeta::new_readonly(payer, false), // payer (funds, not part of seeds)
             AccountMeta::new_readonly(mint, false),  // mint (seed)
             AccountMeta::new_readonly(solana_system_interface::program::ID, false), // system program
+            AccountMeta::new(setup.vault_token, false), // vault ATA (created)
+            AccountMeta::new_readonly(spl_token_interface::ID, false), // token program
+            AccountMeta::new_readonly(utils::ASSOCIATED_TOKEN_PROGRAM_ID, false), // ATA program
         ],
-        data: vec![instruction::INITIALIZE_GLOBAL_VAULT, bump],
+        data: vec![instruction::INITIALIZE_GLOBAL_VAULT],
     };
 
     let tx = Transaction::new_signed_with_payer(
@@ -59,4 +71,16 @@ async fn initialize_global_vault() {
     let mut mut_acc = account.data.clone();
     let vault_data = unsafe { load_mut_unchecked::<GlobalVault>(mut_acc.as_mut_slice()).unwrap() };
     assert!(vault_data.is_initialized());
+    assert_eq!(vault_data.header.bump, bump);
+
+    // Verify the vault ATA is owned by the vault PDA
+    let vault_token = context
+        .banks_client
+        .get_account(setup.vault_token)
+        .await
+        .unwrap()
+        .expect("vault token account must exist");
+    let vault_token_state = Account::unpack(&vault_token.data).unwrap();
+    assert_eq!(vault_token_state.owner, vault);
+    assert_eq!(vault_token_state.mint, mint);
 }
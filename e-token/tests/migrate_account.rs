@@ -0,0 +1,174 @@
+use ephemeral_spl_api::instruction;
+use ephemeral_spl_api::program::ID;
+use ephemeral_spl_api::state::ephemeral_ata::EphemeralAta;
+use ephemeral_spl_api::state::global_vault::GlobalVault;
+use ephemeral_spl_api::state::{load_mut, Discriminated, RawType};
+use solana_account::Account;
+use solana_instruction::{AccountMeta, Instruction};
+use solana_keypair::Keypair;
+use solana_program::rent::Rent;
+use {
+    solana_program_test::{tokio, ProgramTest},
+    solana_pubkey::Pubkey,
+    solana_signer::Signer,
+    solana_transaction::Transaction,
+};
+
+mod utils;
+
+pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
+
+fn migrate_ix(payer: Pubkey, account: Pubkey, vault_accounts: &[AccountMeta]) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(payer, true),
+        AccountMeta::new(account, false),
+        AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+    ];
+    accounts.extend_from_slice(vault_accounts);
+    Instruction {
+        program_id: PROGRAM,
+        accounts,
+        data: vec![instruction::MIGRATE_ACCOUNT],
+    }
+}
+
+fn legacy_account(data: Vec<u8>) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: PROGRAM,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+#[tokio::test]
+async fn migrate_legacy_ephemeral_ata() {
+    let mut pt = ProgramTest::new("ephemeral_token_program", PROGRAM, None);
+
+    let owner = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let (ephemeral_ata, _) =
+        Pubkey::find_program_address(&[owner.as_ref(), mint.as_ref()], &PROGRAM);
+
+    // Untagged layout: owner, mint, amount
+    let mut legacy = Vec::with_capacity(EphemeralAta::LEGACY_LEN);
+    legacy.extend_from_slice(owner.as_ref());
+    legacy.extend_from_slice(mint.as_ref());
+    legacy.extend_from_slice(&42u64.to_le_bytes());
+    pt.add_account(ephemeral_ata, legacy_account(legacy));
+
+    let context = pt.start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    // Migrating twice is a no-op the second time
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            migrate_ix(payer, ephemeral_ata, &[]),
+            migrate_ix(payer, ephemeral_ata, &[]),
+        ],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(ephemeral_ata)
+        .await
+        .unwrap()
+        .expect("ephemeral ata account must exist");
+    assert_eq!(account.data.len(), EphemeralAta::LEN);
+    assert!(account.lamports >= Rent::default().minimum_balance(EphemeralAta::LEN));
+
+    let mut data = account.data;
+    let migrated = unsafe { load_mut::<EphemeralAta>(data.as_mut_slice()).unwrap() };
+    assert_eq!(migrated.header.version, EphemeralAta::VERSION);
+    assert_eq!(migrated.owner.as_array(), &owner.to_bytes());
+    assert_eq!(migrated.mint.as_array(), &mint.to_bytes());
+    assert_eq!(migrated.amount, 42);
+    assert!(migrated.delegate().is_none());
+}
+
+#[tokio::test]
+async fn migrate_legacy_global_vault_seeds_total_deposited() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+
+    let payer = context.payer.pubkey();
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+    let pdas = utils::derive_pdas(payer, mint);
+    let tokens = utils::setup_mint_and_token_accounts(
+        &mut context,
+        payer,
+        &mint_kp,
+        pdas.vault,
+        6,
+        1_000,
+        1,
+    )
+    .await;
+
+    // Untagged layout: mint
+    context.set_account(
+        &pdas.vault,
+        &legacy_account(mint.to_bytes().to_vec()).into(),
+    );
+
+    // Re-running InitializeGlobalVault on the legacy vault only creates its ATA
+    let ix_init_vault = Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(pdas.vault, false),
+            AccountMeta::new_readonly(payer, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+            AccountMeta::new(tokens.vault_token, false),
+            AccountMeta::new_readonly(spl_token_interface::ID, false),
+            AccountMeta::new_readonly(utils::ASSOCIATED_TOKEN_PROGRAM_ID, false),
+        ],
+        data: vec![instruction::INITIALIZE_GLOBAL_VAULT],
+    };
+    let ix_fund_vault = spl_token_interface::instruction::transfer(
+        &spl_token_interface::ID,
+        &tokens.user_tokens[0],
+        &tokens.vault_token,
+        &payer,
+        &[],
+        700,
+    )
+    .unwrap();
+    let ix_migrate = migrate_ix(
+        payer,
+        pdas.vault,
+        &[
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(tokens.vault_token, false),
+            AccountMeta::new_readonly(spl_token_interface::ID, false),
+        ],
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_init_vault, ix_fund_vault, ix_migrate],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let mut data = context
+        .banks_client
+        .get_account(pdas.vault)
+        .await
+        .unwrap()
+        .expect("global vault must exist")
+        .data;
+    assert_eq!(data.len(), GlobalVault::LEN);
+    let vault = unsafe { load_mut::<GlobalVault>(data.as_mut_slice()).unwrap() };
+    assert_eq!(vault.header.version, GlobalVault::VERSION);
+    assert_eq!(vault.mint.as_array(), &mint.to_bytes());
+    assert_eq!(vault.total_deposited, 700);
+}
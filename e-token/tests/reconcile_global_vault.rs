@@ -0,0 +1,112 @@
+use ephemeral_spl_api::program::ID;
+use ephemeral_spl_api::state::global_vault::GlobalVault;
+use ephemeral_spl_api::state::load_mut_unchecked;
+use ephemeral_spl_client as client;
+use solana_keypair::Keypair;
+use {
+    solana_program_test::{tokio, ProgramTest},
+    solana_pubkey::Pubkey,
+    solana_signer::Signer,
+    solana_transaction::Transaction,
+};
+
+mod utils;
+
+pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
+
+const DECIMALS: u8 = 6;
+const STARTING_BALANCE: u64 = 10_000 * 10u64.pow(DECIMALS as u32);
+const DEPOSIT_AMOUNT: u64 = 1_000 * 10u64.pow(DECIMALS as u32);
+const DONATION: u64 = 5 * 10u64.pow(DECIMALS as u32);
+
+#[tokio::test]
+async fn reconcile_global_vault_reports_surplus() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+
+    let payer = context.payer.pubkey();
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+
+    let pdas = utils::derive_pdas(payer, mint);
+    let tokens = utils::setup_mint_and_token_accounts(
+        &mut context,
+        payer,
+        &mint_kp,
+        pdas.vault,
+        DECIMALS,
+        STARTING_BALANCE,
+        1,
+    )
+    .await;
+
+    let token_program = spl_token_interface::ID;
+    let ix_init_ata = client::initialize_ephemeral_ata(&payer, &payer, &mint);
+    let ix_init_vault = client::initialize_global_vault(&payer, &mint, &token_program);
+    let ix_deposit = client::deposit_spl_tokens(
+        &payer,
+        &payer,
+        &mint,
+        &tokens.user_tokens[0],
+        &token_program,
+        DEPOSIT_AMOUNT,
+    );
+    // Tokens sent straight to the vault ATA are not owed to anyone
+    let ix_donate = spl_token_interface::instruction::transfer(
+        &spl_token_interface::ID,
+        &tokens.user_tokens[0],
+        &tokens.vault_token,
+        &payer,
+        &[],
+        DONATION,
+    )
+    .unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_init_ata, ix_init_vault, ix_deposit, ix_donate],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let mut vault_data = context
+        .banks_client
+        .get_account(pdas.vault)
+        .await
+        .unwrap()
+        .expect("global vault must exist")
+        .data;
+    let vault = unsafe { load_mut_unchecked::<GlobalVault>(vault_data.as_mut_slice()).unwrap() };
+    assert_eq!(vault.total_deposited, DEPOSIT_AMOUNT);
+
+    // Reconcile is permissionless: no account signs besides the fee payer
+    let ix_reconcile = client::reconcile_global_vault(&mint, &token_program);
+    let tx = Transaction::new_signed_with_payer(
+        &[ix_reconcile],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let result = context
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await
+        .unwrap();
+    assert!(result.result.is_ok());
+
+    let return_data = result
+        .metadata
+        .expect("metadata must be present")
+        .return_data
+        .expect("reconcile must set return data");
+    assert_eq!(return_data.program_id, PROGRAM);
+
+    let read =
+        |i: usize| u64::from_le_bytes(return_data.data[i * 8..i * 8 + 8].try_into().unwrap());
+    assert_eq!(read(0), DEPOSIT_AMOUNT + DONATION);
+    assert_eq!(read(1), DEPOSIT_AMOUNT);
+    assert_eq!(read(2), DONATION);
+    assert_eq!(read(3), 0);
+}
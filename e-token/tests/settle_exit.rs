@@ -0,0 +1,415 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::program::{DELEGATION_PROGRAM_ID, ID};
+use ephemeral_spl_api::state::{
+    decode, ephemeral_ata::EphemeralAta, global_vault::GlobalVault, load_mut_unchecked,
+};
+use ephemeral_spl_client as client;
+use solana_account::Account as SolanaAccount;
+use solana_instruction::error::InstructionError;
+use solana_keypair::Keypair;
+use solana_program::rent::Rent;
+use solana_program::{account_info::AccountInfo, bpf_loader, entrypoint::ProgramResult};
+use solana_program_pack::Pack;
+use solana_program_test::{processor, read_file, tokio, ProgramTest, ProgramTestContext};
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+use solana_system_interface::instruction::create_account;
+use solana_transaction::{Transaction, TransactionError};
+use spl_token_2022_interface::instruction::{initialize_account3, initialize_mint2, mint_to};
+use spl_token_2022_interface::state::{Account as Token2022Account, Mint as Token2022Mint};
+use spl_token_interface::state::Account;
+use std::sync::Mutex;
+
+mod utils;
+
+pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
+
+static INTENT_BUNDLE: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+fn record_intent_bundle(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    *INTENT_BUNDLE.lock().unwrap() = data.to_vec();
+    Ok(())
+}
+
+async fn settle(
+    context: &mut ProgramTestContext,
+    owner: Pubkey,
+    mint: Pubkey,
+    destination: Pubkey,
+) -> Result<(), TransactionError> {
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[client::settle_exit(
+            &owner,
+            &mint,
+            &destination,
+            &spl_token_interface::ID,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+#[tokio::test]
+async fn settle_exit_pays_the_owner_once() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+
+    let payer = context.payer.pubkey();
+    let owner = payer;
+
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+    let pdas = utils::derive_pdas(owner, mint);
+    let setup = utils::setup_mint_and_token_accounts(
+        &mut context,
+        payer,
+        &mint_kp,
+        pdas.vault,
+        6,
+        1_000,
+        2,
+    )
+    .await;
+    let source = setup.user_tokens[0];
+    let destination = setup.user_tokens[1];
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            client::initialize_ephemeral_ata(&payer, &owner, &mint),
+            client::initialize_global_vault(&payer, &mint, &spl_token_interface::ID),
+            client::deposit_spl_tokens(
+                &owner,
+                &owner,
+                &mint,
+                &source,
+                &spl_token_interface::ID,
+                300,
+            ),
+        ],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Only an Ephemeral ATA that left with ExitRollup is paid out
+    let not_requested = TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(EphemeralSplError::ExitNotRequested as u32),
+    );
+    assert_eq!(
+        settle(&mut context, owner, mint, destination).await,
+        Err(not_requested.clone())
+    );
+
+    // Simulate the state restored from the ephemeral rollup after ExitRollup
+    let mut ephemeral_ata = context
+        .banks_client
+        .get_account(pdas.ephemeral_ata)
+        .await
+        .unwrap()
+        .unwrap();
+    unsafe { load_mut_unchecked::<EphemeralAta>(&mut ephemeral_ata.data).unwrap() }
+        .set_withdraw_on_exit(true);
+    context.set_account(&pdas.ephemeral_ata, &ephemeral_ata.into());
+
+    settle(&mut context, owner, mint, destination)
+        .await
+        .unwrap();
+
+    let ephemeral_ata = decode::<EphemeralAta>(
+        &context
+            .banks_client
+            .get_account(pdas.ephemeral_ata)
+            .await
+            .unwrap()
+            .unwrap()
+            .data,
+    )
+    .unwrap();
+    assert_eq!(ephemeral_ata.amount, 0);
+    assert!(!ephemeral_ata.withdraw_on_exit());
+
+    let vault = decode::<GlobalVault>(
+        &context
+            .banks_client
+            .get_account(pdas.vault)
+            .await
+            .unwrap()
+            .unwrap()
+            .data,
+    )
+    .unwrap();
+    assert_eq!(vault.total_deposited, 0);
+
+    let destination_account = Account::unpack(
+        &context
+            .banks_client
+            .get_account(destination)
+            .await
+            .unwrap()
+            .unwrap()
+            .data,
+    )
+    .unwrap();
+    assert_eq!(destination_account.amount, 300);
+
+    // The exit cannot be settled twice
+    assert_eq!(
+        settle(&mut context, owner, mint, destination).await,
+        Err(not_requested)
+    );
+}
+
+#[tokio::test]
+async fn exit_rollup_pays_the_owner_after_undelegation() {
+    let mut pt = ProgramTest::new("ephemeral_token_program", PROGRAM, None);
+    pt.prefer_bpf(true);
+    let data = read_file("tests/fixtures/dlp.so");
+    pt.add_account(
+        DELEGATION_PROGRAM_ID,
+        SolanaAccount {
+            lamports: Rent::default().minimum_balance(data.len()).max(1),
+            data,
+            owner: bpf_loader::id(),
+            executable: true,
+            rent_epoch: 0,
+        },
+    );
+    // Magic program recording the intent bundle scheduled by ExitRollup
+    pt.prefer_bpf(false);
+    pt.add_program(
+        "magic_program",
+        client::MAGIC_PROGRAM_ID,
+        processor!(record_intent_bundle),
+    );
+    pt.prefer_bpf(true);
+    let mut context = pt.start_with_context().await;
+
+    let owner = context.payer.pubkey();
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+    let pdas = utils::derive_pdas(owner, mint);
+    let setup = utils::setup_mint_and_token_accounts(
+        &mut context,
+        owner,
+        &mint_kp,
+        pdas.vault,
+        6,
+        1_000,
+        2,
+    )
+    .await;
+    let destination = setup.user_tokens[1];
+
+    let tx = Transaction::new_signed_with_payer(
+        &[client::enter_rollup(
+            &owner,
+            &mint,
+            &setup.user_tokens[0],
+            &spl_token_interface::ID,
+            300,
+            None,
+            None,
+            None,
+        )],
+        Some(&owner),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Nothing is paid while the Ephemeral ATA is delegated
+    assert_eq!(
+        settle(&mut context, owner, mint, destination).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(EphemeralSplError::AccountDelegated as u32)
+        ))
+    );
+
+    // In the ephemeral rollup the Ephemeral ATA is owned by the program again
+    let mut ephemeral_ata = context
+        .banks_client
+        .get_account(pdas.ephemeral_ata)
+        .await
+        .unwrap()
+        .unwrap();
+    ephemeral_ata.owner = PROGRAM;
+    context.set_account(&pdas.ephemeral_ata, &ephemeral_ata.into());
+
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[client::exit_rollup(
+            &owner,
+            &mint,
+            &destination,
+            &spl_token_interface::ID,
+        )],
+        Some(&owner),
+        &[&context.payer],
+        blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The post-undelegation action is a SettleExit on the accounts of the payout
+    let settle_exit = client::settle_exit(&owner, &mint, &destination, &spl_token_interface::ID);
+    let intent_bundle = INTENT_BUNDLE.lock().unwrap().clone();
+    for address in [PROGRAM]
+        .iter()
+        .chain(settle_exit.accounts.iter().map(|meta| &meta.pubkey))
+    {
+        assert!(intent_bundle
+            .windows(32)
+            .any(|window| window == address.as_ref()));
+    }
+
+    // The delegation program runs the action once the Ephemeral ATA is undelegated
+    settle(&mut context, owner, mint, destination)
+        .await
+        .unwrap();
+
+    let ephemeral_ata = decode::<EphemeralAta>(
+        &context
+            .banks_client
+            .get_account(pdas.ephemeral_ata)
+            .await
+            .unwrap()
+            .unwrap()
+            .data,
+    )
+    .unwrap();
+    assert_eq!(ephemeral_ata.amount, 0);
+    assert!(!ephemeral_ata.withdraw_on_exit());
+
+    let destination_account = context
+        .banks_client
+        .get_account(destination)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        Account::unpack(&destination_account.data).unwrap().amount,
+        300
+    );
+}
+
+#[tokio::test]
+async fn exit_rollup_pays_the_owner_of_a_token_2022_ephemeral_ata() {
+    let mut pt = ProgramTest::new("ephemeral_token_program", PROGRAM, None);
+    utils::add_magic_program(&mut pt);
+    let mut context = pt.start_with_context().await;
+
+    let token_program = spl_token_2022_interface::ID;
+    let owner = context.payer.pubkey();
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+    let source_kp = Keypair::new();
+    let destination_kp = Keypair::new();
+    let (source, destination) = (source_kp.pubkey(), destination_kp.pubkey());
+    let pdas = utils::derive_pdas(owner, mint);
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let mut instructions = vec![
+        create_account(
+            &owner,
+            &mint,
+            rent.minimum_balance(Token2022Mint::LEN),
+            Token2022Mint::LEN as u64,
+            &token_program,
+        ),
+        initialize_mint2(&token_program, &mint, &owner, None, 6).unwrap(),
+    ];
+    for token_account in [source, destination] {
+        instructions.push(create_account(
+            &owner,
+            &token_account,
+            rent.minimum_balance(Token2022Account::LEN),
+            Token2022Account::LEN as u64,
+            &token_program,
+        ));
+        instructions
+            .push(initialize_account3(&token_program, &token_account, &mint, &owner).unwrap());
+    }
+    instructions.extend([
+        mint_to(&token_program, &mint, &source, &owner, &[], 1_000).unwrap(),
+        client::initialize_ephemeral_ata(&owner, &owner, &mint),
+        client::initialize_global_vault(&owner, &mint, &token_program),
+        client::deposit_spl_tokens(&owner, &owner, &mint, &source, &token_program, 300),
+    ]);
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&owner),
+        &[&context.payer, &mint_kp, &source_kp, &destination_kp],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The Ephemeral ATA as seen in the ephemeral rollup, where it is owned by the program
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[client::exit_rollup(
+            &owner,
+            &mint,
+            &destination,
+            &token_program,
+        )],
+        Some(&owner),
+        &[&context.payer],
+        blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The post-undelegation action the delegation program runs on the base layer
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[client::settle_exit(
+            &owner,
+            &mint,
+            &destination,
+            &token_program,
+        )],
+        Some(&owner),
+        &[&context.payer],
+        blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let ephemeral_ata = decode::<EphemeralAta>(
+        &context
+            .banks_client
+            .get_account(pdas.ephemeral_ata)
+            .await
+            .unwrap()
+            .unwrap()
+            .data,
+    )
+    .unwrap();
+    assert_eq!(ephemeral_ata.amount, 0);
+    assert!(!ephemeral_ata.withdraw_on_exit());
+
+    let destination_account = context
+        .banks_client
+        .get_account(destination)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        Token2022Account::unpack(&destination_account.data)
+            .unwrap()
+            .amount,
+        300
+    );
+}
@@ -0,0 +1,271 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::event::EventKind;
+use ephemeral_spl_api::instruction;
+use ephemeral_spl_api::program::ID;
+use ephemeral_spl_api::state::ephemeral_ata::EphemeralAta;
+use ephemeral_spl_api::state::load_mut_unchecked;
+use solana_instruction::{error::InstructionError, AccountMeta, Instruction};
+use solana_keypair::Keypair;
+use solana_program_test::ProgramTestContext;
+use {
+    solana_program_test::{tokio, ProgramTest},
+    solana_pubkey::Pubkey,
+    solana_signer::Signer,
+    solana_transaction::{Transaction, TransactionError},
+};
+
+mod utils;
+
+pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
+
+const DECIMALS: u8 = 6;
+const STARTING_BALANCE: u64 = 10_000 * 10u64.pow(DECIMALS as u32);
+const DEPOSIT_AMOUNT: u64 = 1_000 * 10u64.pow(DECIMALS as u32);
+
+struct Setup {
+    recipient: Keypair,
+    source_eata: Pubkey,
+    destination_eata: Pubkey,
+}
+
+// Initializes the vault and two Ephemeral ATAs (payer and recipient) for a fresh mint,
+// and deposits DEPOSIT_AMOUNT into the payer's Ephemeral ATA.
+async fn setup(context: &mut ProgramTestContext) -> Setup {
+    let payer = context.payer.pubkey();
+    let recipient = Keypair::new();
+
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+
+    let source_pdas = utils::derive_pdas(payer, mint);
+    let destination_pdas = utils::derive_pdas(recipient.pubkey(), mint);
+    let tokens = utils::setup_mint_and_token_accounts(
+        context,
+        payer,
+        &mint_kp,
+        source_pdas.vault,
+        DECIMALS,
+        STARTING_BALANCE,
+        1,
+    )
+    .await;
+
+    let init_ata = |ephemeral_ata: Pubkey, user: Pubkey| Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(ephemeral_ata, false),
+            AccountMeta::new_readonly(payer, false),
+            AccountMeta::new_readonly(user, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA],
+    };
+
+    let ix_init_vault = Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(source_pdas.vault, false),
+            AccountMeta::new_readonly(payer, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+            AccountMeta::new(tokens.vault_token, false),
+            AccountMeta::new_readonly(spl_token_interface::ID, false),
+            AccountMeta::new_readonly(utils::ASSOCIATED_TOKEN_PROGRAM_ID, false),
+        ],
+        data: vec![instruction::INITIALIZE_GLOBAL_VAULT],
+    };
+
+    let mut deposit_data = vec![instruction::DEPOSIT_SPL_TOKENS];
+    deposit_data.extend_from_slice(&DEPOSIT_AMOUNT.to_le_bytes());
+    let ix_deposit = Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(source_pdas.ephemeral_ata, false),
+            AccountMeta::new(source_pdas.vault, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(tokens.user_tokens[0], false),
+            AccountMeta::new(tokens.vault_token, false),
+            AccountMeta::new_readonly(payer, true),
+            AccountMeta::new_readonly(spl_token_interface::ID, false),
+        ],
+        data: deposit_data,
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            init_ata(source_pdas.ephemeral_ata, payer),
+            init_ata(destination_pdas.ephemeral_ata, recipient.pubkey()),
+            ix_init_vault,
+            ix_deposit,
+        ],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    Setup {
+        recipient,
+        source_eata: source_pdas.ephemeral_ata,
+        destination_eata: destination_pdas.ephemeral_ata,
+    }
+}
+
+fn transfer_ix(owner: Pubkey, source: Pubkey, destination: Pubkey, amount: u64) -> Instruction {
+    let mut data = vec![instruction::TRANSFER_EPHEMERAL];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new_readonly(owner, true), // [signer] source owner
+            AccountMeta::new(source, false),        // [writable] source eata
+            AccountMeta::new(destination, false),   // [writable] destination eata
+        ],
+        data,
+    }
+}
+
+async fn eata_amount(context: &mut ProgramTestContext, ephemeral_ata: Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(ephemeral_ata)
+        .await
+        .unwrap()
+        .expect("ephemeral ata account must exist");
+    let mut data = account.data.clone();
+    let ephemeral_ata = unsafe { load_mut_unchecked::<EphemeralAta>(data.as_mut_slice()).unwrap() };
+    ephemeral_ata.amount
+}
+
+#[tokio::test]
+async fn transfer_ephemeral_moves_balance() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+    let payer = context.payer.pubkey();
+    let setup = setup(&mut context).await;
+
+    let amount: u64 = 250 * 10u64.pow(DECIMALS as u32);
+    let tx = Transaction::new_signed_with_payer(
+        &[transfer_ix(
+            payer,
+            setup.source_eata,
+            setup.destination_eata,
+            amount,
+        )],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let result = context
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await
+        .unwrap();
+    result.result.unwrap();
+
+    // One event per side, each with that account's balance after the transfer
+    let events: Vec<_> = utils::events(&result.metadata.unwrap().log_messages)
+        .into_iter()
+        .map(|event| {
+            (
+                event.kind,
+                event.ephemeral_ata,
+                event.amount,
+                event.post_balance,
+            )
+        })
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            (
+                EventKind::TransferOut,
+                setup.source_eata,
+                amount,
+                DEPOSIT_AMOUNT - amount
+            ),
+            (
+                EventKind::TransferIn,
+                setup.destination_eata,
+                amount,
+                amount
+            ),
+        ]
+    );
+
+    assert_eq!(
+        eata_amount(&mut context, setup.source_eata).await,
+        DEPOSIT_AMOUNT - amount
+    );
+    assert_eq!(
+        eata_amount(&mut context, setup.destination_eata).await,
+        amount
+    );
+}
+
+#[tokio::test]
+async fn transfer_ephemeral_rejects_non_owner_and_overdraft() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+    let payer = context.payer.pubkey();
+    let setup = setup(&mut context).await;
+
+    // The recipient cannot move funds out of the payer's Ephemeral ATA
+    let tx = Transaction::new_signed_with_payer(
+        &[transfer_ix(
+            setup.recipient.pubkey(),
+            setup.source_eata,
+            setup.destination_eata,
+            1,
+        )],
+        Some(&payer),
+        &[&context.payer, &setup.recipient],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(EphemeralSplError::OwnerMismatch as u32)
+        )
+    );
+
+    // The owner cannot transfer more than the Ephemeral ATA holds
+    let tx = Transaction::new_signed_with_payer(
+        &[transfer_ix(
+            payer,
+            setup.source_eata,
+            setup.destination_eata,
+            DEPOSIT_AMOUNT + 1,
+        )],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(EphemeralSplError::InsufficientFunds as u32)
+        )
+    );
+
+    assert_eq!(
+        eata_amount(&mut context, setup.source_eata).await,
+        DEPOSIT_AMOUNT
+    );
+    assert_eq!(eata_amount(&mut context, setup.destination_eata).await, 0);
+}
@@ -0,0 +1,280 @@
+use ephemeral_spl_api::event::EventKind;
+use ephemeral_spl_api::instruction;
+use ephemeral_spl_api::program::ID;
+use ephemeral_spl_api::state::ephemeral_ata::EphemeralAta;
+use ephemeral_spl_api::state::load_mut_unchecked;
+use solana_instruction::{AccountMeta, Instruction};
+use solana_keypair::Keypair;
+use solana_program_test::ProgramTestContext;
+use {
+    solana_program_test::{tokio, ProgramTest},
+    solana_pubkey::Pubkey,
+    solana_signer::Signer,
+    solana_transaction::Transaction,
+};
+
+mod utils;
+
+pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
+
+const DECIMALS: u8 = 6;
+const STARTING_BALANCE: u64 = 10_000 * 10u64.pow(DECIMALS as u32);
+const DEPOSIT_AMOUNT: u64 = 1_000 * 10u64.pow(DECIMALS as u32);
+
+struct Setup {
+    source_eata: Pubkey,
+    destination_eata: Pubkey,
+}
+
+// Initializes the vault and two Ephemeral ATAs (payer and a fresh recipient) for a new mint,
+// and deposits DEPOSIT_AMOUNT into the payer's Ephemeral ATA.
+async fn setup(context: &mut ProgramTestContext) -> Setup {
+    let payer = context.payer.pubkey();
+    let recipient = Pubkey::new_unique();
+
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+
+    let source_pdas = utils::derive_pdas(payer, mint);
+    let destination_pdas = utils::derive_pdas(recipient, mint);
+    let tokens = utils::setup_mint_and_token_accounts(
+        context,
+        payer,
+        &mint_kp,
+        source_pdas.vault,
+        DECIMALS,
+        STARTING_BALANCE,
+        1,
+    )
+    .await;
+
+    let init_ata = |ephemeral_ata: Pubkey, user: Pubkey| Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(ephemeral_ata, false),
+            AccountMeta::new_readonly(payer, false),
+            AccountMeta::new_readonly(user, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA],
+    };
+
+    let ix_init_vault = Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(source_pdas.vault, false),
+            AccountMeta::new_readonly(payer, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+            AccountMeta::new(tokens.vault_token, false),
+            AccountMeta::new_readonly(spl_token_interface::ID, false),
+            AccountMeta::new_readonly(utils::ASSOCIATED_TOKEN_PROGRAM_ID, false),
+        ],
+        data: vec![instruction::INITIALIZE_GLOBAL_VAULT],
+    };
+
+    let mut deposit_data = vec![instruction::DEPOSIT_SPL_TOKENS];
+    deposit_data.extend_from_slice(&DEPOSIT_AMOUNT.to_le_bytes());
+    let ix_deposit = Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(source_pdas.ephemeral_ata, false),
+            AccountMeta::new(source_pdas.vault, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(tokens.user_tokens[0], false),
+            AccountMeta::new(tokens.vault_token, false),
+            AccountMeta::new_readonly(payer, true),
+            AccountMeta::new_readonly(spl_token_interface::ID, false),
+        ],
+        data: deposit_data,
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            init_ata(source_pdas.ephemeral_ata, payer),
+            init_ata(destination_pdas.ephemeral_ata, recipient),
+            ix_init_vault,
+            ix_deposit,
+        ],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    Setup {
+        source_eata: source_pdas.ephemeral_ata,
+        destination_eata: destination_pdas.ephemeral_ata,
+    }
+}
+
+fn approve_ix(owner: Pubkey, ephemeral_ata: Pubkey, delegate: Pubkey, amount: u64) -> Instruction {
+    let mut data = vec![instruction::APPROVE_EPHEMERAL];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new_readonly(owner, true),     // [signer] owner
+            AccountMeta::new(ephemeral_ata, false),     // [writable] eata
+            AccountMeta::new_readonly(delegate, false), // [] delegate
+        ],
+        data,
+    }
+}
+
+fn transfer_from_ix(
+    delegate: Pubkey,
+    source: Pubkey,
+    destination: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut data = vec![instruction::TRANSFER_FROM_EPHEMERAL];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new_readonly(delegate, true), // [signer] delegate
+            AccountMeta::new(source, false),           // [writable] source eata
+            AccountMeta::new(destination, false),      // [writable] destination eata
+        ],
+        data,
+    }
+}
+
+async fn read_eata(context: &mut ProgramTestContext, ephemeral_ata: Pubkey) -> Vec<u8> {
+    context
+        .banks_client
+        .get_account(ephemeral_ata)
+        .await
+        .unwrap()
+        .expect("ephemeral ata account must exist")
+        .data
+}
+
+#[tokio::test]
+async fn transfer_from_ephemeral_spends_allowance() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+    let payer = context.payer.pubkey();
+    let setup = setup(&mut context).await;
+
+    let delegate = Keypair::new();
+    let allowance: u64 = 300 * 10u64.pow(DECIMALS as u32);
+    let spend: u64 = 100 * 10u64.pow(DECIMALS as u32);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            approve_ix(payer, setup.source_eata, delegate.pubkey(), allowance),
+            transfer_from_ix(
+                delegate.pubkey(),
+                setup.source_eata,
+                setup.destination_eata,
+                spend,
+            ),
+        ],
+        Some(&payer),
+        &[&context.payer, &delegate],
+        context.last_blockhash,
+    );
+    let result = context
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await
+        .unwrap();
+    result.result.unwrap();
+
+    let events: Vec<_> = utils::events(&result.metadata.unwrap().log_messages)
+        .into_iter()
+        .map(|event| (event.kind, event.ephemeral_ata, event.post_balance))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            (
+                EventKind::TransferOut,
+                setup.source_eata,
+                DEPOSIT_AMOUNT - spend
+            ),
+            (EventKind::TransferIn, setup.destination_eata, spend),
+        ]
+    );
+
+    let mut source_data = read_eata(&mut context, setup.source_eata).await;
+    let source = unsafe { load_mut_unchecked::<EphemeralAta>(source_data.as_mut_slice()).unwrap() };
+    assert_eq!(source.amount, DEPOSIT_AMOUNT - spend);
+    assert_eq!(source.delegated_amount, allowance - spend);
+    assert_eq!(
+        source.delegate().map(|d| d.to_bytes()),
+        Some(delegate.pubkey().to_bytes())
+    );
+
+    let mut destination_data = read_eata(&mut context, setup.destination_eata).await;
+    let destination =
+        unsafe { load_mut_unchecked::<EphemeralAta>(destination_data.as_mut_slice()).unwrap() };
+    assert_eq!(destination.amount, spend);
+
+    // Spending beyond the remaining allowance fails
+    let tx = Transaction::new_signed_with_payer(
+        &[transfer_from_ix(
+            delegate.pubkey(),
+            setup.source_eata,
+            setup.destination_eata,
+            allowance,
+        )],
+        Some(&payer),
+        &[&context.payer, &delegate],
+        context.last_blockhash,
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+}
+
+#[tokio::test]
+async fn revoke_ephemeral_clears_delegate() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+    let payer = context.payer.pubkey();
+    let setup = setup(&mut context).await;
+
+    let delegate = Keypair::new();
+    let ix_revoke = Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new_readonly(payer, true),
+            AccountMeta::new(setup.source_eata, false),
+        ],
+        data: vec![instruction::REVOKE_EPHEMERAL],
+    };
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            approve_ix(payer, setup.source_eata, delegate.pubkey(), DEPOSIT_AMOUNT),
+            ix_revoke,
+        ],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let mut source_data = read_eata(&mut context, setup.source_eata).await;
+    let source = unsafe { load_mut_unchecked::<EphemeralAta>(source_data.as_mut_slice()).unwrap() };
+    assert!(source.delegate().is_none());
+    assert_eq!(source.delegated_amount, 0);
+
+    // The revoked delegate can no longer spend
+    let tx = Transaction::new_signed_with_payer(
+        &[transfer_from_ix(
+            delegate.pubkey(),
+            setup.source_eata,
+            setup.destination_eata,
+            1,
+        )],
+        Some(&payer),
+        &[&context.payer, &delegate],
+        context.last_blockhash,
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+}
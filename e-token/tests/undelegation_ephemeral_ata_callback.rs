@@ -1,24 +1,51 @@
 use dlp::pda::{fees_vault_pda, validator_fees_vault_pda_from_validator};
-use ephemeral_rollups_pinocchio::consts::DELEGATION_PROGRAM_ID;
+use ephemeral_rollups_pinocchio::consts::{
+    DELEGATION_PROGRAM_ID, EXTERNAL_UNDELEGATE_DISCRIMINATOR,
+};
+use ephemeral_spl_api::instruction::EphemeralSplInstruction;
+use ephemeral_spl_api::pda;
 use ephemeral_spl_api::program::ID;
 use ephemeral_spl_api::state::ephemeral_ata::EphemeralAta;
-use ephemeral_spl_api::state::{load_mut_unchecked, RawType};
+use ephemeral_spl_api::state::{decode, load_mut_unchecked, AccountHeader, RawType};
 use solana_account::Account;
-use solana_instruction::{AccountMeta, Instruction};
+use solana_instruction::{error::InstructionError, AccountMeta, Instruction};
 use solana_keypair::Keypair;
 use solana_program::bpf_loader;
 use solana_program::example_mocks::solana_sdk::system_program;
 use solana_program::native_token::LAMPORTS_PER_SOL;
 use solana_program::rent::Rent;
-use solana_program_test::{read_file, tokio, ProgramTest};
+use solana_program_test::{read_file, tokio, BanksClientError, ProgramTest, ProgramTestContext};
 use solana_pubkey::Pubkey;
 use solana_signer::Signer;
-use solana_transaction::Transaction;
+use solana_transaction::{Transaction, TransactionError};
 
 pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
 
-#[tokio::test]
-async fn undelegation_callback_restores_ephemeral_ata() {
+/// Delegate an Ephemeral ATA whose snapshot is edited by `snapshot`, then
+/// undelegate it through the delegation program.
+async fn undelegate_snapshot(
+    snapshot: impl FnOnce(&mut EphemeralAta),
+) -> (ProgramTestContext, Pubkey, Result<(), BanksClientError>) {
+    undelegate_data(|owner, mint, bump| {
+        let mut data = vec![0u8; EphemeralAta::LEN];
+        let ephemeral_ata =
+            unsafe { load_mut_unchecked::<EphemeralAta>(data.as_mut_slice()).unwrap() };
+        ephemeral_ata.header = AccountHeader::new::<EphemeralAta>(bump);
+        ephemeral_ata.owner = owner;
+        ephemeral_ata.mint = mint;
+        ephemeral_ata.amount = 500;
+        snapshot(ephemeral_ata);
+        data
+    })
+    .await
+}
+
+/// Delegate the Ephemeral ATA PDA of a new owner and mint with the snapshot built by
+/// `snapshot` from the owner, mint and bump, then undelegate it through the
+/// delegation program.
+async fn undelegate_data(
+    snapshot: impl FnOnce(Pubkey, Pubkey, u8) -> Vec<u8>,
+) -> (ProgramTestContext, Pubkey, Result<(), BanksClientError>) {
     // Start the program test with our program loaded
     let mut pt = ProgramTest::new("ephemeral_token_program", PROGRAM, None);
     pt.prefer_bpf(true);
@@ -31,14 +58,12 @@ async fn undelegation_callback_restores_ephemeral_ata() {
     let payer = Keypair::new();
     let payer_pubkey = payer.pubkey();
     let seeds: [&[u8]; 2] = [payer_pubkey.as_ref(), mint.as_ref()];
-    let (delegated_ata, _bump) = Pubkey::find_program_address(&seeds, &PROGRAM);
-
-    println!("Delegated ata: {:?}", delegated_ata);
+    let (delegated_ata, bump) = Pubkey::find_program_address(&seeds, &PROGRAM);
 
     // Setup the delegation program
     let data = read_file("tests/fixtures/dlp.so");
     pt.add_account(
-        ephemeral_rollups_pinocchio::ID.into(),
+        ephemeral_rollups_pinocchio::ID,
         Account {
             lamports: Rent::default().minimum_balance(data.len()).max(1),
             data,
@@ -61,16 +86,13 @@ async fn undelegation_callback_restores_ephemeral_ata() {
     );
 
     // Setup the delegated PDA
-    let mut data = vec![0u8; EphemeralAta::LEN];
-    let ephemeral_ata = unsafe { load_mut_unchecked::<EphemeralAta>(data.as_mut_slice()).unwrap() };
-    ephemeral_ata.mint = pinocchio::Address::new_from_array(mint.to_bytes());
-    ephemeral_ata.amount = 500;
+    let data = snapshot(payer_pubkey, mint, bump);
     pt.add_account(
         delegated_ata,
         Account {
             lamports: LAMPORTS_PER_SOL,
             data: data.clone(),
-            owner: ephemeral_rollups_pinocchio::ID.into(),
+            owner: ephemeral_rollups_pinocchio::ID,
             executable: false,
             rent_epoch: 0,
         },
@@ -92,13 +114,13 @@ async fn undelegation_callback_restores_ephemeral_ata() {
     pt.add_account(
         Pubkey::find_program_address(
             &[b"delegation", delegated_ata.to_bytes().as_slice()],
-            &DELEGATION_PROGRAM_ID.into(),
+            &DELEGATION_PROGRAM_ID,
         )
         .0,
         Account {
             lamports: Rent::default().minimum_balance(delegation_record_data.len()),
             data: delegation_record_data,
-            owner: DELEGATION_PROGRAM_ID.into(),
+            owner: DELEGATION_PROGRAM_ID,
             executable: false,
             rent_epoch: 0,
         },
@@ -118,13 +140,13 @@ async fn undelegation_callback_restores_ephemeral_ata() {
     pt.add_account(
         Pubkey::find_program_address(
             &[b"delegation-metadata", delegated_ata.to_bytes().as_slice()],
-            &DELEGATION_PROGRAM_ID.into(),
+            &DELEGATION_PROGRAM_ID,
         )
         .0,
         Account {
             lamports: Rent::default().minimum_balance(delegation_metadata_data.len()),
             data: delegation_metadata_data,
-            owner: DELEGATION_PROGRAM_ID.into(),
+            owner: DELEGATION_PROGRAM_ID,
             executable: false,
             rent_epoch: 0,
         },
@@ -136,7 +158,7 @@ async fn undelegation_callback_restores_ephemeral_ata() {
         Account {
             lamports: Rent::default().minimum_balance(0),
             data: vec![],
-            owner: DELEGATION_PROGRAM_ID.into(),
+            owner: DELEGATION_PROGRAM_ID,
             executable: false,
             rent_epoch: 0,
         },
@@ -150,7 +172,7 @@ async fn undelegation_callback_restores_ephemeral_ata() {
         Account {
             lamports: LAMPORTS_PER_SOL,
             data: vec![],
-            owner: DELEGATION_PROGRAM_ID.into(),
+            owner: DELEGATION_PROGRAM_ID,
             executable: false,
             rent_epoch: 0,
         },
@@ -187,7 +209,199 @@ async fn undelegation_callback_restores_ephemeral_ata() {
         context.last_blockhash,
     );
 
-    context.banks_client.process_transaction(tx).await.unwrap();
+    let result = context.banks_client.process_transaction(tx).await;
+    (context, delegated_ata, result)
+}
+
+#[tokio::test]
+async fn undelegation_callback_restores_ephemeral_ata() {
+    let (context, delegated_ata, result) = undelegate_snapshot(|_| {}).await;
+    result.unwrap();
+
+    // The delegated PDA is owned by our program again with the committed snapshot
+    let account = context
+        .banks_client
+        .get_account(delegated_ata)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, PROGRAM);
+    assert_eq!(decode::<EphemeralAta>(&account.data).unwrap().amount, 500);
+}
+
+#[tokio::test]
+async fn undelegation_callback_migrates_legacy_ephemeral_ata() {
+    // Delegated before account versioning: owner, mint, amount
+    let (context, delegated_ata, result) = undelegate_data(|owner, mint, _| {
+        [owner.as_ref(), mint.as_ref(), &500u64.to_le_bytes()].concat()
+    })
+    .await;
+    result.unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(delegated_ata)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, PROGRAM);
+    assert_eq!(account.data.len(), EphemeralAta::LEN);
+    let ephemeral_ata = decode::<EphemeralAta>(&account.data).unwrap();
+    assert_eq!(
+        Pubkey::find_program_address(
+            &[ephemeral_ata.owner.as_ref(), ephemeral_ata.mint.as_ref()],
+            &PROGRAM
+        ),
+        (delegated_ata, ephemeral_ata.header.bump)
+    );
+    assert_eq!(ephemeral_ata.amount, 500);
+}
+
+#[tokio::test]
+async fn undelegation_callback_rejects_snapshot_of_another_mint() {
+    let (context, delegated_ata, result) = undelegate_snapshot(|ephemeral_ata| {
+        ephemeral_ata.mint = Pubkey::new_unique();
+    })
+    .await;
+    assert!(result.is_err());
+
+    // The Ephemeral ATA stays delegated
+    let account = context
+        .banks_client
+        .get_account(delegated_ata)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, DELEGATION_PROGRAM_ID);
+}
 
-    // Assert the delegated PDA now exists, is owned by our program, and has data equal to buffer (zeros)
+#[tokio::test]
+async fn undelegation_callback_rejects_snapshot_that_is_not_an_ephemeral_ata() {
+    let (_, _, result) = undelegate_snapshot(|ephemeral_ata| {
+        ephemeral_ata.header.discriminator = 0;
+    })
+    .await;
+    assert!(result.is_err());
+}
+
+/// Call the undelegation callback directly, as anyone but the delegation program could.
+async fn call_callback(
+    data: Vec<u8>,
+    buffer: Pubkey,
+    buffer_account: Option<(&Keypair, Pubkey)>,
+) -> TransactionError {
+    let mut pt = ProgramTest::new("ephemeral_token_program", PROGRAM, None);
+    pt.prefer_bpf(true);
+    if let Some((_, owner)) = buffer_account {
+        pt.add_account(
+            buffer,
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                data: vec![0; EphemeralAta::LEN],
+                owner,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+    let context = pt.start_with_context().await;
+
+    let payer = context.payer.pubkey();
+    let (delegated_ata, _) = pda::find_ephemeral_ata(&payer, &Pubkey::new_unique());
+    let ix = Instruction {
+        program_id: PROGRAM,
+        accounts: vec![
+            AccountMeta::new(delegated_ata, false),
+            AccountMeta::new(buffer, buffer_account.is_some()),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    };
+
+    let mut signers = vec![&context.payer];
+    if let Some((buffer_kp, _)) = buffer_account {
+        signers.push(buffer_kp);
+    }
+    let tx =
+        Transaction::new_signed_with_payer(&[ix], Some(&payer), &signers, context.last_blockhash);
+    context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err()
+        .unwrap()
+}
+
+/// Callback data carrying the seeds `[owner, mint]`.
+fn callback_data(owner: &Pubkey, mint: &Pubkey) -> Vec<u8> {
+    let mut args = 2u32.to_le_bytes().to_vec();
+    for seed in [owner, mint] {
+        args.extend_from_slice(&32u32.to_le_bytes());
+        args.extend_from_slice(seed.as_ref());
+    }
+    let ix = EphemeralSplInstruction::UndelegationCallback { args: &args };
+    let mut data = vec![0; ix.packed_len()];
+    ix.pack(&mut data).unwrap();
+    data
+}
+
+#[tokio::test]
+async fn undelegation_callback_rejects_truncated_data() {
+    let error = call_callback(
+        EXTERNAL_UNDELEGATE_DISCRIMINATOR[..3].to_vec(),
+        Pubkey::new_unique(),
+        None,
+    )
+    .await;
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+}
+
+#[tokio::test]
+async fn undelegation_callback_rejects_unsigned_buffer() {
+    let error = call_callback(
+        callback_data(&Pubkey::new_unique(), &Pubkey::new_unique()),
+        Pubkey::new_unique(),
+        None,
+    )
+    .await;
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn undelegation_callback_rejects_buffer_not_owned_by_delegation_program() {
+    let buffer = Keypair::new();
+    let error = call_callback(
+        callback_data(&Pubkey::new_unique(), &Pubkey::new_unique()),
+        buffer.pubkey(),
+        Some((&buffer, system_program::ID)),
+    )
+    .await;
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::IllegalOwner)
+    );
+}
+
+#[tokio::test]
+async fn undelegation_callback_rejects_forged_buffer() {
+    // A keypair account assigned to the delegation program can sign, but it is
+    // not the undelegate buffer PDA
+    let buffer = Keypair::new();
+    let error = call_callback(
+        callback_data(&Pubkey::new_unique(), &Pubkey::new_unique()),
+        buffer.pubkey(),
+        Some((&buffer, DELEGATION_PROGRAM_ID)),
+    )
+    .await;
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
 }
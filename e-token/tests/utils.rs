@@ -1,6 +1,14 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ephemeral_spl_api::event::EphemeralSplEvent;
+use ephemeral_spl_api::pda;
+use ephemeral_spl_api::state::{
+    ephemeral_ata::EphemeralAta, load_mut_unchecked, AccountHeader, RawType,
+};
+use solana_account::Account;
 use solana_keypair::Keypair;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
 use solana_program_pack::Pack;
-use solana_program_test::ProgramTestContext;
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
 use solana_pubkey::Pubkey;
 use solana_signer::Signer;
 use solana_system_interface::instruction::create_account;
@@ -8,6 +16,22 @@ use solana_transaction::Transaction;
 use spl_token_interface::instruction::{initialize_account, initialize_mint};
 use spl_token_interface::state::{Account as SplAccount, Mint};
 
+#[allow(dead_code)]
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    ephemeral_spl_api::program::ASSOCIATED_TOKEN_PROGRAM_ID;
+
+// Decode the events logged by the program from the transaction logs.
+#[allow(dead_code)]
+pub fn events(log_messages: &[String]) -> Vec<EphemeralSplEvent> {
+    log_messages
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|data| STANDARD.decode(data).ok())
+        .filter_map(|data| EphemeralSplEvent::unpack(&data).ok())
+        .collect()
+}
+
+#[allow(dead_code)]
 pub struct Pdas {
     pub ephemeral_ata: Pubkey,
     pub bump_ata: u8,
@@ -21,12 +45,9 @@ pub struct TokenSetup {
     pub vault_token: Pubkey,
 }
 
-pub fn derive_pdas(program: Pubkey, owner: Pubkey, mint: Pubkey) -> Pdas {
-    let (ephemeral_ata, bump_ata) = Pubkey::find_program_address(
-        &[owner.to_bytes().as_slice(), mint.to_bytes().as_slice()],
-        &program,
-    );
-    let (vault, bump_vault) = Pubkey::find_program_address(&[mint.to_bytes().as_slice()], &program);
+pub fn derive_pdas(owner: Pubkey, mint: Pubkey) -> Pdas {
+    let (ephemeral_ata, bump_ata) = pda::find_ephemeral_ata(&owner, &mint);
+    let (vault, bump_vault) = pda::find_global_vault(&mint);
     Pdas {
         ephemeral_ata,
         bump_ata,
@@ -35,12 +56,63 @@ pub fn derive_pdas(program: Pubkey, owner: Pubkey, mint: Pubkey) -> Pdas {
     }
 }
 
+// Associated token account of the vault PDA, created by InitializeGlobalVault.
+#[allow(dead_code)]
+pub fn vault_ata(vault: Pubkey, mint: Pubkey, token_program: Pubkey) -> Pubkey {
+    pda::find_associated_token_account(&vault, &mint, &token_program).0
+}
+
+// Register a stand-in for the magic program, which only exists on ephemeral rollup
+// validators. It accepts every instruction, so the commit and undelegation CPIs of
+// the program complete. There is no SBF build of it, so it is always native; the
+// test program is still preferred as SBF.
+#[allow(dead_code)]
+pub fn add_magic_program(pt: &mut ProgramTest) {
+    fn process(_program_id: &Pubkey, _accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+        Ok(())
+    }
+    pt.prefer_bpf(false);
+    pt.add_program(
+        "magic_program",
+        ephemeral_spl_client::MAGIC_PROGRAM_ID,
+        processor!(process),
+    );
+    pt.prefer_bpf(true);
+}
+
+// Ephemeral ATA of `owner` for `mint` holding `amount`, owned by `program_owner`,
+// for tests that place it at an address of their choice with `set_account`.
+#[allow(dead_code)]
+pub fn ephemeral_ata_account(
+    owner: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+    program_owner: Pubkey,
+) -> Account {
+    let (_, bump) = pda::find_ephemeral_ata(&owner, &mint);
+    let mut data = vec![0u8; EphemeralAta::LEN];
+    let ephemeral_ata = unsafe { load_mut_unchecked::<EphemeralAta>(&mut data).unwrap() };
+    ephemeral_ata.header = AccountHeader::new::<EphemeralAta>(bump);
+    ephemeral_ata.owner = owner;
+    ephemeral_ata.mint = mint;
+    ephemeral_ata.amount = amount;
+    Account {
+        lamports: solana_program::rent::Rent::default().minimum_balance(data.len()),
+        data,
+        owner: program_owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
 // Creates and initializes:
 // - Mint (with mint authority = payer, freeze authority = payer)
 // - `user_accounts` token accounts owned by `payer`
-// - one vault token account owned by `vault_owner`
 // - mints `starting_balance` to the first user token account
 // Submits a single transaction for all instructions.
+// The returned vault token account is the ATA of `vault_owner`; it is created by
+// InitializeGlobalVault, not here.
+#[allow(dead_code)]
 pub async fn setup_mint_and_token_accounts(
     context: &mut ProgramTestContext,
     payer: Pubkey,
@@ -117,22 +189,6 @@ pub async fn setup_mint_and_token_accounts(
         signers.push(kp);
     }
 
-    // Create vault ata
-    let vault_token_kp = Keypair::new();
-    let vault_token = vault_token_kp.pubkey();
-    instructions.push(create_account(
-        &payer,
-        &vault_token,
-        token_acc_lamports,
-        token_acc_space as u64,
-        &spl_token_interface::ID,
-    ));
-    let mut init_vault_ix =
-        initialize_account(&spl_token_interface::ID, &vault_token, &mint, &vault_owner).unwrap();
-    init_vault_ix.program_id = spl_token_interface::ID;
-    instructions.push(init_vault_ix);
-    signers.push(&vault_token_kp);
-
     // Mint starting balance to first user token
     let first_user = user_tokens[0];
     let mut mint_to_ix = spl_token_interface::instruction::mint_to(
@@ -159,6 +215,6 @@ pub async fn setup_mint_and_token_accounts(
 
     TokenSetup {
         user_tokens,
-        vault_token,
+        vault_token: vault_ata(vault_owner, mint, spl_token_interface::ID),
     }
 }
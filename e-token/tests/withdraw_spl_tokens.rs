@@ -1,8 +1,10 @@
-use ephemeral_spl_api::instruction;
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::event::{EphemeralSplEvent, EventKind};
 use ephemeral_spl_api::program::ID;
 use ephemeral_spl_api::state::ephemeral_ata::EphemeralAta;
 use ephemeral_spl_api::state::{load_mut_unchecked, RawType};
-use solana_instruction::{AccountMeta, Instruction};
+use ephemeral_spl_client as client;
+use solana_instruction::error::InstructionError;
 use solana_keypair::Keypair;
 use solana_program_pack::Pack;
 use spl_token_interface::state::Account;
@@ -10,7 +12,7 @@ use {
     solana_program_test::{tokio, ProgramTest},
     solana_pubkey::Pubkey,
     solana_signer::Signer,
-    solana_transaction::Transaction,
+    solana_transaction::{Transaction, TransactionError},
 };
 
 mod utils;
@@ -33,7 +35,7 @@ async fn withdraw_spl_tokens_decrements_ephemeral_amount() {
     let mint = mint_kp.pubkey();
 
     // Derive PDAs and setup mint/accounts via utils
-    let pdas = utils::derive_pdas(PROGRAM, user, mint);
+    let pdas = utils::derive_pdas(user, mint);
     let setup = utils::setup_mint_and_token_accounts(
         &mut context,
         payer,
@@ -46,37 +48,13 @@ async fn withdraw_spl_tokens_decrements_ephemeral_amount() {
     .await;
 
     let ephemeral_ata = pdas.ephemeral_ata;
-    let bump_ata = pdas.bump_ata;
-    let vault = pdas.vault;
-    let bump_vault = pdas.bump_vault;
     let user_source = setup.user_tokens[0];
     let user_dest = setup.user_tokens[1];
     let vault_token = setup.vault_token;
 
-    // Initialize Ephemeral ATA
-    let ix_init_ata = Instruction {
-        program_id: PROGRAM,
-        accounts: vec![
-            AccountMeta::new(ephemeral_ata, false),
-            AccountMeta::new_readonly(payer, false),
-            AccountMeta::new_readonly(user, false),
-            AccountMeta::new_readonly(mint, false),
-            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
-        ],
-        data: vec![instruction::INITIALIZE_EPHEMERAL_ATA, bump_ata],
-    };
-
-    // Initialize Global Vault
-    let ix_init_vault = Instruction {
-        program_id: PROGRAM,
-        accounts: vec![
-            AccountMeta::new(vault, false),
-            AccountMeta::new_readonly(payer, false),
-            AccountMeta::new_readonly(mint, false),
-            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
-        ],
-        data: vec![instruction::INITIALIZE_GLOBAL_VAULT, bump_vault],
-    };
+    let token_program = spl_token_interface::ID;
+    let ix_init_ata = client::initialize_ephemeral_ata(&payer, &user, &mint);
+    let ix_init_vault = client::initialize_global_vault(&payer, &mint, &token_program);
 
     let tx_init = Transaction::new_signed_with_payer(
         &[ix_init_ata, ix_init_vault],
@@ -92,21 +70,14 @@ async fn withdraw_spl_tokens_decrements_ephemeral_amount() {
 
     // Deposit first to fund the vault and set ephemeral amount
     let deposit_amount: u64 = 1_000 * 10u64.pow(DECIMALS as u32);
-    let mut deposit_data = vec![instruction::DEPOSIT_SPL_TOKENS];
-    deposit_data.extend_from_slice(&deposit_amount.to_le_bytes());
-    let ix_deposit = Instruction {
-        program_id: PROGRAM,
-        accounts: vec![
-            AccountMeta::new(ephemeral_ata, false),
-            AccountMeta::new_readonly(vault, false),
-            AccountMeta::new_readonly(mint, false),
-            AccountMeta::new(user_source, false),
-            AccountMeta::new(vault_token, false),
-            AccountMeta::new_readonly(payer, true),
-            AccountMeta::new_readonly(spl_token_interface::ID, false),
-        ],
-        data: deposit_data,
-    };
+    let ix_deposit = client::deposit_spl_tokens(
+        &user,
+        &payer,
+        &mint,
+        &user_source,
+        &token_program,
+        deposit_amount,
+    );
     let tx_deposit = Transaction::new_signed_with_payer(
         &[ix_deposit],
         Some(&payer),
@@ -121,23 +92,8 @@ async fn withdraw_spl_tokens_decrements_ephemeral_amount() {
 
     // Now withdraw a portion
     let withdraw_amount: u64 = 400 * 10u64.pow(DECIMALS as u32);
-    let mut withdraw_data = vec![instruction::WITHDRAW_SPL_TOKENS];
-    withdraw_data.extend_from_slice(&withdraw_amount.to_le_bytes());
-    withdraw_data.push(bump_vault); // provide vault bump for PDA signing
-
-    let ix_withdraw = Instruction {
-        program_id: PROGRAM,
-        accounts: vec![
-            AccountMeta::new_readonly(payer, true),  // [writable]
-            AccountMeta::new(ephemeral_ata, false),  // [writable]
-            AccountMeta::new_readonly(vault, false), // [] vault data
-            AccountMeta::new_readonly(mint, false),  // [] mint
-            AccountMeta::new(vault_token, false),    // [writable] source (vault)
-            AccountMeta::new(user_dest, false),      // [writable] destination (user)
-            AccountMeta::new_readonly(spl_token_interface::ID, false), // [] token program
-        ],
-        data: withdraw_data,
-    };
+    let ix_withdraw =
+        client::withdraw_spl_tokens(&user, &mint, &user_dest, &token_program, withdraw_amount);
 
     let tx_withdraw = Transaction::new_signed_with_payer(
         &[ix_withdraw],
@@ -145,11 +101,23 @@ async fn withdraw_spl_tokens_decrements_ephemeral_amount() {
         &[&context.payer],
         context.last_blockhash,
     );
-    context
+    let result = context
         .banks_client
-        .process_transaction(tx_withdraw)
+        .process_transaction_with_metadata(tx_withdraw)
         .await
         .unwrap();
+    result.result.unwrap();
+    assert_eq!(
+        utils::events(&result.metadata.unwrap().log_messages),
+        vec![EphemeralSplEvent {
+            kind: EventKind::Withdraw,
+            ephemeral_ata,
+            mint,
+            owner: user,
+            amount: withdraw_amount,
+            post_balance: deposit_amount - withdraw_amount,
+        }]
+    );
 
     // Check SPL balances
     let vault_after = context
@@ -185,3 +153,78 @@ async fn withdraw_spl_tokens_decrements_ephemeral_amount() {
     let ata_data = unsafe { load_mut_unchecked::<EphemeralAta>(mut_acc.as_mut_slice()).unwrap() };
     assert_eq!(ata_data.amount, deposit_amount - withdraw_amount);
 }
+
+#[tokio::test]
+async fn withdraw_spl_tokens_rejects_balance_above_vault_deposits() {
+    let mut context = ProgramTest::new("ephemeral_token_program", PROGRAM, None)
+        .start_with_context()
+        .await;
+
+    let payer = context.payer.pubkey();
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+
+    let pdas = utils::derive_pdas(payer, mint);
+    let setup = utils::setup_mint_and_token_accounts(
+        &mut context,
+        payer,
+        &mint_kp,
+        pdas.vault,
+        DECIMALS,
+        STARTING_BALANCE,
+        1,
+    )
+    .await;
+    let token_program = spl_token_interface::ID;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            client::initialize_ephemeral_ata(&payer, &payer, &mint),
+            client::initialize_global_vault(&payer, &mint, &token_program),
+            client::deposit_spl_tokens(
+                &payer,
+                &payer,
+                &mint,
+                &setup.user_tokens[0],
+                &token_program,
+                100,
+            ),
+        ],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // A balance restored from the ephemeral rollup larger than the vault deposits
+    context.set_account(
+        &pdas.ephemeral_ata,
+        &utils::ephemeral_ata_account(payer, mint, 101, PROGRAM).into(),
+    );
+
+    // Even a withdrawal the vault could cover is rejected
+    let tx = Transaction::new_signed_with_payer(
+        &[client::withdraw_spl_tokens(
+            &payer,
+            &mint,
+            &setup.user_tokens[0],
+            &token_program,
+            1,
+        )],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(EphemeralSplError::ExceedsVaultDeposits as u32)
+        )
+    );
+}
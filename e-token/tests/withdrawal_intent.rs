@@ -0,0 +1,324 @@
+use ephemeral_spl_api::error::EphemeralSplError;
+use ephemeral_spl_api::event::EventKind;
+use ephemeral_spl_api::pda;
+use ephemeral_spl_api::program::{DELEGATION_PROGRAM_ID, ID};
+use ephemeral_spl_api::state::{
+    decode, ephemeral_ata::EphemeralAta, global_vault::GlobalVault, load_mut_unchecked,
+    withdrawal_intent::WithdrawalIntent, AccountHeader, RawType,
+};
+use ephemeral_spl_client as client;
+use solana_account::Account;
+use solana_instruction::error::InstructionError;
+use solana_keypair::Keypair;
+use solana_program::bpf_loader;
+use solana_program::rent::Rent;
+use solana_program_pack::Pack;
+use solana_program_test::{read_file, tokio, ProgramTest, ProgramTestContext};
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+use solana_transaction::{Transaction, TransactionError};
+use spl_token_interface::state::Account as SplAccount;
+
+mod utils;
+
+pub const PROGRAM: Pubkey = Pubkey::new_from_array(ID);
+
+/// Start a test with the delegation program, a new mint and 300 tokens deposited in
+/// the Ephemeral ATA of the context payer. Return the mint and a second token
+/// account of the payer.
+async fn start() -> (ProgramTestContext, Pubkey, Pubkey) {
+    let mut pt = ProgramTest::new("ephemeral_token_program", PROGRAM, None);
+    pt.prefer_bpf(true);
+
+    let data = read_file("tests/fixtures/dlp.so");
+    pt.add_account(
+        DELEGATION_PROGRAM_ID,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()).max(1),
+            data,
+            owner: bpf_loader::id(),
+            executable: true,
+            rent_epoch: 0,
+        },
+    );
+
+    utils::add_magic_program(&mut pt);
+
+    let mut context = pt.start_with_context().await;
+
+    let owner = context.payer.pubkey();
+    let mint_kp = Keypair::new();
+    let mint = mint_kp.pubkey();
+    let pdas = utils::derive_pdas(owner, mint);
+    let setup = utils::setup_mint_and_token_accounts(
+        &mut context,
+        owner,
+        &mint_kp,
+        pdas.vault,
+        6,
+        1_000,
+        2,
+    )
+    .await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            client::initialize_ephemeral_ata(&owner, &owner, &mint),
+            client::initialize_global_vault(&owner, &mint, &spl_token_interface::ID),
+            client::deposit_spl_tokens(
+                &owner,
+                &owner,
+                &mint,
+                &setup.user_tokens[0],
+                &spl_token_interface::ID,
+                300,
+            ),
+        ],
+        Some(&owner),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    (context, mint, setup.user_tokens[1])
+}
+
+async fn account(context: &mut ProgramTestContext, address: Pubkey) -> Account {
+    context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+/// Place a withdrawal intent of `amount` for `ephemeral_ata` owned by the program,
+/// as restored from the ephemeral rollup or as seen inside it. `owner` and `mint` are
+/// stored as given.
+fn set_withdrawal_intent(
+    context: &mut ProgramTestContext,
+    ephemeral_ata: Pubkey,
+    owner: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let (address, bump) = pda::find_withdrawal_intent(&ephemeral_ata);
+    let mut data = vec![0u8; WithdrawalIntent::LEN];
+    let withdrawal_intent = unsafe { load_mut_unchecked::<WithdrawalIntent>(&mut data).unwrap() };
+    withdrawal_intent.header = AccountHeader::new::<WithdrawalIntent>(bump);
+    withdrawal_intent.ephemeral_ata = ephemeral_ata;
+    withdrawal_intent.owner = owner;
+    withdrawal_intent.mint = mint;
+    withdrawal_intent.amount = amount;
+    context.set_account(
+        &address,
+        &Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: PROGRAM,
+            executable: false,
+            rent_epoch: 0,
+        }
+        .into(),
+    );
+    address
+}
+
+async fn settle(
+    context: &mut ProgramTestContext,
+    owner: Pubkey,
+    mint: Pubkey,
+    destination: Pubkey,
+) -> Result<(), TransactionError> {
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[client::settle_withdrawal_intent(
+            &owner,
+            &mint,
+            &destination,
+            &spl_token_interface::ID,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+#[tokio::test]
+async fn delegate_withdrawal_intent_creates_and_delegates() {
+    let (mut context, mint, destination) = start().await;
+    let owner = context.payer.pubkey();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[client::delegate_withdrawal_intent(
+            &owner, &owner, &mint, None, None,
+        )],
+        Some(&owner),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let ephemeral_ata = utils::derive_pdas(owner, mint).ephemeral_ata;
+    let (address, bump) = pda::find_withdrawal_intent(&ephemeral_ata);
+    let intent_account = account(&mut context, address).await;
+    assert_eq!(intent_account.owner, DELEGATION_PROGRAM_ID);
+    let withdrawal_intent = decode::<WithdrawalIntent>(&intent_account.data).unwrap();
+    assert_eq!(withdrawal_intent.header.bump, bump);
+    assert_eq!(withdrawal_intent.ephemeral_ata, ephemeral_ata);
+    assert_eq!(withdrawal_intent.owner, owner);
+    assert_eq!(withdrawal_intent.mint, mint);
+    assert_eq!(withdrawal_intent.amount, 0);
+
+    // A delegated intent is not settled from its stale base layer state
+    assert_eq!(
+        settle(&mut context, owner, mint, destination).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::IllegalOwner
+        ))
+    );
+}
+
+#[tokio::test]
+async fn settle_withdrawal_intent_pays_the_owner_once() {
+    let (mut context, mint, destination) = start().await;
+    let owner = context.payer.pubkey();
+    let pdas = utils::derive_pdas(owner, mint);
+
+    // Simulate an intent of 120 restored from the ephemeral rollup
+    let address = set_withdrawal_intent(&mut context, pdas.ephemeral_ata, owner, mint, 120);
+
+    settle(&mut context, owner, mint, destination)
+        .await
+        .unwrap();
+
+    let intent_account = account(&mut context, address).await;
+    assert_eq!(
+        decode::<WithdrawalIntent>(&intent_account.data)
+            .unwrap()
+            .amount,
+        0
+    );
+    let vault = decode::<GlobalVault>(&account(&mut context, pdas.vault).await.data).unwrap();
+    assert_eq!(vault.total_deposited, 180);
+    let destination_account =
+        SplAccount::unpack(&account(&mut context, destination).await.data).unwrap();
+    assert_eq!(destination_account.amount, 120);
+
+    // The intent cannot be settled twice
+    assert_eq!(
+        settle(&mut context, owner, mint, destination).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(EphemeralSplError::NoWithdrawalIntent as u32)
+        ))
+    );
+}
+
+#[tokio::test]
+async fn settle_withdrawal_intent_rejects_tampered_owner_or_mint() {
+    let (mut context, mint, destination) = start().await;
+    let owner = context.payer.pubkey();
+    let pdas = utils::derive_pdas(owner, mint);
+    let attacker = Pubkey::new_unique();
+
+    // The intent of the owner's Ephemeral ATA, restored with another owner
+    set_withdrawal_intent(&mut context, pdas.ephemeral_ata, attacker, mint, 120);
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut ix =
+        client::settle_withdrawal_intent(&owner, &mint, &destination, &spl_token_interface::ID);
+    ix.accounts[1].pubkey = attacker;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&owner), &[&context.payer], blockhash);
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+
+    // ... or with another mint
+    set_withdrawal_intent(
+        &mut context,
+        pdas.ephemeral_ata,
+        owner,
+        Pubkey::new_unique(),
+        120,
+    );
+    assert_eq!(
+        settle(&mut context, owner, mint, destination).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidSeeds
+        ))
+    );
+
+    let vault = decode::<GlobalVault>(&account(&mut context, pdas.vault).await.data).unwrap();
+    assert_eq!(vault.total_deposited, 300);
+}
+
+#[tokio::test]
+async fn create_withdrawal_intent_moves_the_amount_to_the_intent() {
+    let (mut context, mint, _) = start().await;
+    let owner = context.payer.pubkey();
+    let pdas = utils::derive_pdas(owner, mint);
+
+    // The Ephemeral ATA and its delegated intent as seen in the ephemeral rollup
+    let address = set_withdrawal_intent(&mut context, pdas.ephemeral_ata, owner, mint, 0);
+
+    let tx = Transaction::new_signed_with_payer(
+        &[client::create_withdrawal_intent(&owner, &mint, 120)],
+        Some(&owner),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let result = context
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await
+        .unwrap();
+    result.result.unwrap();
+
+    let events = utils::events(&result.metadata.unwrap().log_messages);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, EventKind::WithdrawalIntent);
+    assert_eq!(events[0].ephemeral_ata, pdas.ephemeral_ata);
+    assert_eq!(events[0].amount, 120);
+    assert_eq!(events[0].post_balance, 180);
+
+    let ephemeral_ata =
+        decode::<EphemeralAta>(&account(&mut context, pdas.ephemeral_ata).await.data).unwrap();
+    assert_eq!(ephemeral_ata.amount, 180);
+    let withdrawal_intent =
+        decode::<WithdrawalIntent>(&account(&mut context, address).await.data).unwrap();
+    assert_eq!(withdrawal_intent.amount, 120);
+
+    // The intent cannot take more than the balance
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[client::create_withdrawal_intent(&owner, &mint, 181)],
+        Some(&owner),
+        &[&context.payer],
+        blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(EphemeralSplError::InsufficientFunds as u32)
+        )
+    );
+}